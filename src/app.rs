@@ -0,0 +1,462 @@
+// src/app.rs
+use crate::cli::{Cli, Frontend};
+use crate::hardware;
+use crate::holotape::{self, HolotapeWatch};
+use crate::input::bindings::InputBindings;
+use crate::input::{InputEvent, InputManager, SCREEN_HEIGHT, SCREEN_WIDTH};
+use crate::settings::{Section, Settings, SettingsChange};
+use crate::ui::global_renderer::{GlobalRenderer, Palette};
+use crate::ui::navigator::Navigator;
+use crate::ui::open_with;
+use crate::ui::screen::ScreenContext;
+use crate::ui::welcome::WelcomeScreen;
+use anyhow::{Context, Result};
+use std::sync::mpsc::Receiver;
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+const FRAME: Duration = Duration::from_millis(16);
+/// How often input is checked for the home action while an app is in front.
+const FOREGROUND_POLL: Duration = Duration::from_millis(50);
+/// How long a popup stays up.
+const POPUP_DURATION: Duration = Duration::from_secs(3);
+const HOLOTAPE_MESSAGE: &str = "Holotape Detected!";
+
+// The holotape popup's Open and Dismiss buttons, as drawn by
+// `GlobalRenderer::render_notification`
+const OPEN_BUTTON: (i32, i32) = (400, 500);
+const DISMISS_BUTTON: (i32, i32) = (510, 610);
+const BUTTONS_Y: (i32, i32) = (60, 100);
+
+/// Sets up input, settings and profiles, then runs the shell on the chosen
+/// frontend until it is asked to quit.
+pub fn run(cli: Cli) -> Result<()> {
+    let profiles = cli.profile_store();
+    log::info!("Profile: {}", profiles.active().name);
+
+    let sdl_context = sdl2::init()
+        .map_err(anyhow::Error::msg)
+        .context("initialising SDL")?;
+    let bindings =
+        InputBindings::load_or_default(cli.bindings_path()).context("loading input bindings")?;
+    let mut input = InputManager::new(&sdl_context, &bindings, &cli.calibration_path(), cli.dev);
+    if let Some(path) = &cli.record {
+        input.start_recording(path).context("starting recording")?;
+    }
+    if let Some(path) = &cli.replay {
+        input
+            .start_replay(path, cli.replay_speed)
+            .context("starting replay")?;
+    }
+
+    let mut ctx = ScreenContext::new(cli, profiles, input);
+    log::debug!("Settings from {}", ctx.settings.path().display());
+    let mut shell = Shell::new(&mut ctx, bindings.foreground_home_events());
+
+    match ctx.cli.frontend {
+        Frontend::Window => {
+            let video = sdl_context
+                .video()
+                .map_err(anyhow::Error::msg)
+                .context("initialising SDL video")?;
+            let ttf_context = sdl2::ttf::init().context("initialising SDL_ttf")?;
+
+            let resolution = ctx.cli.resolution;
+            let mut window = video.window("PipBoy", resolution.width, resolution.height);
+            if ctx.cli.is_fullscreen() {
+                window.fullscreen_desktop();
+            } else {
+                window.position_centered();
+            }
+            let window = window
+                .build()
+                .with_context(|| format!("opening a {} window", resolution))?;
+            let (width, height) = window.size();
+            ctx.input.touchscreen.set_window_size(width, height);
+            let mut canvas = window
+                .into_canvas()
+                .build()
+                .context("creating the canvas")?;
+            canvas
+                .set_logical_size(SCREEN_WIDTH, SCREEN_HEIGHT)
+                .context("scaling the canvas")?;
+            let texture_creator = canvas.texture_creator();
+            let font_path = ctx.cli.assets.join("font.ttf");
+            let font = ttf_context
+                .load_font(&font_path, 24)
+                .map_err(anyhow::Error::msg)
+                .with_context(|| format!("loading {}", font_path.display()))?;
+
+            let mut renderer = GlobalRenderer {
+                canvas,
+                texture_creator: &texture_creator,
+                font,
+                asset_dir: ctx.cli.assets.clone(),
+                user_name: ctx.profiles.active().name.clone(),
+                palette: Palette::for_theme(ctx.settings.get().display.theme),
+                show_popup: false,
+                popup_selection: None,
+            };
+            shell.run(&mut ctx, Some(&mut renderer));
+        }
+        Frontend::Headless => shell.run(&mut ctx, None),
+    }
+    Ok(())
+}
+
+/// The main loop's state: the screen stack plus the popup drawn over it.
+struct Shell {
+    navigator: Navigator,
+    popup: Option<String>,
+    popup_until: Option<Instant>,
+    holotape: HolotapeWatch,
+    /// Inputs that take the screen back from a launched app.
+    home_events: Vec<InputEvent>,
+    settings_changes: Receiver<SettingsChange>,
+    /// The `ScreenContext::settings_generation` subscribed to.
+    settings_generation: u32,
+    /// Set while a launched app is in front and the shell neither draws nor
+    /// handles input.
+    paused: bool,
+    blanking: Blanking,
+}
+
+impl Shell {
+    fn new(ctx: &mut ScreenContext, home_events: Vec<InputEvent>) -> Self {
+        let mut navigator = Navigator::new(Box::new(WelcomeScreen), ctx);
+        if let Some(screen) = ctx.cli.screen.screen(ctx) {
+            navigator.push(screen, ctx);
+        }
+        Self {
+            navigator,
+            popup: None,
+            popup_until: None,
+            holotape: HolotapeWatch::default(),
+            home_events,
+            settings_changes: ctx.settings.subscribe(),
+            settings_generation: ctx.settings_generation,
+            paused: false,
+            blanking: Blanking::new(Instant::now()),
+        }
+    }
+
+    fn run(&mut self, ctx: &mut ScreenContext, mut renderer: Option<&mut GlobalRenderer>) {
+        apply_settings(
+            ctx.settings.get(),
+            &[Section::Display, Section::Sound],
+            None,
+        );
+        let mut last_frame = Instant::now();
+        'frames: loop {
+            for exited in ctx.supervisor.reap() {
+                if !exited.ok() {
+                    ctx.notify(format!("{} quit ({})", exited.name, exited.describe()));
+                }
+            }
+
+            // A launched app owns the screen: keep the encoders and buttons
+            // from steering the hidden menus, and stop drawing under it
+            if let Some(name) = ctx.supervisor.foreground().map(|app| app.name.clone()) {
+                if !self.paused {
+                    log::info!("{} is in front; pausing the shell", name);
+                    self.paused = true;
+                }
+                for event in ctx.input.poll_inputs() {
+                    if let InputEvent::Quit = event {
+                        break 'frames;
+                    }
+                    if self.home_events.contains(&event) {
+                        log::info!("Taking the screen back from {}", name);
+                        ctx.supervisor.release_foreground();
+                        break;
+                    }
+                }
+                sleep(FOREGROUND_POLL);
+                continue;
+            }
+            if self.paused {
+                self.paused = false;
+                log::info!("Shell back in front");
+                if let Some(renderer) = renderer.as_deref_mut() {
+                    renderer.raise();
+                }
+                last_frame = Instant::now();
+                self.blanking.last_input = last_frame;
+            }
+
+            for event in ctx.input.poll_inputs() {
+                if let InputEvent::Quit = event {
+                    break 'frames;
+                }
+                // The input that wakes the screen does nothing else
+                if !self.wake(Instant::now()) {
+                    self.handle(event, ctx);
+                }
+            }
+            for change in self.settings_changes(ctx) {
+                apply_settings(&change.settings, &change.sections, renderer.as_deref_mut());
+            }
+
+            let now = Instant::now();
+            self.navigator.update(ctx, now - last_frame);
+            last_frame = now;
+            self.update_popup(ctx, now);
+            self.blank_if_idle(ctx, now);
+
+            if let Some(renderer) = renderer.as_deref_mut() {
+                // A rename keeps the same profile, so check the greeting every frame
+                if renderer.user_name != ctx.profiles.active().name {
+                    renderer.user_name = ctx.profiles.active().name.clone();
+                }
+                renderer.show_popup = self.popup.is_some();
+                renderer.popup_selection = self.popup.clone();
+                self.navigator.render(renderer);
+            }
+
+            sleep(FRAME);
+        }
+        self.wake(Instant::now());
+    }
+
+    /// Turns the backlight off once there has been no input for the screen
+    /// timeout.
+    fn blank_if_idle(&mut self, ctx: &ScreenContext, now: Instant) {
+        let timeout = ctx.settings.get().display.screen_timeout_secs;
+        if !self.blanking.due(timeout, now) {
+            return;
+        }
+        match hardware::set_backlight_power(false) {
+            Ok(0) => log::debug!("No backlight to turn off"),
+            Ok(_) => {
+                log::info!("No input for {} s; screen off", timeout);
+                self.blanking.blanked = true;
+                return;
+            }
+            Err(e) => log::warn!("Cannot turn the screen off: {:#}", e),
+        }
+        // Try again after another timeout rather than every frame
+        self.blanking.last_input = now;
+    }
+
+    /// Notes input, turning the backlight back on if it was off. Returns
+    /// whether it was.
+    fn wake(&mut self, now: Instant) -> bool {
+        if !self.blanking.input(now) {
+            return false;
+        }
+        log::info!("Screen on");
+        if let Err(e) = hardware::set_backlight_power(true) {
+            log::warn!("Cannot turn the screen on: {:#}", e);
+        }
+        true
+    }
+
+    /// Settings saved since the last frame. A profile switch replaces the
+    /// store, so the shell subscribes to the new one and takes its display
+    /// and sound settings whole.
+    fn settings_changes(&mut self, ctx: &mut ScreenContext) -> Vec<SettingsChange> {
+        let mut changes: Vec<SettingsChange> = self.settings_changes.try_iter().collect();
+        if self.settings_generation != ctx.settings_generation {
+            self.settings_generation = ctx.settings_generation;
+            self.settings_changes = ctx.settings.subscribe();
+            changes.push(SettingsChange {
+                sections: vec![Section::Display, Section::Sound],
+                settings: ctx.settings.get().clone(),
+            });
+        }
+        changes
+    }
+
+    fn handle(&mut self, event: InputEvent, ctx: &mut ScreenContext) {
+        match &event {
+            InputEvent::DeviceConnected { name, kind } => {
+                log::info!("{} connected: {}", kind, name);
+                ctx.notify(format!("{} connected", kind));
+                return;
+            }
+            InputEvent::DeviceDisconnected { name, kind } => {
+                log::info!("{} disconnected: {}", kind, name);
+                ctx.notify(format!("{} disconnected", kind));
+                return;
+            }
+            _ => {}
+        }
+
+        // The holotape prompt takes input until it is answered or times out
+        if self.popup.as_deref() == Some(HOLOTAPE_MESSAGE) {
+            match event {
+                InputEvent::Select | InputEvent::EncoderPressed(_) => {
+                    self.close_popup();
+                    self.open_holotape(ctx);
+                }
+                InputEvent::Back => self.close_popup(),
+                InputEvent::Touch(x, y) if (BUTTONS_Y.0..=BUTTONS_Y.1).contains(&y) => {
+                    if (OPEN_BUTTON.0..=OPEN_BUTTON.1).contains(&x) {
+                        self.close_popup();
+                        self.open_holotape(ctx);
+                    } else if (DISMISS_BUTTON.0..=DISMISS_BUTTON.1).contains(&x) {
+                        self.close_popup();
+                    }
+                }
+                _ => {}
+            }
+            return;
+        }
+
+        self.navigator.handle(&event, ctx);
+    }
+
+    /// Opens the drive's top folder with whatever handles folders.
+    fn open_holotape(&mut self, ctx: &mut ScreenContext) {
+        match holotape::mount_points().into_iter().next() {
+            Some(root) => {
+                log::info!("Opening holotape at {}", root.display());
+                let transition = open_with::open(&root, ctx);
+                self.navigator.apply(transition, ctx);
+            }
+            None => {
+                log::warn!("Holotape detected but not mounted");
+                ctx.notify("Holotape is not mounted");
+            }
+        }
+    }
+
+    fn update_popup(&mut self, ctx: &mut ScreenContext, now: Instant) {
+        if self.holotape.poll(now) && ctx.settings.get().storage.usb_popup {
+            ctx.notify(HOLOTAPE_MESSAGE);
+        }
+        // Only the newest notice is worth showing
+        if let Some(message) = ctx.notices.pop() {
+            ctx.notices.clear();
+            log::debug!("Popup: {}", message);
+            self.popup = Some(message);
+            self.popup_until = Some(now + POPUP_DURATION);
+        }
+        if self.popup_until.is_some_and(|until| now >= until) {
+            self.close_popup();
+        }
+    }
+
+    fn close_popup(&mut self) {
+        self.popup = None;
+        self.popup_until = None;
+    }
+}
+
+/// When the backlight goes off for want of input.
+struct Blanking {
+    last_input: Instant,
+    blanked: bool,
+}
+
+impl Blanking {
+    fn new(now: Instant) -> Self {
+        Self {
+            last_input: now,
+            blanked: false,
+        }
+    }
+
+    /// Notes input at `now`. Returns whether the screen was blanked, which
+    /// it no longer is.
+    fn input(&mut self, now: Instant) -> bool {
+        self.last_input = now;
+        std::mem::take(&mut self.blanked)
+    }
+
+    /// Whether the screen is lit but has had no input for `timeout_secs`;
+    /// 0 never blanks.
+    fn due(&self, timeout_secs: u32, now: Instant) -> bool {
+        !self.blanked
+            && timeout_secs > 0
+            && now - self.last_input >= Duration::from_secs(timeout_secs.into())
+    }
+}
+
+/// Carries saved settings over to the backlight, the mixer and the palette.
+fn apply_settings(
+    settings: &Settings,
+    sections: &[Section],
+    renderer: Option<&mut GlobalRenderer>,
+) {
+    if sections.contains(&Section::Display) {
+        if let Some(renderer) = renderer {
+            renderer.palette = Palette::for_theme(settings.display.theme);
+        }
+        match hardware::set_brightness(settings.display.brightness) {
+            Ok(0) => log::debug!("No backlight to set"),
+            Ok(_) => {}
+            Err(e) => log::warn!("Cannot set brightness: {:#}", e),
+        }
+    }
+    if sections.contains(&Section::Sound) {
+        if let Err(e) = hardware::set_volume(settings.sound.volume, settings.sound.muted) {
+            log::warn!("Cannot set volume: {:#}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::profiles::ProfileStore;
+    use crate::settings::Theme;
+    use crate::ui::profiles::ProfileMenu;
+    use clap::Parser;
+
+    #[test]
+    fn settings_reach_the_shell_after_a_profile_switch() {
+        let dir = std::env::temp_dir().join(format!("kips_ui-shell-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let cli = Cli::parse_from(["kips_ui".as_ref(), "--config".as_ref(), dir.as_os_str()]);
+        let input = InputManager::from_sources(
+            &InputBindings::default(),
+            Vec::new(),
+            &cli.calibration_path(),
+        );
+        let mut profiles = ProfileStore::open(dir.join("profiles"));
+        let second = profiles.create("Second").unwrap();
+        let mut ctx = ScreenContext::new(cli, profiles, input);
+        let mut shell = Shell::new(&mut ctx, Vec::new());
+
+        // Open the second profile and use it
+        let menu = ProfileMenu::new(&ctx.profiles, &ctx.cli.assets);
+        shell.navigator.push(Box::new(menu), &mut ctx);
+        for event in [
+            InputEvent::NavigateDown,
+            InputEvent::Select,
+            InputEvent::Select,
+        ] {
+            shell.handle(event, &mut ctx);
+        }
+        assert_eq!(ctx.profiles.active().id, second);
+        let changes = shell.settings_changes(&mut ctx);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].sections, [Section::Display, Section::Sound]);
+
+        ctx.settings
+            .update(|settings| settings.display.theme = Theme::Amber)
+            .unwrap();
+        let changes = shell.settings_changes(&mut ctx);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].sections, [Section::Display]);
+        assert_eq!(changes[0].settings.display.theme, Theme::Amber);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+    #[test]
+    fn the_screen_blanks_after_the_timeout_until_the_next_input() {
+        let start = Instant::now();
+        let mut blanking = Blanking::new(start);
+        assert!(!blanking.due(30, start + Duration::from_secs(29)));
+        assert!(blanking.due(30, start + Duration::from_secs(30)));
+        assert!(!blanking.due(0, start + Duration::from_secs(3600)));
+
+        blanking.blanked = true;
+        assert!(!blanking.due(30, start + Duration::from_secs(60)));
+        // Only the first input after blanking is the waking one
+        assert!(blanking.input(start + Duration::from_secs(61)));
+        assert!(!blanking.input(start + Duration::from_secs(62)));
+        assert!(!blanking.due(30, start + Duration::from_secs(91)));
+        assert!(blanking.due(30, start + Duration::from_secs(92)));
+    }
+}
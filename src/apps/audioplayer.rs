@@ -1,22 +1,26 @@
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use cpal::{Sample, SampleFormat, StreamConfig};
-use hound;
+use crate::launcher::AUDIO_OUTPUT_ENV;
 use rfd::FileDialog;
 use std::error::Error;
 use std::fs::File;
+use std::path::Path;
 use std::sync::{Arc, Mutex, atomic::{AtomicBool, Ordering}};
 use std::{thread, time::Duration};
 
-fn main () -> Result<(), Box <dyn Error>> {
-
-    //Launches a file dialog to let the user pick a file.
-    let file_path = FileDialog::new().pick_file();
-    let file_path = match file_path {
-        Some(path) => path,
-        None => {
-            println!("No file selected.");
-            return Ok(());
-        }
+/// Plays the WAV file at `file`, or one picked in a file dialog.
+pub fn run (file: Option<&Path>) -> Result<(), Box <dyn Error>> {
+
+    //Launches a file dialog to let the user pick a file, unless one was given.
+    let file_path = match file {
+        Some(path) => path.to_path_buf(),
+        None => match FileDialog::new().pick_file() {
+            Some(path) => path,
+            None => {
+                println!("No file selected.");
+                return Ok(());
+            }
+        },
     };
 
     //-----------------------------------------------------------------
@@ -57,12 +61,12 @@ fn main () -> Result<(), Box <dyn Error>> {
 
     //Audio ouput setup using cpal
     let host = cpal::default_host ();
-    let device = host.default_output_device().ok_or("no output device available")?;
+    let device = output_device(&host)?;
     let default_config = device.default_output_config()?;
     let sample_format = default_config.sample_format();
-    let config: StreamConfig = device.default_output_config()?.into();;
+    let config: StreamConfig = device.default_output_config()?.into();
 
-    println!("Using default output device: {}", device.name()?);
+    println!("Using output device: {}", device.name()?);
     println!("Output config: {:?}", config);
 
    let playback_finished = Arc::new(AtomicBool::new(false));
@@ -84,6 +88,21 @@ fn main () -> Result<(), Box <dyn Error>> {
 
 }
 
+/// The device chosen in the shell's sound settings, or the default one if
+/// none was chosen or it has gone.
+fn output_device(host: &cpal::Host) -> Result<cpal::Device, Box<dyn Error>> {
+    if let Ok(name) = std::env::var(AUDIO_OUTPUT_ENV) {
+        let chosen = host
+            .output_devices()?
+            .find(|device| device.name().is_ok_and(|n| n == name));
+        match chosen {
+            Some(device) => return Ok(device),
+            None => println!("Output device {} not found, using the default.", name),
+        }
+    }
+    Ok(host.default_output_device().ok_or("no output device available")?)
+}
+
 //Streams audio until all samples are played
 fn run_stream<T> (
     device: &cpal::Device,
@@ -1,14 +1,19 @@
 use std::process::Command;
 use std::error::Error;
+use std::path::Path;
 use rfd::FileDialog;
 
 
-fn main() -> Result<(), Box<dyn Error>> {
+/// Plays `file` in VLC, or a video picked in a file dialog.
+pub fn run(file: Option<&Path>) -> Result<(), Box<dyn Error>> {
 
-    //launches a file dialog to let the user select a video file
-    let file_path = FileDialog::new() 
-        .add_filter("Videos", &["mp4", "mkv", "avi", "mov"])
-        .pick_file();
+    //launches a file dialog to let the user select a video file, unless one was given
+    let file_path = match file {
+        Some(path) => Some(path.to_path_buf()),
+        None => FileDialog::new()
+            .add_filter("Videos", &["mp4", "mkv", "avi", "mov"])
+            .pick_file(),
+    };
 
     let file_path = match file_path {
         Some(file) => file,
@@ -1,7 +1,8 @@
-use reqwest;
 use serde::Deserialize;
 use std::error::Error;
 
+// Mirrors the API response, so not every field is used
+#[allow(dead_code)]
 #[derive(Deserialize, Debug)]
 struct WeatherData {
     latitude: f64,
@@ -21,6 +22,7 @@ struct HourlyData {
     precipitation_probability: Vec<f64>,
 }
 
+#[allow(dead_code)]
 #[derive(Deserialize, Debug)]
 struct HourlyUnits {
     temperature_2m: String,
@@ -53,7 +55,7 @@ impl WeatherSimplified {
     fn hourly_entry(self) -> Vec<HourlyDP> {
         self.temperatures
             .into_iter()
-            .zip(self.precipitation_probabilities.into_iter())
+            .zip(self.precipitation_probabilities)
             .enumerate() // Get the index of each element
             .map(|(hour, (temp, precip))| HourlyDP {
                 hour,
@@ -65,8 +67,9 @@ impl WeatherSimplified {
 }
 
 
+/// Prints today's hourly forecast.
 #[tokio::main]
-async fn main() -> Result <(), Box<dyn Error>> {
+pub async fn run() -> Result <(), Box<dyn Error>> {
 
     let api_ep = "https://api.open-meteo.com/v1/forecast?latitude=28.6024812001&longitude=81.2001&hourly=temperature_2m,precipitation_probability&timezone=America%2FNew_York&forecast_days=1&wind_speed_unit=mph&temperature_unit=fahrenheit&precipitation_unit=inch";
 
@@ -0,0 +1,220 @@
+// src/associations.rs
+use crate::registry::{AppManifest, AppRegistry};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Where `--config` keeps associations beyond those in the app manifests.
+pub const ASSOCIATIONS_FILE: &str = "associations.json";
+
+/// The type given to directories.
+pub const DIRECTORY_TYPE: &str = "inode/directory";
+
+/// MIME types by extension, for the files a holotape usually carries.
+const MIME_TYPES: &[(&str, &str)] = &[
+    ("wav", "audio/wav"),
+    ("mp3", "audio/mpeg"),
+    ("ogg", "audio/ogg"),
+    ("flac", "audio/flac"),
+    ("mp4", "video/mp4"),
+    ("mkv", "video/x-matroska"),
+    ("avi", "video/x-msvideo"),
+    ("mov", "video/quicktime"),
+    ("webm", "video/webm"),
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("bmp", "image/bmp"),
+    ("webp", "image/webp"),
+    ("txt", "text/plain"),
+    ("md", "text/markdown"),
+    ("csv", "text/csv"),
+    ("py", "text/x-python"),
+    ("pdf", "application/pdf"),
+    ("rtf", "application/rtf"),
+    ("odt", "application/vnd.oasis.opendocument.text"),
+    ("ods", "application/vnd.oasis.opendocument.spreadsheet"),
+    ("odp", "application/vnd.oasis.opendocument.presentation"),
+    ("doc", "application/msword"),
+    (
+        "docx",
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+    ),
+    ("xls", "application/vnd.ms-excel"),
+    (
+        "xlsx",
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+    ),
+    ("ppt", "application/vnd.ms-powerpoint"),
+    (
+        "pptx",
+        "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+    ),
+];
+
+/// The MIME type of `path`, guessed from its extension.
+pub fn mime_type(path: &Path) -> Option<&'static str> {
+    if path.is_dir() {
+        return Some(DIRECTORY_TYPE);
+    }
+    let extension = extension(path)?;
+    MIME_TYPES
+        .iter()
+        .find(|(ext, _)| *ext == extension)
+        .map(|(_, mime)| *mime)
+}
+
+/// `path`'s extension in lower case.
+pub fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+}
+
+/// What `path` can be looked up by, most specific first: its extension,
+/// its MIME type, then the MIME type's `type/*`.
+fn file_types(path: &Path) -> Vec<String> {
+    let mut types = Vec::new();
+    if !path.is_dir() {
+        types.extend(extension(path));
+    }
+    if let Some(mime) = mime_type(path) {
+        types.push(mime.to_string());
+        if let Some((major, _)) = mime.split_once('/') {
+            types.push(format!("{}/*", major));
+        }
+    }
+    types
+}
+
+/// Which apps open which files. Apps name the types they open in their
+/// manifests; `associations.json` adds to that, mapping an extension or
+/// MIME type to app ids, preferred first:
+///
+/// ```json
+/// { "pdf": ["browser"], "image/*": ["image-viewer"] }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Associations {
+    table: BTreeMap<String, Vec<String>>,
+}
+
+impl Associations {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("reading associations {}", path.display()))?;
+        let associations: Associations = serde_json::from_str(&text)
+            .with_context(|| format!("parsing associations {}", path.display()))?;
+        Ok(associations.normalised())
+    }
+
+    /// No extra associations when `path` does not exist.
+    pub fn load_or_default(path: impl AsRef<Path>) -> Result<Self> {
+        if path.as_ref().exists() {
+            Self::load(path)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    /// The apps that can open `path`, best first. Types are tried from the
+    /// most specific; for each, apps listed here come before those whose
+    /// manifests name it, which keep registry order.
+    pub fn handlers<'a>(&self, apps: &'a AppRegistry, path: &Path) -> Vec<&'a AppManifest> {
+        let mut handlers: Vec<&AppManifest> = Vec::new();
+        for file_type in file_types(path) {
+            let listed = self
+                .table
+                .get(&file_type)
+                .into_iter()
+                .flatten()
+                .filter_map(|id| {
+                    let app = apps.get(id);
+                    if app.is_none() {
+                        log::warn!("{}: no app called `{}`", file_type, id);
+                    }
+                    app
+                });
+            let declared = apps
+                .apps()
+                .iter()
+                .filter(|app| app.file_types.iter().any(|t| normalise(t) == file_type));
+            for app in listed.chain(declared) {
+                if !handlers.iter().any(|h| h.id == app.id) {
+                    handlers.push(app);
+                }
+            }
+        }
+        handlers
+    }
+
+    /// Lower-cases keys and drops leading dots, as users write `.ODT`.
+    fn normalised(self) -> Self {
+        let mut table: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for (file_type, ids) in self.table {
+            table.entry(normalise(&file_type)).or_default().extend(ids);
+        }
+        Self { table }
+    }
+}
+
+fn normalise(file_type: &str) -> String {
+    file_type
+        .trim()
+        .trim_start_matches('.')
+        .to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app(id: &str, file_types: &[&str]) -> AppManifest {
+        serde_json::from_value(serde_json::json!({
+            "id": id, "name": id, "category": "Tools",
+            "exec": { "type": "command", "program": id },
+            "file_types": file_types,
+        }))
+        .unwrap()
+    }
+
+    fn ids(handlers: Vec<&AppManifest>) -> Vec<&str> {
+        handlers.iter().map(|app| app.id.as_str()).collect()
+    }
+
+    #[test]
+    fn handlers_go_from_the_most_specific_type() {
+        let apps = AppRegistry::new(vec![
+            app("player", &["audio/*"]),
+            app("mime-app", &["audio/wav"]),
+            app("wav-tool", &[".WAV"]),
+            app("picked", &["audio/wav"]),
+        ]);
+        let associations: Associations =
+            serde_json::from_str(r#"{ ".WAV": ["picked"], "audio/*": ["picked", "gone"] }"#)
+                .unwrap();
+        let associations = associations.normalised();
+
+        // Listed before declared for each type, and each app only once
+        let handlers = associations.handlers(&apps, Path::new("/media/usb/song.Wav"));
+        assert_eq!(ids(handlers), ["picked", "wav-tool", "mime-app", "player"]);
+        assert!(associations
+            .handlers(&apps, Path::new("notes.txt"))
+            .is_empty());
+    }
+
+    #[test]
+    fn keys_are_normalised_and_merged() {
+        let associations: Associations =
+            serde_json::from_str(r#"{ ".ODT": ["writer"], "odt": ["viewer"], " Image/*": [] }"#)
+                .unwrap();
+        let associations = associations.normalised();
+        assert_eq!(associations.table["odt"], ["writer", "viewer"]);
+        assert!(associations.table.contains_key("image/*"));
+        assert_eq!(associations.table.len(), 2);
+    }
+}
@@ -0,0 +1,10 @@
+use std::path::PathBuf;
+
+/// The audio player, started by the shell as an internal app.
+fn main() {
+    let file = std::env::args_os().nth(1).map(PathBuf::from);
+    if let Err(e) = kips_ui::apps::audioplayer::run(file.as_deref()) {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
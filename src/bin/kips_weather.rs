@@ -0,0 +1,7 @@
+/// The weather app, started by the shell as an internal app.
+fn main() {
+    if let Err(e) = kips_ui::apps::weather::run() {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,248 @@
+use crate::associations::{Associations, ASSOCIATIONS_FILE};
+use crate::profiles::ProfileStore;
+use crate::registry::{self, AppRegistry};
+use crate::settings::SettingsStore;
+use crate::ui::calibration::CalibrationScreen;
+use crate::ui::files::FileBrowser;
+use crate::ui::main_menu::MainMenuScreen;
+use crate::ui::profiles::ProfileMenu;
+use crate::ui::running::RunningAppsScreen;
+use crate::ui::screen::{Screen, ScreenContext};
+use crate::ui::settings::SettingsMenu;
+use clap::{Parser, ValueEnum};
+use log::LevelFilter;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+pub const BINDINGS_FILE: &str = "bindings.json";
+pub const CALIBRATION_FILE: &str = "calibration.json";
+
+/// Pip-Boy style launcher shell for the Raspberry Pi.
+#[derive(Debug, Parser)]
+#[command(name = "kips_ui", version, about)]
+pub struct Cli {
+    /// Read keys from the terminal instead of GPIO and /dev/input
+    #[arg(long)]
+    pub dev: bool,
+
+    /// How the shell is shown
+    #[arg(long, value_enum, default_value_t = Frontend::Window)]
+    pub frontend: Frontend,
+
+    /// Run in a window (the default with --dev)
+    #[arg(long, conflicts_with = "fullscreen")]
+    pub windowed: bool,
+
+    /// Run fullscreen (the default without --dev)
+    #[arg(long)]
+    pub fullscreen: bool,
+
+    /// Window size as WIDTHxHEIGHT; the UI is scaled to fit
+    #[arg(long, value_name = "WxH", default_value = "800x480")]
+    pub resolution: Resolution,
+
+    /// Directory holding bindings.json, calibration.json, associations.json,
+    /// the app manifests in apps/ and the user profiles
+    #[arg(long, value_name = "DIR", default_value = "config")]
+    pub config: PathBuf,
+
+    /// Settings file for every profile [default: one per profile under
+    /// profiles/ in the config directory]
+    #[arg(long, value_name = "FILE")]
+    pub settings: Option<PathBuf>,
+
+    /// Directory holding fonts and images
+    #[arg(long, value_name = "DIR", default_value = "assets")]
+    pub assets: PathBuf,
+
+    /// off, error, warn, info, debug or trace
+    #[arg(long, value_name = "LEVEL", default_value = "info")]
+    pub log_level: LevelFilter,
+
+    /// Screen shown at startup
+    #[arg(long, value_enum, default_value_t = StartScreen::Welcome)]
+    pub screen: StartScreen,
+
+    /// Record every input event to FILE as JSON lines
+    #[arg(long, value_name = "FILE")]
+    pub record: Option<PathBuf>,
+
+    /// Play back a recording made with --record instead of live input
+    #[arg(long, value_name = "FILE")]
+    pub replay: Option<PathBuf>,
+
+    /// Replay speed; 2 plays twice as fast
+    #[arg(long, value_name = "FACTOR", default_value_t = 1.0, requires = "replay")]
+    pub replay_speed: f32,
+}
+
+impl Cli {
+    pub fn is_fullscreen(&self) -> bool {
+        self.fullscreen || (!self.windowed && !self.dev)
+    }
+
+    pub fn bindings_path(&self) -> PathBuf {
+        self.config.join(BINDINGS_FILE)
+    }
+
+    pub fn calibration_path(&self) -> PathBuf {
+        self.config.join(CALIBRATION_FILE)
+    }
+
+    /// The manifests in `apps/` under the config directory, or the built-in
+    /// apps when there is no such directory.
+    pub fn app_registry(&self) -> AppRegistry {
+        let dir = self.config.join(registry::APPS_DIR);
+        AppRegistry::load_or_default(&dir).unwrap_or_else(|e| {
+            log::warn!("Using the built-in apps: {:#}", e);
+            AppRegistry::default()
+        })
+    }
+
+    /// Extra open-with associations from the config directory.
+    pub fn associations(&self) -> Associations {
+        let path = self.config.join(ASSOCIATIONS_FILE);
+        Associations::load_or_default(&path).unwrap_or_else(|e| {
+            log::warn!("Ignoring associations: {:#}", e);
+            Associations::default()
+        })
+    }
+
+    /// The profiles kept in the config directory.
+    pub fn profile_store(&self) -> ProfileStore {
+        ProfileStore::open(&self.config)
+    }
+
+    /// The active profile's settings, unless `--settings` names a file.
+    pub fn settings_store(&self, profiles: &ProfileStore) -> SettingsStore {
+        match &self.settings {
+            Some(path) => SettingsStore::open(path),
+            None => SettingsStore::open(profiles.settings_path()),
+        }
+    }
+
+    /// Sets up logging at `--log-level`; `RUST_LOG` can still narrow it
+    /// per module.
+    pub fn init_logging(&self) {
+        env_logger::Builder::new()
+            .filter_level(self.log_level)
+            .parse_default_env()
+            .init();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Resolution {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl FromStr for Resolution {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (width, height) = s
+            .split_once(['x', 'X'])
+            .ok_or_else(|| format!("expected WIDTHxHEIGHT, got `{}`", s))?;
+        let parse = |value: &str| {
+            value
+                .trim()
+                .parse::<u32>()
+                .ok()
+                .filter(|&v| v > 0)
+                .ok_or_else(|| format!("`{}` is not a valid size", value))
+        };
+        Ok(Self {
+            width: parse(width)?,
+            height: parse(height)?,
+        })
+    }
+}
+
+impl fmt::Display for Resolution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}x{}", self.width, self.height)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Frontend {
+    /// Draw the UI in an SDL window
+    Window,
+    /// No window: inputs still navigate and launch apps, nothing is drawn.
+    /// Useful with --replay or over SSH.
+    Headless,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum StartScreen {
+    Welcome,
+    Apps,
+    Profile,
+    Settings,
+    /// The apps launched from the shell
+    Running,
+    /// The file browser, at the home folder
+    Files,
+    /// Touchscreen calibration, for a panel whose taps land in the wrong place
+    Calibration,
+}
+
+impl StartScreen {
+    /// The screen to open over the welcome screen, so Back still lands there.
+    pub fn screen(self, ctx: &ScreenContext) -> Option<Box<dyn Screen>> {
+        let screen: Box<dyn Screen> = match self {
+            StartScreen::Welcome => return None,
+            StartScreen::Apps => Box::new(MainMenuScreen::new(0)),
+            StartScreen::Profile => Box::new(ProfileMenu::new(&ctx.profiles, &ctx.cli.assets)),
+            StartScreen::Settings => Box::new(SettingsMenu::new(&ctx.settings, &ctx.input)),
+            StartScreen::Running => Box::new(RunningAppsScreen::default()),
+            StartScreen::Files => Box::new(FileBrowser::new(None)),
+            StartScreen::Calibration => Box::new(CalibrationScreen::new()),
+        };
+        Some(screen)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolutions_are_width_by_height() {
+        let resolution: Resolution = "800x480".parse().unwrap();
+        assert_eq!((resolution.width, resolution.height), (800, 480));
+        assert_eq!(resolution.to_string(), "800x480");
+        assert_eq!(
+            "1024X600".parse(),
+            Ok(Resolution {
+                width: 1024,
+                height: 600
+            })
+        );
+
+        assert_eq!(
+            "800".parse::<Resolution>(),
+            Err("expected WIDTHxHEIGHT, got `800`".to_string())
+        );
+        assert_eq!(
+            "0x480".parse::<Resolution>(),
+            Err("`0` is not a valid size".to_string())
+        );
+        assert!("800x".parse::<Resolution>().is_err());
+        assert!("wide x tall".parse::<Resolution>().is_err());
+    }
+
+    #[test]
+    fn profiles_live_in_the_config_directory() {
+        let dir = std::env::temp_dir().join(format!("kips_ui-cli-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let cli = Cli::parse_from(["kips_ui".as_ref(), "--config".as_ref(), dir.as_os_str()]);
+
+        let profiles = cli.profile_store();
+        assert!(dir.join(crate::profiles::PROFILES_FILE).exists());
+        assert!(cli.settings_store(&profiles).path().starts_with(&dir));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
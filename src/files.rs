@@ -0,0 +1,359 @@
+// src/files.rs
+use crate::associations;
+use anyhow::{bail, Context, Result};
+use std::cmp::Ordering;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// What a file is, for picking its icon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileKind {
+    Folder,
+    Audio,
+    Video,
+    Image,
+    Document,
+    Text,
+    Other,
+}
+
+impl FileKind {
+    pub fn of(path: &Path) -> Self {
+        let Some(mime) = associations::mime_type(path) else {
+            return FileKind::Other;
+        };
+        if mime == associations::DIRECTORY_TYPE {
+            return FileKind::Folder;
+        }
+        match mime.split_once('/').map_or(mime, |(major, _)| major) {
+            "audio" => FileKind::Audio,
+            "video" => FileKind::Video,
+            "image" => FileKind::Image,
+            "text" => FileKind::Text,
+            "application" => FileKind::Document,
+            _ => FileKind::Other,
+        }
+    }
+
+    /// Name of the icon in `assets/icons`, and the tag drawn without it.
+    pub fn name(self) -> &'static str {
+        match self {
+            FileKind::Folder => "folder",
+            FileKind::Audio => "audio",
+            FileKind::Video => "video",
+            FileKind::Image => "image",
+            FileKind::Document => "document",
+            FileKind::Text => "text",
+            FileKind::Other => "file",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortOrder {
+    #[default]
+    Name,
+    /// Newest first.
+    Modified,
+    /// Largest first.
+    Size,
+    /// By extension, then name.
+    Type,
+}
+
+impl SortOrder {
+    pub fn next(self) -> Self {
+        match self {
+            SortOrder::Name => SortOrder::Modified,
+            SortOrder::Modified => SortOrder::Size,
+            SortOrder::Size => SortOrder::Type,
+            SortOrder::Type => SortOrder::Name,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortOrder::Name => "Name",
+            SortOrder::Modified => "Date",
+            SortOrder::Size => "Size",
+            SortOrder::Type => "Type",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub name: String,
+    pub path: PathBuf,
+    pub kind: FileKind,
+    /// Bytes; 0 for folders.
+    pub size: u64,
+    pub modified: Option<SystemTime>,
+}
+
+impl Entry {
+    pub fn is_dir(&self) -> bool {
+        self.kind == FileKind::Folder
+    }
+}
+
+/// The entries of `dir`, folders first, each group in `order`. Names
+/// starting with a dot are left out unless `hidden` is set.
+pub fn list_dir(dir: &Path, order: SortOrder, hidden: bool) -> Result<Vec<Entry>> {
+    let mut entries: Vec<Entry> = fs::read_dir(dir)
+        .with_context(|| format!("listing {}", dir.display()))?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name.starts_with('.') && !hidden {
+                return None;
+            }
+            // Follows links, so a link to a folder opens like one
+            let metadata = fs::metadata(entry.path()).ok();
+            let path = entry.path();
+            Some(Entry {
+                kind: FileKind::of(&path),
+                size: metadata
+                    .as_ref()
+                    .filter(|m| m.is_file())
+                    .map_or(0, |m| m.len()),
+                modified: metadata.and_then(|m| m.modified().ok()),
+                name,
+                path,
+            })
+        })
+        .collect();
+    entries.sort_by(|a, b| compare(a, b, order));
+    Ok(entries)
+}
+
+fn compare(a: &Entry, b: &Entry, order: SortOrder) -> Ordering {
+    let by_name = || a.name.to_lowercase().cmp(&b.name.to_lowercase());
+    b.is_dir().cmp(&a.is_dir()).then_with(|| match order {
+        SortOrder::Name => by_name(),
+        SortOrder::Modified => b.modified.cmp(&a.modified).then_with(by_name),
+        SortOrder::Size => b.size.cmp(&a.size).then_with(by_name),
+        SortOrder::Type => associations::extension(&a.path)
+            .cmp(&associations::extension(&b.path))
+            .then_with(by_name),
+    })
+}
+
+/// Checks a new name for a file in `dir`.
+pub fn validate_name(dir: &Path, name: &str) -> Result<()> {
+    if name.trim().is_empty() || name == "." || name == ".." {
+        bail!("enter a name");
+    }
+    if name.contains('/') || name.contains('\0') {
+        bail!("names cannot contain `/`");
+    }
+    if dir.join(name).symlink_metadata().is_ok() {
+        bail!("{} already exists", name);
+    }
+    Ok(())
+}
+
+/// Renames `path` in place and returns its new path.
+pub fn rename(path: &Path, name: &str) -> Result<PathBuf> {
+    let dir = path.parent().context("cannot rename the top folder")?;
+    validate_name(dir, name)?;
+    let target = dir.join(name);
+    fs::rename(path, &target)
+        .with_context(|| format!("renaming {} to {}", path.display(), name))?;
+    Ok(target)
+}
+
+/// Copies `path`, folders included, into `dir` and returns the copy. A
+/// name already taken there gets " (copy)" added. Links are copied as links.
+pub fn copy_to(path: &Path, dir: &Path) -> Result<PathBuf> {
+    if dir.starts_with(path) {
+        bail!("cannot copy a folder into itself");
+    }
+    let target = free_name(path, dir)?;
+    copy_tree(path, &target)
+        .with_context(|| format!("copying {} to {}", path.display(), dir.display()))?;
+    Ok(target)
+}
+
+/// Moves `path` into `dir` and returns its new path. Between drives this is
+/// a copy and a delete.
+pub fn move_to(path: &Path, dir: &Path) -> Result<PathBuf> {
+    if dir.starts_with(path) {
+        bail!("cannot move a folder into itself");
+    }
+    if path.parent() == Some(dir) {
+        return Ok(path.to_path_buf());
+    }
+    let target = free_name(path, dir)?;
+    match fs::rename(path, &target) {
+        Ok(()) => {}
+        Err(e) if e.kind() == ErrorKind::CrossesDevices => {
+            copy_tree(path, &target)
+                .with_context(|| format!("moving {} to {}", path.display(), dir.display()))?;
+            delete(path)?;
+        }
+        Err(e) => {
+            return Err(e)
+                .with_context(|| format!("moving {} to {}", path.display(), dir.display()))
+        }
+    }
+    Ok(target)
+}
+
+pub fn delete(path: &Path) -> Result<()> {
+    let result = if path.symlink_metadata()?.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    };
+    result.with_context(|| format!("deleting {}", path.display()))
+}
+
+/// Copies `from` to the new path `to`, removing what was copied if any of
+/// it fails.
+fn copy_tree(from: &Path, to: &Path) -> Result<()> {
+    if to.symlink_metadata().is_ok() {
+        bail!("{} already exists", to.display());
+    }
+    let result = copy_recursive(from, to);
+    if result.is_err() && to.symlink_metadata().is_ok() {
+        if let Err(e) = delete(to) {
+            log::warn!("Cannot remove the partial copy: {:#}", e);
+        }
+    }
+    result
+}
+
+fn copy_recursive(from: &Path, to: &Path) -> Result<()> {
+    let file_type = from
+        .symlink_metadata()
+        .with_context(|| format!("reading {}", from.display()))?
+        .file_type();
+    // A link is recreated rather than followed, so one pointing at a parent
+    // folder cannot copy forever
+    if file_type.is_symlink() {
+        let link = fs::read_link(from).with_context(|| format!("reading {}", from.display()))?;
+        std::os::unix::fs::symlink(&link, to)
+            .with_context(|| format!("linking {}", to.display()))?;
+    } else if file_type.is_dir() {
+        fs::create_dir(to).with_context(|| format!("creating {}", to.display()))?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+        }
+    } else if file_type.is_file() {
+        fs::copy(from, to).with_context(|| format!("copying {}", from.display()))?;
+    } else {
+        // Reading a pipe or device would block or never end
+        bail!("{} is not a regular file", from.display());
+    }
+    Ok(())
+}
+
+/// `path`'s name in `dir`, with " (copy)", " (copy 2)"… added until it is
+/// not taken.
+fn free_name(path: &Path, dir: &Path) -> Result<PathBuf> {
+    let name = path.file_name().context("nothing to copy")?;
+    let target = dir.join(name);
+    if target.symlink_metadata().is_err() {
+        return Ok(target);
+    }
+    let stem = path
+        .file_stem()
+        .map_or_else(String::new, |s| s.to_string_lossy().into_owned());
+    let extension = path
+        .extension()
+        .map_or_else(String::new, |e| format!(".{}", e.to_string_lossy()));
+    for n in 1.. {
+        let suffix = if n == 1 {
+            " (copy)".to_string()
+        } else {
+            format!(" (copy {})", n)
+        };
+        let target = dir.join(format!("{}{}{}", stem, suffix, extension));
+        if target.symlink_metadata().is_err() {
+            return Ok(target);
+        }
+    }
+    unreachable!("ran out of copy names")
+}
+
+/// "512 B", "3.2 KB", "41 MB".
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if size < 10.0 {
+        format!("{:.1} {}", size, UNITS[unit])
+    } else {
+        format!("{:.0} {}", size, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("kips_ui-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn links_are_copied_as_links() {
+        let dir = temp_dir("files-links");
+        let folder = dir.join("folder");
+        fs::create_dir(&folder).unwrap();
+        fs::write(folder.join("notes.txt"), "hello").unwrap();
+        // Followed, this would copy the folder into itself forever
+        std::os::unix::fs::symlink("..", folder.join("up")).unwrap();
+        std::os::unix::fs::symlink("notes.txt", folder.join("readme")).unwrap();
+
+        let copy = copy_to(&folder, &dir).unwrap();
+        assert_eq!(copy, dir.join("folder (copy)"));
+        assert_eq!(fs::read_to_string(copy.join("notes.txt")).unwrap(), "hello");
+        assert_eq!(fs::read_link(copy.join("up")).unwrap(), Path::new(".."));
+        assert_eq!(
+            fs::read_link(copy.join("readme")).unwrap(),
+            Path::new("notes.txt")
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn failed_copies_are_removed() {
+        let dir = temp_dir("files-partial");
+        let folder = dir.join("folder");
+        fs::create_dir(&folder).unwrap();
+        fs::write(folder.join("a.txt"), "a").unwrap();
+        // A fifo cannot be copied
+        let fifo = folder.join("b.fifo");
+        assert!(std::process::Command::new("mkfifo")
+            .arg(&fifo)
+            .status()
+            .unwrap()
+            .success());
+
+        let target = dir.join("elsewhere");
+        fs::create_dir(&target).unwrap();
+        assert!(copy_to(&folder, &target).is_err());
+        assert!(fs::read_dir(&target).unwrap().next().is_none());
+
+        // Moving on the same drive is a rename, so nothing is copied
+        let moved = move_to(&folder, &target).unwrap();
+        assert!(moved.join("b.fifo").exists());
+        assert!(!folder.exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,73 @@
+// src/hardware.rs
+use anyhow::{bail, Context, Result};
+use std::process::{Command, Stdio};
+
+const BACKLIGHT_DIR: &str = "/sys/class/backlight";
+
+/// Sets every backlight under `/sys/class/backlight` to `percent` of its
+/// maximum. Returns how many were set; a desktop usually has none.
+pub fn set_brightness(percent: u8) -> Result<usize> {
+    let Ok(entries) = std::fs::read_dir(BACKLIGHT_DIR) else {
+        return Ok(0);
+    };
+
+    let mut count = 0;
+    for entry in entries.flatten() {
+        let dir = entry.path();
+        let max_path = dir.join("max_brightness");
+        let max: u32 = std::fs::read_to_string(&max_path)
+            .with_context(|| format!("reading {}", max_path.display()))?
+            .trim()
+            .parse()
+            .with_context(|| format!("parsing {}", max_path.display()))?;
+        // Never fully dark, so there is still a screen to turn it back up on
+        let level = (max * percent.min(100) as u32 / 100).max(1);
+        let path = dir.join("brightness");
+        std::fs::write(&path, level.to_string())
+            .with_context(|| format!("writing {}", path.display()))?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Turns every backlight with a `bl_power` control off, or back on at the
+/// brightness it had. Returns how many were switched.
+pub fn set_backlight_power(on: bool) -> Result<usize> {
+    let Ok(entries) = std::fs::read_dir(BACKLIGHT_DIR) else {
+        return Ok(0);
+    };
+
+    let mut count = 0;
+    for entry in entries.flatten() {
+        let path = entry.path().join("bl_power");
+        if !path.exists() {
+            continue;
+        }
+        // FB_BLANK_UNBLANK and FB_BLANK_POWERDOWN
+        std::fs::write(&path, if on { "0" } else { "4" })
+            .with_context(|| format!("writing {}", path.display()))?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Sets the ALSA `Master` control through `amixer`.
+pub fn set_volume(percent: u8, muted: bool) -> Result<()> {
+    let volume = format!("{}%", percent.min(100));
+    let status = Command::new("amixer")
+        .args([
+            "-q",
+            "sset",
+            "Master",
+            &volume,
+            if muted { "mute" } else { "unmute" },
+        ])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .context("running amixer")?;
+    if !status.success() {
+        bail!("amixer failed ({})", status);
+    }
+    Ok(())
+}
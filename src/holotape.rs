@@ -0,0 +1,64 @@
+// src/holotape.rs
+use rusb::{Context, UsbContext};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use sysinfo::{DiskExt, System, SystemExt};
+
+/// How often the USB bus is scanned; enumerating it every frame is slow.
+const SCAN_INTERVAL: Duration = Duration::from_secs(1);
+
+// Function to check if a USB flash drive (holotape) is connected
+pub fn drive_connected() -> bool {
+    match Context::new() {
+        Ok(context) => {
+            if let Ok(devices) = context.devices() {
+                for device in devices.iter() {
+                    if let Ok(config_desc) = device.active_config_descriptor() {
+                        for interface in config_desc.interfaces() {
+                            for interface_desc in interface.descriptors() {
+                                if interface_desc.class_code() == 0x08 {
+                                    return true;
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+            false
+        }
+        Err(_) => false,
+    }
+}
+
+/// Where removable drives are mounted.
+pub fn mount_points() -> Vec<PathBuf> {
+    let mut system = System::new();
+    system.refresh_disks_list();
+    system
+        .disks()
+        .iter()
+        .filter(|disk| disk.is_removable())
+        .map(|disk| disk.mount_point().to_path_buf())
+        .collect()
+}
+
+/// Notices a holotape being plugged in, once per insertion.
+#[derive(Debug, Default)]
+pub struct HolotapeWatch {
+    connected: bool,
+    next_scan: Option<Instant>,
+}
+
+impl HolotapeWatch {
+    /// `true` on the first scan after a drive appears.
+    pub fn poll(&mut self, now: Instant) -> bool {
+        if self.next_scan.is_some_and(|next| now < next) {
+            return false;
+        }
+        self.next_scan = Some(now + SCAN_INTERVAL);
+
+        let was_connected = self.connected;
+        self.connected = drive_connected();
+        self.connected && !was_connected
+    }
+}
@@ -0,0 +1,484 @@
+use crate::input::gesture::{ButtonId, ChordBinding, GestureTiming};
+use crate::input::keymap::Layout;
+use crate::input::quadrature::{AccelerationConfig, StepMode};
+use crate::input::InputEvent;
+use anyhow::{bail, Context, Result};
+use evdev::Key;
+use rppal::gpio::{InputPin, Level, Pin};
+use sdl2::keyboard::Keycode;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+use std::str::FromStr;
+
+// BCM numbering on the 40-pin header
+const MAX_GPIO_PIN: u8 = 27;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PullMode {
+    #[default]
+    Up,
+    Down,
+    Floating,
+}
+
+impl PullMode {
+    pub fn configure(self, pin: Pin) -> InputPin {
+        match self {
+            PullMode::Up => pin.into_input_pullup(),
+            PullMode::Down => pin.into_input_pulldown(),
+            PullMode::Floating => pin.into_input(),
+        }
+    }
+
+    /// Level read while the contact is closed; floating inputs are assumed to
+    /// have an external pull-up.
+    pub fn active_level(self) -> Level {
+        match self {
+            PullMode::Down => Level::High,
+            PullMode::Up | PullMode::Floating => Level::Low,
+        }
+    }
+}
+
+/// What a key or GPIO action produces on the input bus.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    NavigateUp,
+    NavigateDown,
+    NavigateLeft,
+    NavigateRight,
+    Activate,
+    Select,
+    Back,
+    Home,
+    Menu,
+    Power,
+    Key(char),
+    Button(String),
+    /// Each press emits the next label as `ButtonPressed`.
+    Cycle(Vec<String>),
+    EncoderPressed(u8),
+}
+
+impl Action {
+    /// `Cycle` needs per-button state and is resolved by `GestureDetector`.
+    pub fn to_event(&self) -> Option<InputEvent> {
+        match self {
+            Action::NavigateUp => Some(InputEvent::NavigateUp),
+            Action::NavigateDown => Some(InputEvent::NavigateDown),
+            Action::NavigateLeft => Some(InputEvent::NavigateLeft),
+            Action::NavigateRight => Some(InputEvent::NavigateRight),
+            Action::Activate => Some(InputEvent::Activate),
+            Action::Select => Some(InputEvent::Select),
+            Action::Back => Some(InputEvent::Back),
+            Action::Home => Some(InputEvent::Home),
+            Action::Menu => Some(InputEvent::Menu),
+            Action::Power => Some(InputEvent::Power),
+            Action::Key(c) => Some(InputEvent::KeyPress(*c)),
+            Action::Button(label) => Some(InputEvent::ButtonPressed(label.clone())),
+            Action::Cycle(_) => None,
+            Action::EncoderPressed(id) => Some(InputEvent::EncoderPressed(*id)),
+        }
+    }
+}
+
+fn default_debounce_ms() -> u64 {
+    50
+}
+
+fn default_foreground_home() -> Vec<Action> {
+    vec![Action::Home]
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EncoderBinding {
+    pub id: u8,
+    pub clk: u8,
+    pub dt: u8,
+    pub btn: u8,
+    #[serde(default)]
+    pub pull: PullMode,
+    #[serde(default = "default_debounce_ms")]
+    pub debounce_ms: u64,
+    #[serde(default)]
+    pub step_mode: StepMode,
+    /// Decode from GPIO edge interrupts instead of sampling once per frame.
+    #[serde(default)]
+    pub interrupts: bool,
+    /// Velocity-based scaling of fast spins; off when unset.
+    #[serde(default)]
+    pub acceleration: Option<AccelerationConfig>,
+    /// Defaults to `EncoderPressed(id)`.
+    #[serde(default)]
+    pub press: Option<Action>,
+    #[serde(default)]
+    pub long_press: Option<Action>,
+    #[serde(default)]
+    pub double_press: Option<Action>,
+    /// When both are unset the encoder emits `EncoderTurned`.
+    #[serde(default)]
+    pub clockwise: Option<Action>,
+    #[serde(default)]
+    pub counter_clockwise: Option<Action>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ButtonBinding {
+    pub id: u8,
+    pub pin: u8,
+    #[serde(default)]
+    pub pull: PullMode,
+    #[serde(default = "default_debounce_ms")]
+    pub debounce_ms: u64,
+    pub press: Action,
+    #[serde(default)]
+    pub long_press: Option<Action>,
+    #[serde(default)]
+    pub double_press: Option<Action>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InputBindings {
+    #[serde(default)]
+    pub encoders: Vec<EncoderBinding>,
+    #[serde(default)]
+    pub buttons: Vec<ButtonBinding>,
+    #[serde(default)]
+    pub gestures: GestureTiming,
+    /// Buttons held together, e.g. both encoder buttons for `home`.
+    #[serde(default)]
+    pub chords: Vec<ChordBinding>,
+    /// Character layout of attached evdev keyboards.
+    #[serde(default)]
+    pub keyboard_layout: Layout,
+    /// evdev key names, e.g. `KEY_ENTER`. Keys that type text also send
+    /// `KeyPress`, so letters do not need binding.
+    #[serde(default)]
+    pub keys: BTreeMap<String, Action>,
+    /// SDL key names, e.g. `Return`, used when no evdev keyboard is attached.
+    #[serde(default)]
+    pub sdl_keys: BTreeMap<String, Action>,
+    /// What brings the shell back while a launched app is in front; any
+    /// other input is ignored until then.
+    #[serde(default = "default_foreground_home")]
+    pub foreground_home: Vec<Action>,
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        let keys = [
+            ("KEY_UP", Action::NavigateUp),
+            ("KEY_DOWN", Action::NavigateDown),
+            ("KEY_LEFT", Action::NavigateLeft),
+            ("KEY_RIGHT", Action::NavigateRight),
+            ("KEY_SPACE", Action::Activate),
+            ("KEY_ENTER", Action::Select),
+            ("KEY_ESC", Action::Back),
+            // Remote controls
+            ("KEY_OK", Action::Select),
+            ("KEY_SELECT", Action::Select),
+            ("KEY_BACK", Action::Back),
+            ("KEY_HOMEPAGE", Action::Home),
+            ("KEY_MENU", Action::Menu),
+            ("KEY_POWER", Action::Power),
+            // Gamepads
+            ("BTN_DPAD_UP", Action::NavigateUp),
+            ("BTN_DPAD_DOWN", Action::NavigateDown),
+            ("BTN_DPAD_LEFT", Action::NavigateLeft),
+            ("BTN_DPAD_RIGHT", Action::NavigateRight),
+            ("BTN_SOUTH", Action::Select),
+            ("BTN_EAST", Action::Back),
+            ("BTN_START", Action::Menu),
+            ("BTN_MODE", Action::Home),
+        ];
+        let sdl_keys = [
+            ("Up", Action::NavigateUp),
+            ("Down", Action::NavigateDown),
+            ("Left", Action::NavigateLeft),
+            ("Right", Action::NavigateRight),
+            ("Space", Action::Activate),
+            ("Return", Action::Select),
+            ("Escape", Action::Back),
+        ];
+
+        InputBindings {
+            encoders: vec![
+                EncoderBinding {
+                    id: 0,
+                    clk: 17,
+                    dt: 27,
+                    btn: 22,
+                    pull: PullMode::Up,
+                    debounce_ms: default_debounce_ms(),
+                    step_mode: StepMode::Full,
+                    interrupts: false,
+                    acceleration: None,
+                    press: None,
+                    long_press: None,
+                    double_press: None,
+                    clockwise: None,
+                    counter_clockwise: None,
+                },
+                EncoderBinding {
+                    id: 1,
+                    clk: 23,
+                    dt: 24,
+                    btn: 25,
+                    pull: PullMode::Up,
+                    debounce_ms: default_debounce_ms(),
+                    step_mode: StepMode::Full,
+                    interrupts: false,
+                    acceleration: None,
+                    press: None,
+                    long_press: None,
+                    double_press: None,
+                    clockwise: None,
+                    counter_clockwise: None,
+                },
+            ],
+            buttons: vec![ButtonBinding {
+                id: 0,
+                pin: 26,
+                pull: PullMode::Up,
+                debounce_ms: default_debounce_ms(),
+                press: Action::Cycle(vec![
+                    "APPS".to_string(),
+                    "PROFILE".to_string(),
+                    "SETTINGS".to_string(),
+                ]),
+                long_press: None,
+                double_press: None,
+            }],
+            gestures: GestureTiming::default(),
+            chords: Vec::new(),
+            keyboard_layout: Layout::Us,
+            keys: keys.into_iter().map(|(k, a)| (k.to_string(), a)).collect(),
+            sdl_keys: sdl_keys.into_iter().map(|(k, a)| (k.to_string(), a)).collect(),
+            foreground_home: default_foreground_home(),
+        }
+    }
+}
+
+impl InputBindings {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("reading input bindings {}", path.display()))?;
+        let bindings: InputBindings = serde_json::from_str(&text)
+            .with_context(|| format!("parsing input bindings {}", path.display()))?;
+        bindings
+            .validate()
+            .with_context(|| format!("invalid input bindings in {}", path.display()))?;
+        Ok(bindings)
+    }
+
+    /// Uses the built-in bindings when `path` does not exist; a file that
+    /// exists but is invalid is still an error.
+    pub fn load_or_default(path: impl AsRef<Path>) -> Result<Self> {
+        if path.as_ref().exists() {
+            Self::load(path)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        let mut pins = HashSet::new();
+        let mut claim_pin = |pin: u8, owner: String| -> Result<()> {
+            if pin > MAX_GPIO_PIN {
+                bail!("{}: GPIO {} is out of range (0-{})", owner, pin, MAX_GPIO_PIN);
+            }
+            if !pins.insert(pin) {
+                bail!("{}: GPIO {} is already used by another binding", owner, pin);
+            }
+            Ok(())
+        };
+
+        let mut encoder_ids = HashSet::new();
+        for enc in &self.encoders {
+            let owner = format!("encoder {}", enc.id);
+            if !encoder_ids.insert(enc.id) {
+                bail!("{}: duplicate encoder id", owner);
+            }
+            claim_pin(enc.clk, format!("{} clk", owner))?;
+            claim_pin(enc.dt, format!("{} dt", owner))?;
+            claim_pin(enc.btn, format!("{} btn", owner))?;
+            if enc.clockwise.is_some() != enc.counter_clockwise.is_some() {
+                bail!("{}: set both clockwise and counter_clockwise, or neither", owner);
+            }
+            if let Some(accel) = &enc.acceleration {
+                if accel.max_multiplier == 0 {
+                    bail!("{}: acceleration max_multiplier must be at least 1", owner);
+                }
+                if accel.fast_ms >= accel.slow_ms {
+                    bail!("{}: acceleration fast_ms must be below slow_ms", owner);
+                }
+            }
+            if let Some(press) = &enc.press {
+                validate_press(press, &owner)?;
+            }
+            for action in [
+                &enc.long_press,
+                &enc.double_press,
+                &enc.clockwise,
+                &enc.counter_clockwise,
+            ]
+            .into_iter()
+            .flatten()
+            {
+                validate_action(action, &owner)?;
+            }
+        }
+
+        let mut button_ids = HashSet::new();
+        for button in &self.buttons {
+            let owner = format!("button {}", button.id);
+            if !button_ids.insert(button.id) {
+                bail!("{}: duplicate button id", owner);
+            }
+            claim_pin(button.pin, owner.clone())?;
+            validate_press(&button.press, &owner)?;
+            for action in [&button.long_press, &button.double_press].into_iter().flatten() {
+                validate_action(action, &owner)?;
+            }
+        }
+
+        if self.gestures.long_press_ms == 0 {
+            bail!("gestures: long_press_ms must be above 0");
+        }
+        for (i, chord) in self.chords.iter().enumerate() {
+            let owner = format!("chord {}", i);
+            if chord.buttons.len() < 2 {
+                bail!("{}: needs at least two buttons", owner);
+            }
+            for button in &chord.buttons {
+                let exists = match button {
+                    ButtonId::Button(id) => button_ids.contains(id),
+                    ButtonId::Encoder(id) => encoder_ids.contains(id),
+                };
+                if !exists {
+                    bail!("{}: {:?} is not bound", owner, button);
+                }
+            }
+            validate_action(&chord.action, &owner)?;
+        }
+
+        if self.foreground_home.is_empty() {
+            bail!("foreground_home: needs at least one action, or launched apps cannot be left");
+        }
+        for action in &self.foreground_home {
+            validate_action(action, "foreground_home")?;
+        }
+
+        self.evdev_keymap()?;
+        self.sdl_keymap()?;
+        Ok(())
+    }
+
+    pub fn evdev_keymap(&self) -> Result<HashMap<Key, Action>> {
+        self.keys
+            .iter()
+            .map(|(name, action)| {
+                let key = Key::from_str(name)
+                    .map_err(|_| anyhow::anyhow!("unknown evdev key `{}`", name))?;
+                validate_action(action, name)?;
+                Ok((key, action.clone()))
+            })
+            .collect()
+    }
+
+    /// The events `foreground_home` produces.
+    pub fn foreground_home_events(&self) -> Vec<InputEvent> {
+        self.foreground_home.iter().filter_map(Action::to_event).collect()
+    }
+
+    pub fn sdl_keymap(&self) -> Result<HashMap<Keycode, Action>> {
+        self.sdl_keys
+            .iter()
+            .map(|(name, action)| {
+                let keycode = Keycode::from_name(name)
+                    .with_context(|| format!("unknown SDL key `{}`", name))?;
+                validate_action(action, name)?;
+                Ok((keycode, action.clone()))
+            })
+            .collect()
+    }
+}
+
+/// Presses may also cycle through labels.
+fn validate_press(action: &Action, owner: &str) -> Result<()> {
+    match action {
+        Action::Cycle(labels) if labels.is_empty() => {
+            bail!("{}: cycle needs at least one label", owner)
+        }
+        Action::Cycle(_) => Ok(()),
+        action => validate_action(action, owner),
+    }
+}
+
+fn validate_action(action: &Action, owner: &str) -> Result<()> {
+    match action {
+        Action::Cycle(_) => bail!("{}: cycle is only supported as a button press", owner),
+        Action::Button(label) if label.is_empty() => bail!("{}: button label is empty", owner),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The error from loading `json` as a bindings file, with its causes.
+    fn load_error(name: &str, json: &str) -> String {
+        let dir = std::env::temp_dir().join(format!("kips_ui-bindings-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("{}.json", name));
+        std::fs::write(&path, json).unwrap();
+        let err = InputBindings::load_or_default(&path).unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        format!("{:#}", err)
+    }
+
+    #[test]
+    fn a_missing_file_uses_the_built_in_bindings() {
+        let path = std::env::temp_dir().join("kips_ui-bindings-missing.json");
+        let bindings = InputBindings::load_or_default(&path).unwrap();
+        assert_eq!(bindings.keys, InputBindings::default().keys);
+    }
+
+    #[test]
+    fn a_pin_can_only_be_bound_once() {
+        let err = load_error(
+            "duplicate-pin",
+            r#"{"buttons": [
+                {"id": 0, "pin": 5, "press": "select"},
+                {"id": 1, "pin": 5, "press": "back"}
+            ]}"#,
+        );
+        assert!(err.contains("button 1: GPIO 5 is already used by another binding"), "{}", err);
+        assert!(err.contains("invalid input bindings in"), "{}", err);
+    }
+
+    #[test]
+    fn unknown_key_names_are_rejected() {
+        let err = load_error("unknown-key", r#"{"keys": {"KEY_NOPE": "select"}}"#);
+        assert!(err.contains("unknown evdev key `KEY_NOPE`"), "{}", err);
+    }
+
+    #[test]
+    fn unknown_actions_are_rejected() {
+        let err = load_error("unknown-action", r#"{"keys": {"KEY_ENTER": "explode"}}"#);
+        assert!(err.contains("parsing input bindings"), "{}", err);
+        assert!(err.contains("unknown variant `explode`"), "{}", err);
+    }
+
+    #[test]
+    fn malformed_json_is_an_error_not_the_defaults() {
+        let err = load_error("malformed", r#"{"keys": {"KEY_ENTER": "select""#);
+        assert!(err.contains("parsing input bindings"), "{}", err);
+        assert!(err.contains("EOF while parsing"), "{}", err);
+    }
+}
@@ -1,66 +1,160 @@
+use crate::input::bindings::ButtonBinding;
+use crate::input::gesture::{ButtonId, GestureKind};
+use crate::input::gpio::{GpioBackend, GpioPin};
+use crate::input::source::InputSource;
 use crate::input::InputEvent;
-use rppal::gpio::{Gpio, InputPin, Level};
+use rppal::gpio::Level;
 use std::time::{Duration, Instant};
 
-const BUTTON_GPIO: u8 = 26;
-const DEBOUNCE_MS: u64 = 50;
-const STATES: [&str; 3] = ["APPS", "PROFILE", "SETTINGS"];
-
-pub enum ButtonInput {
-    Real {
-        pin: InputPin,
-        last_state: bool,
-        last_time: Instant,
-        current_index: usize,
-    },
-    Fake,
+/// Reports debounced press/release edges; `GestureDetector` turns them into
+/// the bound actions.
+pub struct ButtonInput {
+    id: u8,
+    pin: Box<dyn GpioPin>,
+    active_level: Level,
+    debounce: Duration,
+    last_state: bool,
+    /// `None` until the first edge, which is never debounced.
+    last_time: Option<Instant>,
 }
 
 impl ButtonInput {
-    pub fn new() -> Self {
-        let gpio = Gpio::new().expect("Failed to access GPIO");
-        let pin = gpio.get(BUTTON_GPIO).expect("Invalid pin").into_input_pullup();
-
-        ButtonInput::Real {
-            pin,
+    pub fn new(binding: &ButtonBinding, gpio: &dyn GpioBackend) -> anyhow::Result<Self> {
+        Ok(ButtonInput {
+            id: binding.id,
+            pin: gpio.input(binding.pin, binding.pull)?,
+            active_level: binding.pull.active_level(),
+            debounce: Duration::from_millis(binding.debounce_ms),
             last_state: false,
-            last_time: Instant::now(),
-            current_index: 0,
+            last_time: None,
+        })
+    }
+}
+
+impl InputSource for ButtonInput {
+    fn name(&self) -> String {
+        format!("Button {}", self.id)
+    }
+
+    fn poll(&mut self, now: Instant) -> Vec<InputEvent> {
+        let mut events = Vec::new();
+
+        let is_pressed = self.pin.read() == self.active_level;
+
+        let settled = self
+            .last_time
+            .is_none_or(|last| now.duration_since(last) > self.debounce);
+        if is_pressed != self.last_state && settled {
+            self.last_state = is_pressed;
+            self.last_time = Some(now);
+
+            events.push(InputEvent::Gesture {
+                button: ButtonId::Button(self.id),
+                kind: if is_pressed {
+                    GestureKind::Pressed
+                } else {
+                    GestureKind::Released
+                },
+            });
         }
+
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::bindings::InputBindings;
+    use crate::input::gesture::GestureDetector;
+    use crate::input::gpio::SimulatedGpio;
+
+    const PIN: u8 = 5;
+
+    fn bindings(press: &str) -> InputBindings {
+        let json = format!(
+            r#"{{ "buttons": [{{ "id": 0, "pin": {}, "debounce_ms": 20, "press": {} }}] }}"#,
+            PIN, press
+        );
+        let bindings: InputBindings = serde_json::from_str(&json).unwrap();
+        bindings.validate().unwrap();
+        bindings
+    }
+
+    /// Advances and polls once a millisecond up to `until`, passing the
+    /// edges through `gestures`, and keeps only the resulting actions.
+    fn run(
+        gpio: &SimulatedGpio,
+        button: &mut ButtonInput,
+        gestures: &mut GestureDetector,
+        until: u64,
+    ) -> Vec<InputEvent> {
+        (0..=until)
+            .flat_map(|t| {
+                let now = gpio.start() + Duration::from_millis(t);
+                gpio.advance_to(now);
+                gestures.process(button.poll(now), now)
+            })
+            .filter(|event| !matches!(event, InputEvent::Gesture { .. }))
+            .collect()
     }
 
-    pub fn fake() -> Self {
-        ButtonInput::Fake
+    fn press_at(gpio: &SimulatedGpio, at: u64) {
+        gpio.schedule(Duration::from_millis(at), PIN, Level::Low);
+        gpio.schedule(Duration::from_millis(at + 60), PIN, Level::High);
     }
 
-    pub fn poll(&mut self) -> Vec<InputEvent> {
-        match self {
-            ButtonInput::Real {
-                pin,
-                last_state,
-                last_time,
-                current_index,
-            } => {
-                let mut events = Vec::new();
-                let now = Instant::now();
-
-                let is_pressed = pin.read() == Level::Low;
-
-                if is_pressed != *last_state
-                    && now.duration_since(*last_time) > Duration::from_millis(DEBOUNCE_MS)
-                {
-                    *last_state = is_pressed;
-                    *last_time = now;
-
-                    if is_pressed {
-                        *current_index = (*current_index + 1) % STATES.len();
-                        events.push(InputEvent::ButtonPressed(STATES[*current_index]));
-                    }
-                }
-
-                events
-            }
-            ButtonInput::Fake => vec![], // Dev mode: no button input
+    #[test]
+    fn bounce_inside_debounce_is_ignored() {
+        let gpio = SimulatedGpio::new();
+        let bindings = bindings(r#""select""#);
+        let mut button = ButtonInput::new(&bindings.buttons[0], &gpio).unwrap();
+        let mut gestures = GestureDetector::new(&bindings);
+        // Chatter on the press and on the release
+        for (at, level) in [
+            (10, Level::Low),
+            (11, Level::High),
+            (13, Level::Low),
+            (100, Level::High),
+            (105, Level::Low),
+            (108, Level::High),
+        ] {
+            gpio.schedule(Duration::from_millis(at), PIN, level);
         }
+        assert_eq!(
+            run(&gpio, &mut button, &mut gestures, 200),
+            [InputEvent::Select]
+        );
+        assert_eq!(gpio.level(PIN), Some(Level::High));
+
+        // Edges seen by the button itself: one press, one release
+        let gpio = SimulatedGpio::new();
+        let mut button = ButtonInput::new(&bindings.buttons[0], &gpio).unwrap();
+        gpio.schedule(Duration::from_millis(10), PIN, Level::Low);
+        gpio.schedule(Duration::from_millis(15), PIN, Level::High);
+        let now = gpio.start() + Duration::from_millis(10);
+        gpio.advance_to(now);
+        assert_eq!(button.poll(now).len(), 1);
+        let now = gpio.start() + Duration::from_millis(15);
+        gpio.advance_to(now);
+        assert!(button.poll(now).is_empty());
+        let now = gpio.start() + Duration::from_millis(31);
+        assert_eq!(button.poll(now).len(), 1);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn presses_cycle_through_labels() {
+        let gpio = SimulatedGpio::new();
+        let bindings = bindings(r#"{ "cycle": ["A", "B", "C"] }"#);
+        let mut button = ButtonInput::new(&bindings.buttons[0], &gpio).unwrap();
+        let mut gestures = GestureDetector::new(&bindings);
+        for at in [10, 200, 400, 600] {
+            press_at(&gpio, at);
+        }
+        let labels: Vec<InputEvent> = ["B", "C", "A", "B"]
+            .into_iter()
+            .map(|label| InputEvent::ButtonPressed(label.to_string()))
+            .collect();
+        assert_eq!(run(&gpio, &mut button, &mut gestures, 800), labels);
+    }
+}
@@ -0,0 +1,300 @@
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Crosshair positions shown during calibration, as fractions of the screen.
+pub const TARGETS: [(f32, f32); 5] = [
+    (0.1, 0.1),
+    (0.9, 0.1),
+    (0.9, 0.9),
+    (0.1, 0.9),
+    (0.5, 0.5),
+];
+
+/// How the panel is mounted relative to the display, clockwise.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "u16", into = "u16")]
+pub enum Rotation {
+    #[default]
+    Deg0,
+    Deg90,
+    Deg180,
+    Deg270,
+}
+
+impl TryFrom<u16> for Rotation {
+    type Error = String;
+
+    fn try_from(degrees: u16) -> std::result::Result<Self, Self::Error> {
+        match degrees {
+            0 => Ok(Rotation::Deg0),
+            90 => Ok(Rotation::Deg90),
+            180 => Ok(Rotation::Deg180),
+            270 => Ok(Rotation::Deg270),
+            other => Err(format!("rotation must be 0, 90, 180 or 270, not {}", other)),
+        }
+    }
+}
+
+impl From<Rotation> for u16 {
+    fn from(rotation: Rotation) -> u16 {
+        match rotation {
+            Rotation::Deg0 => 0,
+            Rotation::Deg90 => 90,
+            Rotation::Deg180 => 180,
+            Rotation::Deg270 => 270,
+        }
+    }
+}
+
+/// Maps raw touch positions onto the display: the rotation is applied first,
+/// then the affine correction `x' = a*x + b*y + c`, `y' = d*x + e*y + f`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Calibration {
+    pub matrix: [f32; 6],
+    #[serde(default)]
+    pub rotation: Rotation,
+}
+
+impl Default for Calibration {
+    fn default() -> Self {
+        Self {
+            matrix: [1.0, 0.0, 0.0, 0.0, 1.0, 0.0],
+            rotation: Rotation::Deg0,
+        }
+    }
+}
+
+impl Calibration {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("reading touch calibration {}", path.display()))?;
+        serde_json::from_str(&text)
+            .with_context(|| format!("parsing touch calibration {}", path.display()))
+    }
+
+    /// A missing file means an uncalibrated panel; an unreadable one is
+    /// reported and ignored so a bad file cannot lock out the touchscreen.
+    pub fn load_or_default(path: impl AsRef<Path>) -> Self {
+        if !path.as_ref().exists() {
+            return Self::default();
+        }
+        Self::load(path).unwrap_or_else(|e| {
+            log::warn!("Ignoring touch calibration: {:#}", e);
+            Self::default()
+        })
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("creating {}", dir.display()))?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("writing {}", tmp.display()))?;
+        std::fs::rename(&tmp, path).with_context(|| format!("replacing {}", path.display()))
+    }
+
+    /// Same rotation, no affine correction: what calibration samples against.
+    pub fn uncorrected(&self) -> Self {
+        Self {
+            rotation: self.rotation,
+            ..Self::default()
+        }
+    }
+
+    pub fn apply(&self, x: f32, y: f32, width: u32, height: u32) -> (f32, f32) {
+        let (x, y) = self.rotate(x, y, width as f32, height as f32);
+        let [a, b, c, d, e, f] = self.matrix;
+        (a * x + b * y + c, d * x + e * y + f)
+    }
+
+    fn rotate(&self, x: f32, y: f32, width: f32, height: f32) -> (f32, f32) {
+        let (u, v) = (x / width, y / height);
+        let (u, v) = match self.rotation {
+            Rotation::Deg0 => (u, v),
+            Rotation::Deg90 => (1.0 - v, u),
+            Rotation::Deg180 => (1.0 - u, 1.0 - v),
+            Rotation::Deg270 => (v, 1.0 - u),
+        };
+        (u * width, v * height)
+    }
+}
+
+/// Collects one touch per crosshair in `TARGETS` and fits the affine
+/// correction to them by least squares.
+pub struct CalibrationSession {
+    width: u32,
+    height: u32,
+    rotation: Rotation,
+    samples: Vec<(f32, f32)>,
+}
+
+impl CalibrationSession {
+    pub fn new(width: u32, height: u32, rotation: Rotation) -> Self {
+        Self {
+            width,
+            height,
+            rotation,
+            samples: Vec::with_capacity(TARGETS.len()),
+        }
+    }
+
+    pub fn step(&self) -> usize {
+        self.samples.len()
+    }
+
+    /// Where the current crosshair is drawn, or `None` once all are done.
+    pub fn target(&self) -> Option<(i32, i32)> {
+        TARGETS
+            .get(self.samples.len())
+            .map(|&(u, v)| self.target_pixels(u, v))
+    }
+
+    /// Records the (rotated, uncorrected) position touched for the current
+    /// target. Returns whether all targets are done.
+    pub fn record(&mut self, x: f32, y: f32) -> bool {
+        if self.samples.len() < TARGETS.len() {
+            self.samples.push((x, y));
+        }
+        self.samples.len() == TARGETS.len()
+    }
+
+    pub fn finish(&self) -> Result<Calibration> {
+        if self.samples.len() < TARGETS.len() {
+            bail!("only {} of {} targets touched", self.samples.len(), TARGETS.len());
+        }
+
+        let expected: Vec<(f32, f32)> = TARGETS
+            .iter()
+            .map(|&(u, v)| (u * self.width as f32, v * self.height as f32))
+            .collect();
+        let [a, b, c] = fit_axis(&self.samples, expected.iter().map(|p| p.0))?;
+        let [d, e, f] = fit_axis(&self.samples, expected.iter().map(|p| p.1))?;
+
+        Ok(Calibration {
+            matrix: [a, b, c, d, e, f],
+            rotation: self.rotation,
+        })
+    }
+
+    fn target_pixels(&self, u: f32, v: f32) -> (i32, i32) {
+        ((u * self.width as f32) as i32, (v * self.height as f32) as i32)
+    }
+}
+
+/// Least-squares `(p, q, r)` for `target ≈ p*x + q*y + r` over all samples,
+/// solved from the 3x3 normal equations by Cramer's rule.
+fn fit_axis(samples: &[(f32, f32)], targets: impl Iterator<Item = f32>) -> Result<[f32; 3]> {
+    let (mut sxx, mut sxy, mut syy, mut sx, mut sy, n) =
+        (0.0f64, 0.0f64, 0.0f64, 0.0f64, 0.0f64, samples.len() as f64);
+    let (mut sxt, mut syt, mut st) = (0.0f64, 0.0f64, 0.0f64);
+
+    for (&(x, y), t) in samples.iter().zip(targets) {
+        let (x, y, t) = (x as f64, y as f64, t as f64);
+        sxx += x * x;
+        sxy += x * y;
+        syy += y * y;
+        sx += x;
+        sy += y;
+        sxt += x * t;
+        syt += y * t;
+        st += t;
+    }
+
+    let det = |m: [[f64; 3]; 3]| {
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    };
+
+    let m = [[sxx, sxy, sx], [sxy, syy, sy], [sx, sy, n]];
+    let rhs = [sxt, syt, st];
+    let d = det(m);
+    if d.abs() < 1e-6 {
+        bail!("touches are too close together to calibrate");
+    }
+
+    let mut solution = [0.0f32; 3];
+    for (col, value) in solution.iter_mut().enumerate() {
+        let mut mi = m;
+        for row in 0..3 {
+            mi[row][col] = rhs[row];
+        }
+        *value = (det(mi) / d) as f32;
+    }
+    Ok(solution)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WIDTH: u32 = 800;
+    const HEIGHT: u32 = 480;
+
+    fn close(a: (f32, f32), b: (f32, f32)) -> bool {
+        (a.0 - b.0).abs() < 0.01 && (a.1 - b.1).abs() < 0.01
+    }
+
+    #[test]
+    fn fit_recovers_a_known_transform() {
+        // The panel reads slightly scaled, skewed and offset: touching
+        // (x, y) reports `raw`. Calibration must undo that.
+        let raw = |x: f32, y: f32| (0.9 * x + 0.05 * y + 12.0, -0.03 * x + 1.1 * y - 8.0);
+        let mut session = CalibrationSession::new(WIDTH, HEIGHT, Rotation::Deg0);
+        for (step, &(u, v)) in TARGETS.iter().enumerate() {
+            assert_eq!(session.step(), step);
+            let (x, y) = raw(u * WIDTH as f32, v * HEIGHT as f32);
+            assert_eq!(session.record(x, y), step == TARGETS.len() - 1);
+        }
+        assert_eq!(session.target(), None);
+
+        let calibration = session.finish().unwrap();
+        for (x, y) in [(0.0, 0.0), (400.0, 240.0), (799.0, 10.0), (123.0, 456.0)] {
+            let (rx, ry) = raw(x, y);
+            let corrected = calibration.apply(rx, ry, WIDTH, HEIGHT);
+            assert!(close(corrected, (x, y)), "{:?} -> {:?}", (x, y), corrected);
+        }
+    }
+
+    #[test]
+    fn fit_needs_every_target_spread_out() {
+        let mut session = CalibrationSession::new(WIDTH, HEIGHT, Rotation::Deg0);
+        session.record(10.0, 10.0);
+        assert!(session.finish().is_err());
+        while !session.record(10.0, 10.0) {}
+        assert!(session.finish().is_err());
+    }
+
+    #[test]
+    fn rotations_round_trip() {
+        let point = (200.0, 120.0);
+        let rotate = |rotation, (x, y): (f32, f32)| {
+            Calibration {
+                rotation,
+                ..Calibration::default()
+            }
+            .apply(x, y, WIDTH, HEIGHT)
+        };
+        assert!(close(rotate(Rotation::Deg0, point), point));
+        assert!(close(rotate(Rotation::Deg90, point), (600.0, 120.0)));
+        assert!(close(rotate(Rotation::Deg180, point), (600.0, 360.0)));
+        assert!(close(rotate(Rotation::Deg270, point), (200.0, 360.0)));
+
+        // A quarter turn back the other way undoes one; two half turns cancel
+        let there = rotate(Rotation::Deg90, point);
+        assert!(close(rotate(Rotation::Deg270, there), point));
+        let there = rotate(Rotation::Deg180, point);
+        assert!(close(rotate(Rotation::Deg180, there), point));
+
+        for degrees in [0, 90, 180, 270] {
+            let rotation = Rotation::try_from(degrees).unwrap();
+            assert_eq!(u16::from(rotation), degrees);
+        }
+        assert!(Rotation::try_from(45).is_err());
+    }
+}
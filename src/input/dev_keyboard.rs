@@ -0,0 +1,180 @@
+use crate::input::gesture::{ButtonId, GestureKind};
+use crate::input::source::{InputSource, SourceHealth};
+use crate::input::InputEvent;
+use std::io::{self, BufRead};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::Instant;
+
+/// Drives the UI from a terminal when there is no hardware. stdin is read on
+/// its own thread so the render loop never waits for it; every character of
+/// a line is one key, and an empty line is Select.
+///
+/// | key       | event                        |
+/// |-----------|------------------------------|
+/// | w a s d   | navigate up/left/down/right  |
+/// | e, Enter  | select                       |
+/// | space     | activate                     |
+/// | q h m p   | back, home, menu, power      |
+/// | , .       | encoder 0 turn left/right    |
+/// | < >       | encoder 1 turn left/right    |
+/// | z x       | encoder 0/1 press            |
+/// | b         | button 0 press               |
+/// | 1 2 3     | APPS, PROFILE, SETTINGS      |
+/// | Q         | quit                         |
+///
+/// Two line forms take arguments: `t X Y` touches at (X, Y) and `"text`
+/// types `text`.
+pub struct DevKeyboard {
+    receiver: Receiver<InputEvent>,
+    closed: bool,
+}
+
+impl DevKeyboard {
+    pub fn spawn() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for line in io::stdin().lock().lines() {
+                let Ok(line) = line else {
+                    return;
+                };
+                for event in parse_line(&line) {
+                    if sender.send(event).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+        Self {
+            receiver,
+            closed: false,
+        }
+    }
+}
+
+impl InputSource for DevKeyboard {
+    fn name(&self) -> String {
+        "Terminal keyboard".to_string()
+    }
+
+    fn health(&self) -> SourceHealth {
+        if self.closed {
+            SourceHealth::Failed("stdin closed".to_string())
+        } else {
+            SourceHealth::Ok
+        }
+    }
+
+    fn poll(&mut self, _now: Instant) -> Vec<InputEvent> {
+        let mut events = Vec::new();
+        loop {
+            match self.receiver.try_recv() {
+                Ok(event) => events.push(event),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    self.closed = true;
+                    break;
+                }
+            }
+        }
+        events
+    }
+}
+
+fn parse_line(line: &str) -> Vec<InputEvent> {
+    if let Some(text) = line.strip_prefix('"') {
+        return text.chars().map(InputEvent::KeyPress).collect();
+    }
+    if let Some(coords) = line.strip_prefix("t ") {
+        let mut parts = coords.split_whitespace().map(str::parse::<i32>);
+        return match (parts.next(), parts.next()) {
+            (Some(Ok(x)), Some(Ok(y))) => vec![InputEvent::Touch(x, y)],
+            _ => {
+                log::warn!("Expected `t X Y`, got `{}`", line);
+                vec![]
+            }
+        };
+    }
+    if line.is_empty() {
+        return vec![InputEvent::Select];
+    }
+
+    line.chars().flat_map(map_key).collect()
+}
+
+fn map_key(c: char) -> Vec<InputEvent> {
+    match c {
+        'w' => vec![InputEvent::NavigateUp],
+        's' => vec![InputEvent::NavigateDown],
+        'a' => vec![InputEvent::NavigateLeft],
+        'd' => vec![InputEvent::NavigateRight],
+        'e' => vec![InputEvent::Select],
+        ' ' => vec![InputEvent::Activate],
+        'q' => vec![InputEvent::Back],
+        'h' => vec![InputEvent::Home],
+        'm' => vec![InputEvent::Menu],
+        'p' => vec![InputEvent::Power],
+        ',' => vec![InputEvent::EncoderTurned { id: 0, delta: -1 }],
+        '.' => vec![InputEvent::EncoderTurned { id: 0, delta: 1 }],
+        '<' => vec![InputEvent::EncoderTurned { id: 1, delta: -1 }],
+        '>' => vec![InputEvent::EncoderTurned { id: 1, delta: 1 }],
+        'z' => click(ButtonId::Encoder(0)),
+        'x' => click(ButtonId::Encoder(1)),
+        'b' => click(ButtonId::Button(0)),
+        '1' => vec![InputEvent::ButtonPressed("APPS".to_string())],
+        '2' => vec![InputEvent::ButtonPressed("PROFILE".to_string())],
+        '3' => vec![InputEvent::ButtonPressed("SETTINGS".to_string())],
+        'Q' => vec![InputEvent::Quit],
+        _ => vec![],
+    }
+}
+
+/// A press and release, so the gesture layer fires the bound press action.
+fn click(button: ButtonId) -> Vec<InputEvent> {
+    vec![
+        InputEvent::Gesture {
+            button,
+            kind: GestureKind::Pressed,
+        },
+        InputEvent::Gesture {
+            button,
+            kind: GestureKind::Released,
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_map_one_event_per_character() {
+        assert_eq!(
+            parse_line("ws."),
+            [
+                InputEvent::NavigateUp,
+                InputEvent::NavigateDown,
+                InputEvent::EncoderTurned { id: 0, delta: 1 },
+            ]
+        );
+        assert_eq!(parse_line(""), [InputEvent::Select]);
+        assert_eq!(parse_line("b").len(), 2);
+        // Unbound characters are dropped
+        assert_eq!(
+            parse_line("k2"),
+            [InputEvent::ButtonPressed("PROFILE".to_string())]
+        );
+        assert_eq!(
+            parse_line("\"hi"),
+            [InputEvent::KeyPress('h'), InputEvent::KeyPress('i')]
+        );
+    }
+
+    #[test]
+    fn touch_lines_take_two_coordinates() {
+        assert_eq!(parse_line("t 120 -5"), [InputEvent::Touch(120, -5)]);
+        assert!(parse_line("t 120").is_empty());
+        assert!(parse_line("t x 40").is_empty());
+        assert!(parse_line("t ").is_empty());
+    }
+}
@@ -1,79 +1,276 @@
-use rppal::gpio::{Gpio, InputPin, Level};
+use rppal::gpio::Level;
+use crate::input::bindings::{Action, EncoderBinding};
+use crate::input::gesture::{ButtonId, GestureKind};
+use crate::input::gpio::{GpioBackend, GpioPin};
+use crate::input::quadrature::RotationState;
+use crate::input::source::{InputSource, SourceHealth};
 use crate::input::InputEvent;
+use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
-const DEBOUNCE_MS: u64 = 50;
-
-pub enum RotaryEncoder {
-    Real(RealEncoder),
-    Fake,
-}
-
-pub struct RealEncoder {
+pub struct RotaryEncoder {
     id: u8,
-    pin_clk: InputPin,
-    pin_dt: InputPin,
-    pin_btn: InputPin,
-    last_clk: bool,
+    pin_clk: Box<dyn GpioPin>,
+    pin_dt: Box<dyn GpioPin>,
+    pin_btn: Box<dyn GpioPin>,
+    active_level: Level,
+    debounce: Duration,
+    turn: Option<(Action, Action)>,
+    rotation: Arc<Mutex<RotationState>>,
+    interrupts: bool,
+    /// Interrupts were configured but could not be set up.
+    polling_fallback: bool,
     last_btn: bool,
-    last_btn_time: Instant,
+    /// `None` until the first edge, which is never debounced.
+    last_btn_time: Option<Instant>,
 }
 
 impl RotaryEncoder {
-    pub fn new(id: u8, clk: u8, dt: u8, btn: u8) -> Self {
-        let gpio = Gpio::new().unwrap();
-        Self::Real(RealEncoder {
-            id,
-            pin_clk: gpio.get(clk).unwrap().into_input_pullup(),
-            pin_dt: gpio.get(dt).unwrap().into_input_pullup(),
-            pin_btn: gpio.get(btn).unwrap().into_input_pullup(),
-            last_clk: false,
+    pub fn new(binding: &EncoderBinding, gpio: &dyn GpioBackend) -> anyhow::Result<Self> {
+        let pull = binding.pull;
+        let active_level = pull.active_level();
+        let mut pin_clk = gpio.input(binding.clk, pull)?;
+        let mut pin_dt = gpio.input(binding.dt, pull)?;
+        let pin_btn = gpio.input(binding.btn, pull)?;
+
+        let rotation = Arc::new(Mutex::new(RotationState::new(
+            binding.step_mode,
+            binding.acceleration,
+            pin_clk.read() == active_level,
+            pin_dt.read() == active_level,
+        )));
+
+        // Interrupts catch transitions that happen between 16 ms frames; the
+        // levels they report are fed straight into the shared decoder.
+        let mut interrupts = binding.interrupts;
+        if interrupts {
+            let clk_state = Arc::clone(&rotation);
+            let dt_state = Arc::clone(&rotation);
+            let result = pin_clk
+                .set_interrupt(Box::new(move |level, at| {
+                    clk_state.lock().unwrap().set_clk(level == active_level, at);
+                }))
+                .and_then(|_| {
+                    pin_dt.set_interrupt(Box::new(move |level, at| {
+                        dt_state.lock().unwrap().set_dt(level == active_level, at);
+                    }))
+                });
+
+            if let Err(e) = result {
+                log::warn!(
+                    "Encoder {}: GPIO interrupts unavailable ({:#}), falling back to polling",
+                    binding.id, e
+                );
+                pin_clk.clear_interrupt();
+                pin_dt.clear_interrupt();
+                interrupts = false;
+            }
+        }
+
+        Ok(Self {
+            id: binding.id,
+            pin_clk,
+            pin_dt,
+            pin_btn,
+            active_level,
+            debounce: Duration::from_millis(binding.debounce_ms),
+            turn: binding
+                .clockwise
+                .clone()
+                .zip(binding.counter_clockwise.clone()),
+            rotation,
+            interrupts,
+            polling_fallback: binding.interrupts && !interrupts,
             last_btn: false,
-            last_btn_time: Instant::now(),
+            last_btn_time: None,
         })
     }
 
-    pub fn fake() -> Self {
-        Self::Fake
+    fn turn_events(&self, steps: i32) -> Vec<InputEvent> {
+        match &self.turn {
+            // Mapped actions fire once per (accelerated) step
+            Some((clockwise, counter_clockwise)) => {
+                let action = if steps > 0 { clockwise } else { counter_clockwise };
+                (0..steps.unsigned_abs())
+                    .filter_map(|_| action.to_event())
+                    .collect()
+            }
+            None => vec![InputEvent::EncoderTurned {
+                id: self.id,
+                delta: steps.clamp(i8::MIN as i32, i8::MAX as i32) as i8,
+            }],
+        }
+    }
+}
+
+impl InputSource for RotaryEncoder {
+    fn name(&self) -> String {
+        format!("Encoder {}", self.id)
     }
 
-    pub fn poll(&mut self) -> Vec<InputEvent> {
-        match self {
-            RotaryEncoder::Real(encoder) => encoder.poll(),
-            RotaryEncoder::Fake => vec![],
+    fn health(&self) -> SourceHealth {
+        if self.polling_fallback {
+            SourceHealth::Degraded("polling, GPIO interrupts unavailable".to_string())
+        } else {
+            SourceHealth::Ok
         }
     }
-}
 
-impl RealEncoder {
-    fn poll(&mut self) -> Vec<InputEvent> {
+    fn poll(&mut self, now: Instant) -> Vec<InputEvent> {
         let mut events = Vec::new();
 
-        let clk_now = self.pin_clk.read() == Level::Low;
-        let dt_now = self.pin_dt.read() == Level::Low;
-
-        if clk_now != self.last_clk {
-            self.last_clk = clk_now;
-            if clk_now {
-                let delta = if dt_now { -1 } else { 1 };
-                events.push(InputEvent::EncoderTurned {
-                    id: self.id,
-                    delta,
-                });
+        let steps = {
+            let mut rotation = self.rotation.lock().unwrap();
+            if !self.interrupts {
+                rotation.set_levels(
+                    self.pin_clk.read() == self.active_level,
+                    self.pin_dt.read() == self.active_level,
+                    now,
+                );
             }
+            rotation.take_pending()
+        };
+        if steps != 0 {
+            events.extend(self.turn_events(steps));
         }
 
-        let btn_now = self.pin_btn.read() == Level::Low;
-        let now = Instant::now();
-        if btn_now != self.last_btn && now.duration_since(self.last_btn_time) > Duration::from_millis(DEBOUNCE_MS) {
+        let btn_now = self.pin_btn.read() == self.active_level;
+        let settled = self
+            .last_btn_time
+            .is_none_or(|last| now.duration_since(last) > self.debounce);
+        if btn_now != self.last_btn && settled {
             self.last_btn = btn_now;
-            self.last_btn_time = now;
+            self.last_btn_time = Some(now);
 
-            if btn_now {
-                events.push(InputEvent::EncoderPressed(self.id));
-            }
+            events.push(InputEvent::Gesture {
+                button: ButtonId::Encoder(self.id),
+                kind: if btn_now {
+                    GestureKind::Pressed
+                } else {
+                    GestureKind::Released
+                },
+            });
         }
 
         events
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::gpio::SimulatedGpio;
+
+    const CLK: u8 = 17;
+    const DT: u8 = 27;
+    const BTN: u8 = 22;
+    const EDGE_GAP: Duration = Duration::from_millis(2);
+
+    fn encoder(gpio: &SimulatedGpio, extra: &str) -> RotaryEncoder {
+        let json = format!(
+            r#"{{ "id": 0, "clk": {}, "dt": {}, "btn": {}{} }}"#,
+            CLK, DT, BTN, extra
+        );
+        let binding: EncoderBinding = serde_json::from_str(&json).unwrap();
+        RotaryEncoder::new(&binding, gpio).unwrap()
+    }
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    /// Advances and polls once a millisecond up to `until`.
+    fn run(gpio: &SimulatedGpio, encoder: &mut RotaryEncoder, until: u64) -> Vec<InputEvent> {
+        (0..=until)
+            .flat_map(|t| {
+                let now = gpio.start() + ms(t);
+                gpio.advance_to(now);
+                encoder.poll(now)
+            })
+            .collect()
+    }
+
+    fn turned(delta: i8) -> InputEvent {
+        InputEvent::EncoderTurned { id: 0, delta }
+    }
+
+    #[test]
+    fn interrupts_catch_turns_between_polls() {
+        let gpio = SimulatedGpio::new();
+        let mut encoder = encoder(&gpio, r#", "interrupts": true"#);
+        gpio.schedule_detent(ms(10), CLK, DT, true, EDGE_GAP);
+        gpio.schedule_detent(ms(100), CLK, DT, false, EDGE_GAP);
+        gpio.schedule_detent(ms(200), CLK, DT, false, EDGE_GAP);
+
+        // One poll per frame sees none of the edges, but every detent counts
+        let frame = gpio.start() + ms(50);
+        assert_eq!(gpio.advance_to(frame), 8);
+        assert_eq!(encoder.poll(frame), [turned(1)]);
+        let frame = gpio.start() + ms(300);
+        assert_eq!(gpio.advance_to(frame), 0);
+        assert_eq!(encoder.poll(frame), [turned(-2)]);
+        assert_eq!(encoder.health(), SourceHealth::Ok);
+    }
+
+    #[test]
+    fn interrupts_time_steps_by_their_edges() {
+        // Two slow clicks applied at once must not read as a fast spin
+        let gpio = SimulatedGpio::new();
+        let mut encoder = encoder(
+            &gpio,
+            r#", "interrupts": true,
+                 "acceleration": { "slow_ms": 120, "fast_ms": 15, "max_multiplier": 8 }"#,
+        );
+        gpio.schedule_detent(ms(0), CLK, DT, true, EDGE_GAP);
+        gpio.schedule_detent(ms(300), CLK, DT, true, EDGE_GAP);
+        let frame = gpio.start() + ms(400);
+        gpio.advance_to(frame);
+        assert_eq!(encoder.poll(frame), [turned(2)]);
+    }
+
+    #[test]
+    fn polling_follows_both_directions() {
+        let gpio = SimulatedGpio::new();
+        let mut encoder = encoder(
+            &gpio,
+            r#", "clockwise": "navigate_down", "counter_clockwise": "navigate_up""#,
+        );
+        gpio.schedule_detent(ms(10), CLK, DT, true, EDGE_GAP);
+        gpio.schedule_detent(ms(50), CLK, DT, false, EDGE_GAP);
+        gpio.schedule_detent(ms(90), CLK, DT, true, EDGE_GAP);
+        assert_eq!(
+            run(&gpio, &mut encoder, 150),
+            [
+                InputEvent::NavigateDown,
+                InputEvent::NavigateUp,
+                InputEvent::NavigateDown
+            ]
+        );
+    }
+
+    #[test]
+    fn button_bounce_is_ignored() {
+        let gpio = SimulatedGpio::new();
+        let mut encoder = encoder(&gpio, r#", "debounce_ms": 20"#);
+        // Pressed with contact chatter, then released cleanly
+        for (at, level) in [
+            (10, Level::Low),
+            (12, Level::High),
+            (14, Level::Low),
+            (80, Level::High),
+        ] {
+            gpio.schedule(ms(at), BTN, level);
+        }
+        let kinds: Vec<GestureKind> = run(&gpio, &mut encoder, 150)
+            .into_iter()
+            .map(|event| match event {
+                InputEvent::Gesture {
+                    button: ButtonId::Encoder(0),
+                    kind,
+                } => kind,
+                other => panic!("unexpected {:?}", other),
+            })
+            .collect();
+        assert_eq!(kinds, [GestureKind::Pressed, GestureKind::Released]);
+    }
+}
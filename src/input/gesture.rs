@@ -0,0 +1,500 @@
+use crate::input::bindings::{Action, InputBindings};
+use crate::input::InputEvent;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ButtonId {
+    Button(u8),
+    Encoder(u8),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GestureKind {
+    /// Debounced contact closed.
+    Pressed,
+    /// Debounced contact opened.
+    Released,
+    LongPress,
+    DoublePress,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct GestureTiming {
+    pub long_press_ms: u64,
+    /// Longest gap between the first release and the second press.
+    pub double_press_ms: u64,
+}
+
+impl Default for GestureTiming {
+    fn default() -> Self {
+        Self {
+            long_press_ms: 600,
+            double_press_ms: 300,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChordBinding {
+    pub buttons: Vec<ButtonId>,
+    pub action: Action,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ButtonActions {
+    pub press: Option<Action>,
+    pub long_press: Option<Action>,
+    pub double_press: Option<Action>,
+}
+
+#[derive(Debug, Default)]
+struct ButtonState {
+    down_since: Option<Instant>,
+    /// A long press, chord or immediate press already used this hold.
+    consumed: bool,
+    long_reported: bool,
+    second_press: bool,
+    /// Release time of a click that may still become a double press.
+    pending_click: Option<Instant>,
+    cycle_index: usize,
+}
+
+/// Turns the raw `Pressed`/`Released` edges from buttons and encoder buttons
+/// into press, long-press, double-press and chord actions.
+///
+/// A plain press fires on the falling edge, as before, unless the button also
+/// has a long press, a double press or takes part in a chord; then it waits
+/// for the release (or the double-press window) to tell them apart.
+pub struct GestureDetector {
+    long_press: Duration,
+    double_press: Duration,
+    actions: HashMap<ButtonId, ButtonActions>,
+    chords: Vec<ChordBinding>,
+    fired_chords: Vec<bool>,
+    states: HashMap<ButtonId, ButtonState>,
+}
+
+impl GestureDetector {
+    pub fn new(bindings: &InputBindings) -> Self {
+        let mut actions = HashMap::new();
+        for button in &bindings.buttons {
+            actions.insert(
+                ButtonId::Button(button.id),
+                ButtonActions {
+                    press: Some(button.press.clone()),
+                    long_press: button.long_press.clone(),
+                    double_press: button.double_press.clone(),
+                },
+            );
+        }
+        for encoder in &bindings.encoders {
+            actions.insert(
+                ButtonId::Encoder(encoder.id),
+                ButtonActions {
+                    press: Some(
+                        encoder
+                            .press
+                            .clone()
+                            .unwrap_or(Action::EncoderPressed(encoder.id)),
+                    ),
+                    long_press: encoder.long_press.clone(),
+                    double_press: encoder.double_press.clone(),
+                },
+            );
+        }
+
+        Self {
+            long_press: Duration::from_millis(bindings.gestures.long_press_ms),
+            double_press: Duration::from_millis(bindings.gestures.double_press_ms),
+            actions,
+            fired_chords: vec![false; bindings.chords.len()],
+            chords: bindings.chords.clone(),
+            states: HashMap::new(),
+        }
+    }
+
+    /// Expands the edge events in `events` and adds any gestures that became
+    /// due with time (long presses, expired double-press windows).
+    pub fn process(&mut self, events: Vec<InputEvent>, now: Instant) -> Vec<InputEvent> {
+        let mut out = Vec::new();
+        for event in events {
+            match event {
+                InputEvent::Gesture { button, kind: GestureKind::Pressed } => {
+                    out.push(event);
+                    self.on_pressed(button, now, &mut out);
+                }
+                InputEvent::Gesture { button, kind: GestureKind::Released } => {
+                    out.push(event);
+                    self.on_released(button, now, &mut out);
+                }
+                other => out.push(other),
+            }
+        }
+        self.tick(now, &mut out);
+        out
+    }
+
+    fn on_pressed(&mut self, button: ButtonId, now: Instant, out: &mut Vec<InputEvent>) {
+        let immediate = self.fires_immediately(button);
+        let pending = self
+            .states
+            .get_mut(&button)
+            .and_then(|state| state.pending_click.take());
+        let second_press =
+            pending.is_some_and(|released| now.duration_since(released) <= self.double_press);
+        // A click whose window ran out before this poll is still a press
+        if pending.is_some() && !second_press {
+            self.fire_press(button, out);
+        }
+
+        let state = self.states.entry(button).or_default();
+        state.down_since = Some(now);
+        state.consumed = false;
+        state.long_reported = false;
+        state.second_press = second_press;
+
+        if immediate {
+            state.consumed = true;
+            self.fire_press(button, out);
+        }
+
+        self.check_chords(out);
+    }
+
+    fn on_released(&mut self, button: ButtonId, now: Instant, out: &mut Vec<InputEvent>) {
+        let has_double = self.action(button, |a| &a.double_press).is_some();
+        let state = self.states.entry(button).or_default();
+        state.down_since = None;
+        let consumed = std::mem::take(&mut state.consumed);
+        let second_press = std::mem::take(&mut state.second_press);
+
+        for (chord, fired) in self.chords.iter().zip(self.fired_chords.iter_mut()) {
+            if chord.buttons.contains(&button) {
+                *fired = false;
+            }
+        }
+
+        if consumed {
+            return;
+        }
+        if second_press {
+            out.push(InputEvent::Gesture {
+                button,
+                kind: GestureKind::DoublePress,
+            });
+            out.extend(self.action(button, |a| &a.double_press).and_then(|a| a.to_event()));
+        } else if has_double {
+            state.pending_click = Some(now);
+        } else {
+            self.fire_press(button, out);
+        }
+    }
+
+    fn tick(&mut self, now: Instant, out: &mut Vec<InputEvent>) {
+        let mut long_presses = Vec::new();
+        let mut expired_clicks = Vec::new();
+
+        for (button, state) in self.states.iter_mut() {
+            if let Some(since) = state.down_since {
+                if !state.long_reported && now.duration_since(since) >= self.long_press {
+                    state.long_reported = true;
+                    long_presses.push(*button);
+                }
+            }
+            if let Some(released) = state.pending_click {
+                if now.duration_since(released) > self.double_press {
+                    state.pending_click = None;
+                    expired_clicks.push(*button);
+                }
+            }
+        }
+
+        for button in long_presses {
+            out.push(InputEvent::Gesture {
+                button,
+                kind: GestureKind::LongPress,
+            });
+            if let Some(action) = self.action(button, |a| &a.long_press) {
+                if let Some(state) = self.states.get_mut(&button) {
+                    if state.consumed {
+                        continue;
+                    }
+                    state.consumed = true;
+                }
+                out.extend(action.to_event());
+            }
+        }
+        for button in expired_clicks {
+            self.fire_press(button, out);
+        }
+    }
+
+    fn check_chords(&mut self, out: &mut Vec<InputEvent>) {
+        for (i, chord) in self.chords.iter().enumerate() {
+            if self.fired_chords[i] {
+                continue;
+            }
+            let all_held = chord.buttons.iter().all(|b| {
+                self.states
+                    .get(b)
+                    .is_some_and(|state| state.down_since.is_some())
+            });
+            if !all_held {
+                continue;
+            }
+
+            self.fired_chords[i] = true;
+            for button in &chord.buttons {
+                if let Some(state) = self.states.get_mut(button) {
+                    state.consumed = true;
+                }
+            }
+            out.extend(chord.action.to_event());
+        }
+    }
+
+    fn fire_press(&mut self, button: ButtonId, out: &mut Vec<InputEvent>) {
+        match self.action(button, |a| &a.press) {
+            Some(Action::Cycle(labels)) => {
+                let state = self.states.entry(button).or_default();
+                state.cycle_index = (state.cycle_index + 1) % labels.len();
+                out.push(InputEvent::ButtonPressed(labels[state.cycle_index].clone()));
+            }
+            Some(action) => out.extend(action.to_event()),
+            None => {}
+        }
+    }
+
+    fn fires_immediately(&self, button: ButtonId) -> bool {
+        self.action(button, |a| &a.long_press).is_none()
+            && self.action(button, |a| &a.double_press).is_none()
+            && !self.chords.iter().any(|c| c.buttons.contains(&button))
+    }
+
+    fn action(
+        &self,
+        button: ButtonId,
+        slot: impl Fn(&ButtonActions) -> &Option<Action>,
+    ) -> Option<Action> {
+        self.actions.get(&button).and_then(|a| slot(a).clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUTTON: ButtonId = ButtonId::Button(0);
+
+    fn detector(json: &str) -> GestureDetector {
+        let bindings: InputBindings = serde_json::from_str(json).unwrap();
+        bindings.validate().unwrap();
+        GestureDetector::new(&bindings)
+    }
+
+    fn edge(button: ButtonId, kind: GestureKind) -> InputEvent {
+        InputEvent::Gesture { button, kind }
+    }
+
+    /// Feeds `events` at `at` and keeps only the resulting actions.
+    fn actions(
+        detector: &mut GestureDetector,
+        events: Vec<InputEvent>,
+        at: Instant,
+    ) -> Vec<InputEvent> {
+        detector
+            .process(events, at)
+            .into_iter()
+            .filter(|event| !matches!(event, InputEvent::Gesture { .. }))
+            .collect()
+    }
+
+    fn ms(start: Instant, ms: u64) -> Instant {
+        start + Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn plain_press_fires_on_the_falling_edge() {
+        let mut detector = detector(r#"{ "buttons": [{ "id": 0, "pin": 5, "press": "select" }] }"#);
+        let start = Instant::now();
+        let pressed = actions(
+            &mut detector,
+            vec![edge(BUTTON, GestureKind::Pressed)],
+            start,
+        );
+        assert_eq!(pressed, [InputEvent::Select]);
+        let released = actions(
+            &mut detector,
+            vec![edge(BUTTON, GestureKind::Released)],
+            ms(start, 50),
+        );
+        assert!(released.is_empty());
+    }
+
+    #[test]
+    fn long_press_replaces_the_press() {
+        let mut detector = detector(
+            r#"{ "buttons": [{ "id": 0, "pin": 5, "press": "select", "long_press": "power" }],
+                 "gestures": { "long_press_ms": 600, "double_press_ms": 300 } }"#,
+        );
+        let start = Instant::now();
+        assert!(actions(
+            &mut detector,
+            vec![edge(BUTTON, GestureKind::Pressed)],
+            start
+        )
+        .is_empty());
+        assert!(actions(&mut detector, Vec::new(), ms(start, 599)).is_empty());
+
+        let held = detector.process(Vec::new(), ms(start, 600));
+        assert_eq!(
+            held,
+            [edge(BUTTON, GestureKind::LongPress), InputEvent::Power]
+        );
+        assert!(actions(&mut detector, Vec::new(), ms(start, 900)).is_empty());
+        let released = actions(
+            &mut detector,
+            vec![edge(BUTTON, GestureKind::Released)],
+            ms(start, 1000),
+        );
+        assert!(released.is_empty());
+
+        // A short press still presses, on release
+        assert!(actions(
+            &mut detector,
+            vec![edge(BUTTON, GestureKind::Pressed)],
+            ms(start, 2000)
+        )
+        .is_empty());
+        let released = actions(
+            &mut detector,
+            vec![edge(BUTTON, GestureKind::Released)],
+            ms(start, 2100),
+        );
+        assert_eq!(released, [InputEvent::Select]);
+    }
+
+    #[test]
+    fn double_press_within_the_window() {
+        let mut detector = detector(
+            r#"{ "buttons": [{ "id": 0, "pin": 5, "press": "select", "double_press": "menu" }],
+                 "gestures": { "long_press_ms": 600, "double_press_ms": 300 } }"#,
+        );
+        let start = Instant::now();
+        let click = vec![
+            edge(BUTTON, GestureKind::Pressed),
+            edge(BUTTON, GestureKind::Released),
+        ];
+        assert!(actions(&mut detector, click.clone(), start).is_empty());
+        let second = detector.process(click.clone(), ms(start, 200));
+        assert_eq!(second.last(), Some(&InputEvent::Menu));
+        assert!(second.contains(&edge(BUTTON, GestureKind::DoublePress)));
+        assert!(actions(&mut detector, Vec::new(), ms(start, 1000)).is_empty());
+
+        // Too slow: two presses
+        assert!(actions(&mut detector, click.clone(), ms(start, 2000)).is_empty());
+        assert!(actions(&mut detector, Vec::new(), ms(start, 2300)).is_empty());
+        assert_eq!(
+            actions(&mut detector, Vec::new(), ms(start, 2301)),
+            [InputEvent::Select]
+        );
+        assert!(actions(&mut detector, click, ms(start, 2400)).is_empty());
+        assert_eq!(
+            actions(&mut detector, Vec::new(), ms(start, 2800)),
+            [InputEvent::Select]
+        );
+    }
+
+    #[test]
+    fn an_expired_click_still_fires_when_the_next_press_comes_first() {
+        let mut detector = detector(
+            r#"{ "buttons": [{ "id": 0, "pin": 5, "press": "select", "double_press": "menu" }],
+                 "gestures": { "long_press_ms": 600, "double_press_ms": 300 } }"#,
+        );
+        let start = Instant::now();
+        let click = vec![
+            edge(BUTTON, GestureKind::Pressed),
+            edge(BUTTON, GestureKind::Released),
+        ];
+        assert!(actions(&mut detector, click, start).is_empty());
+        // No poll while the window ran out; the next press arrives first
+        assert_eq!(
+            actions(
+                &mut detector,
+                vec![edge(BUTTON, GestureKind::Pressed)],
+                ms(start, 400)
+            ),
+            [InputEvent::Select]
+        );
+        assert!(actions(
+            &mut detector,
+            vec![edge(BUTTON, GestureKind::Released)],
+            ms(start, 450)
+        )
+        .is_empty());
+        assert_eq!(
+            actions(&mut detector, Vec::new(), ms(start, 800)),
+            [InputEvent::Select]
+        );
+    }
+
+    #[test]
+    fn chord_fires_once_and_swallows_the_presses() {
+        let mut detector = detector(
+            r#"{ "encoders": [{ "id": 0, "clk": 17, "dt": 27, "btn": 22 },
+                              { "id": 1, "clk": 23, "dt": 24, "btn": 25 }],
+                 "chords": [{ "buttons": [{ "encoder": 0 }, { "encoder": 1 }], "action": "home" }] }"#,
+        );
+        let (left, right) = (ButtonId::Encoder(0), ButtonId::Encoder(1));
+        let start = Instant::now();
+        assert!(actions(&mut detector, vec![edge(left, GestureKind::Pressed)], start).is_empty());
+        let both = actions(
+            &mut detector,
+            vec![edge(right, GestureKind::Pressed)],
+            ms(start, 40),
+        );
+        assert_eq!(both, [InputEvent::Home]);
+        let released = vec![
+            edge(left, GestureKind::Released),
+            edge(right, GestureKind::Released),
+        ];
+        assert!(actions(&mut detector, released, ms(start, 100)).is_empty());
+
+        // On its own an encoder button presses as usual
+        assert!(actions(
+            &mut detector,
+            vec![edge(left, GestureKind::Pressed)],
+            ms(start, 500)
+        )
+        .is_empty());
+        let released = actions(
+            &mut detector,
+            vec![edge(left, GestureKind::Released)],
+            ms(start, 550),
+        );
+        assert_eq!(released, [InputEvent::EncoderPressed(0)]);
+    }
+
+    #[test]
+    fn cycle_steps_through_its_labels() {
+        let mut detector = detector(
+            r#"{ "buttons": [{ "id": 0, "pin": 5, "press": { "cycle": ["A", "B", "C"] } }] }"#,
+        );
+        let start = Instant::now();
+        let labels: Vec<InputEvent> = (0..4)
+            .flat_map(|i| {
+                let click = vec![
+                    edge(BUTTON, GestureKind::Pressed),
+                    edge(BUTTON, GestureKind::Released),
+                ];
+                actions(&mut detector, click, ms(start, i * 100))
+            })
+            .collect();
+        let label = |l: &str| InputEvent::ButtonPressed(l.to_string());
+        assert_eq!(labels, [label("B"), label("C"), label("A"), label("B")]);
+    }
+}
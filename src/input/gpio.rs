@@ -0,0 +1,224 @@
+use crate::input::bindings::PullMode;
+use anyhow::{Context, Result};
+use rppal::gpio::{Gpio, InputPin, Level, Trigger};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Called with the new level and the time of the edge on every edge.
+pub type InterruptCallback = Box<dyn FnMut(Level, Instant) + Send>;
+
+/// Where encoders and buttons get their pins from: the Pi's GPIO through
+/// rppal, or `SimulatedGpio` anywhere else.
+pub trait GpioBackend {
+    fn input(&self, pin: u8, pull: PullMode) -> Result<Box<dyn GpioPin>>;
+}
+
+pub trait GpioPin {
+    fn read(&self) -> Level;
+
+    /// Calls `callback` on both edges from a background thread (or, when
+    /// simulated, from whoever changes the level).
+    fn set_interrupt(&mut self, callback: InterruptCallback) -> Result<()>;
+
+    fn clear_interrupt(&mut self);
+}
+
+pub struct RppalGpio {
+    gpio: Gpio,
+}
+
+impl RppalGpio {
+    /// Fails off a Raspberry Pi or without access to `/dev/gpiomem`.
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            gpio: Gpio::new().context("accessing GPIO")?,
+        })
+    }
+}
+
+impl GpioBackend for RppalGpio {
+    fn input(&self, pin: u8, pull: PullMode) -> Result<Box<dyn GpioPin>> {
+        let pin = self
+            .gpio
+            .get(pin)
+            .with_context(|| format!("claiming GPIO {}", pin))?;
+        Ok(Box::new(pull.configure(pin)))
+    }
+}
+
+impl GpioPin for InputPin {
+    fn read(&self) -> Level {
+        InputPin::read(self)
+    }
+
+    fn set_interrupt(&mut self, mut callback: InterruptCallback) -> Result<()> {
+        // rppal only reports the level; the callback runs as the edge arrives
+        self.set_async_interrupt(Trigger::Both, move |level| callback(level, Instant::now()))?;
+        Ok(())
+    }
+
+    fn clear_interrupt(&mut self) {
+        let _ = self.clear_async_interrupt();
+    }
+}
+
+/// Shared so it can be called after the state lock is released.
+type SharedCallback = Arc<Mutex<InterruptCallback>>;
+
+#[derive(Default)]
+struct SimulatedState {
+    levels: HashMap<u8, Level>,
+    interrupts: HashMap<u8, SharedCallback>,
+    /// Pending changes as (due, pin, level), kept sorted by `due`.
+    script: VecDeque<(Instant, u8, Level)>,
+}
+
+impl SimulatedState {
+    /// Returns the pin's interrupt if the level changed. The caller fires it
+    /// once the lock is dropped, so callbacks may use the pins themselves.
+    fn set_level(&mut self, pin: u8, level: Level) -> Option<SharedCallback> {
+        if self.levels.insert(pin, level) == Some(level) {
+            return None;
+        }
+        self.interrupts.get(&pin).cloned()
+    }
+}
+
+fn fire(callback: Option<SharedCallback>, level: Level, at: Instant) {
+    if let Some(callback) = callback {
+        (callback.lock().unwrap())(level, at);
+    }
+}
+
+/// In-memory pins whose levels are set directly or scripted over time, so
+/// debounce, quadrature decoding and gestures can run without a Pi. Clones
+/// share the same pins. Pins start at their idle level: high with a pull-up,
+/// low with a pull-down.
+#[derive(Clone)]
+pub struct SimulatedGpio {
+    state: Arc<Mutex<SimulatedState>>,
+    start: Instant,
+}
+
+impl SimulatedGpio {
+    pub fn new() -> Self {
+        Self::starting_at(Instant::now())
+    }
+
+    /// `start` is the time scripted offsets count from.
+    pub fn starting_at(start: Instant) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(SimulatedState::default())),
+            start,
+        }
+    }
+
+    pub fn start(&self) -> Instant {
+        self.start
+    }
+
+    pub fn level(&self, pin: u8) -> Option<Level> {
+        self.state.lock().unwrap().levels.get(&pin).copied()
+    }
+
+    /// Changes a level now, firing the pin's interrupt if it changed.
+    pub fn set_level(&self, pin: u8, level: Level) {
+        let callback = self.state.lock().unwrap().set_level(pin, level);
+        fire(callback, level, Instant::now());
+    }
+
+    /// Queues a level change `at` after `start`; it happens on the first
+    /// `advance_to` at or past that time.
+    pub fn schedule(&self, at: Duration, pin: u8, level: Level) {
+        let due = self.start + at;
+        let mut state = self.state.lock().unwrap();
+        let index = state.script.partition_point(|&(time, _, _)| time <= due);
+        state.script.insert(index, (due, pin, level));
+    }
+
+    /// Queues a full quadrature cycle on `clk`/`dt` (active low), one edge
+    /// every `edge_gap` starting `at`. Clockwise leads with `clk`.
+    pub fn schedule_detent(&self, at: Duration, clk: u8, dt: u8, clockwise: bool, edge_gap: Duration) {
+        let (first, second) = if clockwise { (clk, dt) } else { (dt, clk) };
+        let edges = [
+            (first, Level::Low),
+            (second, Level::Low),
+            (first, Level::High),
+            (second, Level::High),
+        ];
+        for (i, (pin, level)) in edges.into_iter().enumerate() {
+            self.schedule(at + edge_gap * i as u32, pin, level);
+        }
+    }
+
+    /// Applies every scripted change due by `now`, in order, each reported
+    /// to interrupts at its scheduled time. Returns how many are still
+    /// pending.
+    pub fn advance_to(&self, now: Instant) -> usize {
+        loop {
+            let (due, level, callback) = {
+                let mut state = self.state.lock().unwrap();
+                match state.script.front() {
+                    Some(&(due, pin, level)) if due <= now => {
+                        state.script.pop_front();
+                        (due, level, state.set_level(pin, level))
+                    }
+                    _ => return state.script.len(),
+                }
+            };
+            fire(callback, level, due);
+        }
+    }
+}
+
+impl Default for SimulatedGpio {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GpioBackend for SimulatedGpio {
+    fn input(&self, pin: u8, pull: PullMode) -> Result<Box<dyn GpioPin>> {
+        self.state
+            .lock()
+            .unwrap()
+            .levels
+            .entry(pin)
+            .or_insert(!pull.active_level());
+        Ok(Box::new(SimulatedPin {
+            pin,
+            state: Arc::clone(&self.state),
+        }))
+    }
+}
+
+struct SimulatedPin {
+    pin: u8,
+    state: Arc<Mutex<SimulatedState>>,
+}
+
+impl GpioPin for SimulatedPin {
+    fn read(&self) -> Level {
+        self.state
+            .lock()
+            .unwrap()
+            .levels
+            .get(&self.pin)
+            .copied()
+            .unwrap_or(Level::High)
+    }
+
+    fn set_interrupt(&mut self, callback: InterruptCallback) -> Result<()> {
+        self.state
+            .lock()
+            .unwrap()
+            .interrupts
+            .insert(self.pin, Arc::new(Mutex::new(callback)));
+        Ok(())
+    }
+
+    fn clear_interrupt(&mut self) {
+        self.state.lock().unwrap().interrupts.remove(&self.pin);
+    }
+}
@@ -0,0 +1,291 @@
+use evdev::{AbsoluteAxisType, AttributeSetRef, Device, Key};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::fs::read_dir;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const INPUT_DIR: &str = "/dev/input";
+const RESCAN_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeviceKind {
+    Keyboard,
+    Gamepad,
+    Remote,
+}
+
+impl fmt::Display for DeviceKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            DeviceKind::Keyboard => "Keyboard",
+            DeviceKind::Gamepad => "Gamepad",
+            DeviceKind::Remote => "Remote",
+        })
+    }
+}
+
+impl DeviceKind {
+    fn classify(device: &Device) -> Option<Self> {
+        device.supported_keys().and_then(Self::from_keys)
+    }
+
+    fn from_keys(keys: &AttributeSetRef<Key>) -> Option<Self> {
+        if keys.contains(Key::KEY_ENTER) && keys.contains(Key::KEY_A) {
+            Some(DeviceKind::Keyboard)
+        } else if keys.contains(Key::BTN_SOUTH) {
+            Some(DeviceKind::Gamepad)
+        } else if keys.contains(Key::KEY_OK)
+            || keys.contains(Key::KEY_SELECT)
+            || (keys.contains(Key::KEY_UP) && keys.contains(Key::KEY_DOWN))
+        {
+            Some(DeviceKind::Remote)
+        } else {
+            None
+        }
+    }
+}
+
+enum DeviceMessage {
+    Event(PathBuf, evdev::InputEvent),
+    Detached(PathBuf),
+}
+
+pub enum WatcherEvent {
+    Attached {
+        path: PathBuf,
+        name: String,
+        kind: DeviceKind,
+    },
+    Input(PathBuf, evdev::InputEvent),
+    Detached(PathBuf),
+}
+
+/// Watches `/dev/input` for `event*` nodes coming and going. Each keyboard,
+/// gamepad or remote gets a reader thread, so a blocking read never stalls
+/// the render loop and an unplugged device simply ends its thread.
+pub struct DeviceWatcher {
+    sender: Sender<DeviceMessage>,
+    receiver: Receiver<DeviceMessage>,
+    attached: HashSet<PathBuf>,
+    /// Nodes that are not input devices we use.
+    ignored: HashSet<PathBuf>,
+    /// Nodes that could not be opened. They are tried again on every scan,
+    /// as udev may set a new node's permissions a moment after it appears;
+    /// kept so the failure is only logged once.
+    unopened: HashSet<PathBuf>,
+    last_scan: Option<Instant>,
+    /// Why `/dev/input` could not be listed on the last scan.
+    scan_error: Option<String>,
+}
+
+impl Default for DeviceWatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DeviceWatcher {
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self {
+            sender,
+            receiver,
+            attached: HashSet::new(),
+            ignored: HashSet::new(),
+            unopened: HashSet::new(),
+            last_scan: None,
+            scan_error: None,
+        }
+    }
+
+    pub fn poll(&mut self) -> Vec<WatcherEvent> {
+        let mut events = Vec::new();
+
+        let now = Instant::now();
+        if self
+            .last_scan
+            .is_none_or(|last| now.duration_since(last) >= RESCAN_INTERVAL)
+        {
+            self.last_scan = Some(now);
+            self.scan(&mut events);
+        }
+
+        while let Ok(message) = self.receiver.try_recv() {
+            match message {
+                DeviceMessage::Event(path, event) => events.push(WatcherEvent::Input(path, event)),
+                DeviceMessage::Detached(path) => {
+                    self.attached.remove(&path);
+                    events.push(WatcherEvent::Detached(path));
+                }
+            }
+        }
+
+        events
+    }
+
+    pub fn scan_error(&self) -> Option<&str> {
+        self.scan_error.as_deref()
+    }
+
+    fn scan(&mut self, events: &mut Vec<WatcherEvent>) {
+        self.scan_dir(Path::new(INPUT_DIR), events);
+    }
+
+    fn scan_dir(&mut self, dir: &Path, events: &mut Vec<WatcherEvent>) {
+        let entries = match read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) => {
+                if self.scan_error.is_none() {
+                    log::warn!("Cannot list {}: {}", dir.display(), e);
+                }
+                self.scan_error = Some(format!("cannot list {}: {}", dir.display(), e));
+                return;
+            }
+        };
+        self.scan_error = None;
+
+        let present: HashSet<PathBuf> = entries
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| is_event_node(path))
+            .collect();
+
+        // Forget ignored nodes that went away so a new device reusing the
+        // name is looked at again.
+        self.ignored.retain(|path| present.contains(path));
+        self.unopened.retain(|path| present.contains(path));
+
+        for path in present {
+            if self.attached.contains(&path) || self.ignored.contains(&path) {
+                continue;
+            }
+
+            let device = match Device::open(&path) {
+                Ok(device) => device,
+                Err(e) => {
+                    if !self.unopened.contains(&path) {
+                        log::warn!("Cannot open {}: {}", path.display(), e);
+                        self.unopened.insert(path);
+                    }
+                    continue;
+                }
+            };
+            self.unopened.remove(&path);
+            let Some(kind) = DeviceKind::classify(&device) else {
+                self.ignored.insert(path);
+                continue;
+            };
+
+            let name = device.name().unwrap_or("Unknown device").to_string();
+            self.attached.insert(path.clone());
+            spawn_reader(path.clone(), device, self.sender.clone());
+            events.push(WatcherEvent::Attached { path, name, kind });
+        }
+    }
+}
+
+fn is_event_node(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|s| s.starts_with("event"))
+}
+
+fn spawn_reader(path: PathBuf, mut device: Device, sender: Sender<DeviceMessage>) {
+    thread::spawn(move || loop {
+        match device.fetch_events() {
+            Ok(events) => {
+                for event in events {
+                    if sender.send(DeviceMessage::Event(path.clone(), event)).is_err() {
+                        return;
+                    }
+                }
+            }
+            Err(_) => {
+                let _ = sender.send(DeviceMessage::Detached(path));
+                return;
+            }
+        }
+    });
+}
+
+/// Gamepad d-pads often report as a hat axis instead of buttons.
+pub fn hat_direction(axis: AbsoluteAxisType, value: i32) -> Option<Key> {
+    match (axis, value.signum()) {
+        (AbsoluteAxisType::ABS_HAT0X, -1) => Some(Key::BTN_DPAD_LEFT),
+        (AbsoluteAxisType::ABS_HAT0X, 1) => Some(Key::BTN_DPAD_RIGHT),
+        (AbsoluteAxisType::ABS_HAT0Y, -1) => Some(Key::BTN_DPAD_UP),
+        (AbsoluteAxisType::ABS_HAT0Y, 1) => Some(Key::BTN_DPAD_DOWN),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use evdev::AttributeSet;
+
+    fn kind(keys: &[Key]) -> Option<DeviceKind> {
+        let mut set = AttributeSet::<Key>::new();
+        for &key in keys {
+            set.insert(key);
+        }
+        DeviceKind::from_keys(&set)
+    }
+
+    #[test]
+    fn devices_are_classified_by_their_keys() {
+        assert_eq!(
+            kind(&[Key::KEY_A, Key::KEY_ENTER, Key::KEY_UP, Key::KEY_DOWN]),
+            Some(DeviceKind::Keyboard)
+        );
+        assert_eq!(
+            kind(&[Key::BTN_SOUTH, Key::BTN_EAST]),
+            Some(DeviceKind::Gamepad)
+        );
+        assert_eq!(kind(&[Key::KEY_OK]), Some(DeviceKind::Remote));
+        assert_eq!(kind(&[Key::KEY_SELECT]), Some(DeviceKind::Remote));
+        assert_eq!(
+            kind(&[Key::KEY_UP, Key::KEY_DOWN]),
+            Some(DeviceKind::Remote)
+        );
+        // A power button, or a keyboard missing Enter, is left alone
+        assert_eq!(kind(&[Key::KEY_POWER]), None);
+        assert_eq!(kind(&[Key::KEY_A]), None);
+        assert_eq!(kind(&[]), None);
+    }
+
+    #[test]
+    fn nodes_that_fail_to_open_are_retried() {
+        let dir = std::env::temp_dir().join(format!("kips_ui-hotplug-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        // Not an evdev node, so opening it fails like a node udev has not
+        // handed over yet; `mouse0` is not an event node at all
+        let node = dir.join("event3");
+        std::fs::write(&node, "").unwrap();
+        std::fs::write(dir.join("mouse0"), "").unwrap();
+
+        let mut watcher = DeviceWatcher::new();
+        let mut events = Vec::new();
+        for _ in 0..2 {
+            watcher.scan_dir(&dir, &mut events);
+            assert!(events.is_empty());
+            assert!(watcher.ignored.is_empty());
+            assert_eq!(watcher.unopened, HashSet::from([node.clone()]));
+        }
+
+        std::fs::remove_file(&node).unwrap();
+        watcher.scan_dir(&dir, &mut events);
+        assert!(watcher.unopened.is_empty());
+        assert!(watcher.scan_error().is_none());
+
+        watcher.scan_dir(&dir.join("missing"), &mut events);
+        assert!(watcher.scan_error().is_some());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
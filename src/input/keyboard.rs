@@ -1,98 +1,160 @@
+use crate::input::bindings::Action;
+use crate::input::hotplug::{self, DeviceKind, DeviceWatcher, WatcherEvent};
+use crate::input::keymap::{self, Layout, Modifiers};
+use crate::input::source::{DeviceStatus, InputSource, SourceHealth};
 use crate::input::InputEvent;
-use evdev::{Device, InputEventKind, Key};
-use std::fs::read_dir;
+use evdev::{InputEventKind, Key};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Instant;
 
-pub enum KeyboardInput {
-    Real(Device),
-    Fake,
+/// Keyboards, gamepads and remotes under `/dev/input`, attached and
+/// detached as they are plugged in.
+pub struct KeyboardInput {
+    watcher: DeviceWatcher,
+    devices: HashMap<PathBuf, AttachedDevice>,
+    keymap: HashMap<Key, Action>,
+    layout: Layout,
 }
 
-impl KeyboardInput {
-    pub fn new() -> Self {
-        let Ok(entries) = read_dir("/dev/input") else {
-            eprintln!("Could not read /dev/input");
-            return KeyboardInput::Fake;
-        };
-
-        for entry in entries.flatten() {
-            let path = entry.path();
+/// A keyboard, gamepad or remote currently plugged in.
+pub struct AttachedDevice {
+    pub name: String,
+    pub kind: DeviceKind,
+    modifiers: Modifiers,
+}
 
-            let is_event = path.file_name()
-                .and_then(|n| n.to_str())
-                .map_or(false, |s| s.starts_with("event"));
+impl KeyboardInput {
+    /// Starts with no devices; they attach (and detach) as `/dev/input`
+    /// changes, picked up on `poll`.
+    pub fn new(keymap: HashMap<Key, Action>, layout: Layout) -> Self {
+        KeyboardInput {
+            watcher: DeviceWatcher::new(),
+            devices: HashMap::new(),
+            keymap,
+            layout,
+        }
+    }
+}
 
-            if !is_event {
-                continue;
-            }
+impl InputSource for KeyboardInput {
+    fn name(&self) -> String {
+        "Keyboards, gamepads and remotes".to_string()
+    }
 
-            if let Ok(file) = std::fs::File::open(&path) {
-                if let Ok(device) = Device::open(&path) {
-                    if device.supported_keys().map_or(false, |keys| {
-                        keys.contains(Key::KEY_ENTER) && keys.contains(Key::KEY_A)
-                    }) {
-                        return KeyboardInput::Real(device);
-                    }
-                }
-                drop(file);
-            }
+    fn health(&self) -> SourceHealth {
+        match self.watcher.scan_error() {
+            Some(reason) => SourceHealth::Failed(reason.to_string()),
+            None => SourceHealth::Ok,
         }
+    }
 
-        eprintln!("⚠️  No suitable keyboard input device found.");
-        KeyboardInput::Fake
+    /// This source itself, then each attached device.
+    fn status(&self) -> Vec<DeviceStatus> {
+        let mut status = vec![DeviceStatus {
+            name: self.name(),
+            health: self.health(),
+        }];
+        status.extend(self.devices.values().map(|device| DeviceStatus {
+            name: format!("{}: {}", device.kind, device.name),
+            health: SourceHealth::Ok,
+        }));
+        status
     }
 
-    pub fn fake() -> Self {
-        KeyboardInput::Fake
+    fn has_keyboard(&self) -> bool {
+        self.devices
+            .values()
+            .any(|device| device.kind == DeviceKind::Keyboard)
     }
 
-    pub fn poll(&mut self) -> Vec<InputEvent> {
-        match self {
-            KeyboardInput::Real(device) => {
-                let mut events = Vec::new();
+    fn poll(&mut self, _now: Instant) -> Vec<InputEvent> {
+        let mut events = Vec::new();
 
-                if let Ok(ev_list) = device.fetch_events() {
-                    for ev in ev_list {
-                        if let InputEventKind::Key(key) = ev.kind() {
-                            if ev.value() == 1 {
-                                match key {
-                                    Key::KEY_UP => events.push(InputEvent::NavigateUp),
-                                    Key::KEY_DOWN => events.push(InputEvent::NavigateDown),
-                                    Key::KEY_LEFT => events.push(InputEvent::NavigateLeft),
-                                    Key::KEY_RIGHT => events.push(InputEvent::NavigateRight),
-                                    Key::KEY_SPACE => events.push(InputEvent::Activate),
-                                    Key::KEY_ENTER => events.push(InputEvent::Select),
-                                    Key::KEY_A => events.push(InputEvent::KeyPress('a')),
-                                    Key::KEY_B => events.push(InputEvent::KeyPress('b')),
-                                    _ => {}
-                                }
+        for watcher_event in self.watcher.poll() {
+            match watcher_event {
+                WatcherEvent::Attached { path, name, kind } => {
+                    events.push(InputEvent::DeviceConnected {
+                        name: name.clone(),
+                        kind,
+                    });
+                    self.devices.insert(
+                        path,
+                        AttachedDevice {
+                            name,
+                            kind,
+                            modifiers: Modifiers::default(),
+                        },
+                    );
+                }
+                WatcherEvent::Input(path, ev) => {
+                    let Some(device) = self.devices.get_mut(&path) else {
+                        continue;
+                    };
+                    match ev.kind() {
+                        InputEventKind::Key(key) => translate_key(
+                            key,
+                            ev.value(),
+                            &self.keymap,
+                            self.layout,
+                            &mut device.modifiers,
+                            &mut events,
+                        ),
+                        InputEventKind::AbsAxis(axis) => {
+                            if let Some(key) = hotplug::hat_direction(axis, ev.value()) {
+                                events.extend(self.keymap.get(&key).and_then(Action::to_event));
                             }
                         }
+                        _ => {}
                     }
                 }
-
-                events
-            }
-
-            KeyboardInput::Fake => {
-                use std::io::{self, Read};
-                let mut buf = [0; 1];
-                let stdin = io::stdin();
-                if let Ok(n) = stdin.lock().read(&mut buf) {
-                    if n > 0 {
-                        match buf[0] as char {
-                            '1' => vec![InputEvent::ButtonPressed("APPS")],
-                            '2' => vec![InputEvent::ButtonPressed("PROFILE")],
-                            '3' => vec![InputEvent::ButtonPressed("SETTINGS")],
-                            '\n' | '\r' => vec![InputEvent::Select],
-                            _ => vec![],
-                        }
-                    } else {
-                        vec![]
+                WatcherEvent::Detached(path) => {
+                    if let Some(device) = self.devices.remove(&path) {
+                        events.push(InputEvent::DeviceDisconnected {
+                            name: device.name,
+                            kind: device.kind,
+                        });
                     }
-                } else {
-                    vec![]
                 }
             }
         }
+
+        events
+    }
+}
+
+/// Emits `KeyDown`/`KeyUp` for every key, the bound action on press and
+/// auto-repeat, and `KeyPress` for keys that type text. A bound key that also
+/// types text (space, enter) produces both; text fields use the `KeyPress`.
+fn translate_key(
+    key: Key,
+    value: i32,
+    keymap: &HashMap<Key, Action>,
+    layout: Layout,
+    modifiers: &mut Modifiers,
+    events: &mut Vec<InputEvent>,
+) {
+    modifiers.update(key, value);
+
+    if value == 0 {
+        events.push(InputEvent::KeyUp {
+            key: key.code(),
+            modifiers: *modifiers,
+        });
+        return;
+    }
+
+    events.push(InputEvent::KeyDown {
+        key: key.code(),
+        modifiers: *modifiers,
+        repeat: value == 2,
+    });
+    if !modifiers.is_shortcut() {
+        if let Some(action) = keymap.get(&key) {
+            events.extend(action.to_event());
+        }
+    }
+    if let Some(c) = keymap::translate(layout, key, *modifiers) {
+        events.push(InputEvent::KeyPress(c));
     }
-}
\ No newline at end of file
+}
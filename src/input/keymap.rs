@@ -0,0 +1,449 @@
+use evdev::Key;
+use sdl2::keyboard::{Mod, Scancode};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Layout {
+    #[default]
+    Us,
+    Uk,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub ctrl: bool,
+    pub alt: bool,
+    pub altgr: bool,
+    pub meta: bool,
+    pub caps_lock: bool,
+}
+
+impl Modifiers {
+    /// Updates the held-modifier state from a key event; `value` is evdev's
+    /// 0 = up, 1 = down, 2 = repeat. Returns whether `key` was a modifier.
+    pub fn update(&mut self, key: Key, value: i32) -> bool {
+        let down = value != 0;
+        match key {
+            Key::KEY_LEFTSHIFT | Key::KEY_RIGHTSHIFT => self.shift = down,
+            Key::KEY_LEFTCTRL | Key::KEY_RIGHTCTRL => self.ctrl = down,
+            Key::KEY_LEFTALT => self.alt = down,
+            Key::KEY_RIGHTALT => self.altgr = down,
+            Key::KEY_LEFTMETA | Key::KEY_RIGHTMETA => self.meta = down,
+            Key::KEY_CAPSLOCK => {
+                if value == 1 {
+                    self.caps_lock = !self.caps_lock;
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Ctrl, Alt and Meta combinations are shortcuts, not text.
+    pub fn is_shortcut(&self) -> bool {
+        self.ctrl || self.alt || self.meta
+    }
+
+    /// The modifiers SDL reports with a window key event.
+    pub fn from_sdl(keymod: Mod) -> Self {
+        Self {
+            shift: keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD),
+            ctrl: keymod.intersects(Mod::LCTRLMOD | Mod::RCTRLMOD),
+            alt: keymod.contains(Mod::LALTMOD),
+            altgr: keymod.intersects(Mod::RALTMOD | Mod::MODEMOD),
+            meta: keymod.intersects(Mod::LGUIMOD | Mod::RGUIMOD),
+            caps_lock: keymod.contains(Mod::CAPSMOD),
+        }
+    }
+}
+
+/// Translates a key to the character it types, or `None` for keys that do not
+/// produce text (arrows, function keys, modifiers, shortcuts).
+pub fn translate(layout: Layout, key: Key, modifiers: Modifiers) -> Option<char> {
+    if modifiers.is_shortcut() {
+        return None;
+    }
+
+    if modifiers.altgr {
+        return match (layout, key) {
+            (Layout::Uk, Key::KEY_4) => Some('€'),
+            _ => None,
+        };
+    }
+
+    if let Some(letter) = letter(key) {
+        let upper = modifiers.shift != modifiers.caps_lock;
+        return Some(if upper { letter.to_ascii_uppercase() } else { letter });
+    }
+
+    let (plain, shifted) = match layout {
+        Layout::Us => us_symbol(key)?,
+        Layout::Uk => uk_symbol(key).or_else(|| us_symbol(key))?,
+    };
+    Some(if modifiers.shift { shifted } else { plain })
+}
+
+fn letter(key: Key) -> Option<char> {
+    let c = match key {
+        Key::KEY_A => 'a',
+        Key::KEY_B => 'b',
+        Key::KEY_C => 'c',
+        Key::KEY_D => 'd',
+        Key::KEY_E => 'e',
+        Key::KEY_F => 'f',
+        Key::KEY_G => 'g',
+        Key::KEY_H => 'h',
+        Key::KEY_I => 'i',
+        Key::KEY_J => 'j',
+        Key::KEY_K => 'k',
+        Key::KEY_L => 'l',
+        Key::KEY_M => 'm',
+        Key::KEY_N => 'n',
+        Key::KEY_O => 'o',
+        Key::KEY_P => 'p',
+        Key::KEY_Q => 'q',
+        Key::KEY_R => 'r',
+        Key::KEY_S => 's',
+        Key::KEY_T => 't',
+        Key::KEY_U => 'u',
+        Key::KEY_V => 'v',
+        Key::KEY_W => 'w',
+        Key::KEY_X => 'x',
+        Key::KEY_Y => 'y',
+        Key::KEY_Z => 'z',
+        _ => return None,
+    };
+    Some(c)
+}
+
+fn us_symbol(key: Key) -> Option<(char, char)> {
+    let pair = match key {
+        Key::KEY_1 => ('1', '!'),
+        Key::KEY_2 => ('2', '@'),
+        Key::KEY_3 => ('3', '#'),
+        Key::KEY_4 => ('4', '$'),
+        Key::KEY_5 => ('5', '%'),
+        Key::KEY_6 => ('6', '^'),
+        Key::KEY_7 => ('7', '&'),
+        Key::KEY_8 => ('8', '*'),
+        Key::KEY_9 => ('9', '('),
+        Key::KEY_0 => ('0', ')'),
+        Key::KEY_MINUS => ('-', '_'),
+        Key::KEY_EQUAL => ('=', '+'),
+        Key::KEY_LEFTBRACE => ('[', '{'),
+        Key::KEY_RIGHTBRACE => (']', '}'),
+        Key::KEY_BACKSLASH => ('\\', '|'),
+        Key::KEY_SEMICOLON => (';', ':'),
+        Key::KEY_APOSTROPHE => ('\'', '"'),
+        Key::KEY_GRAVE => ('`', '~'),
+        Key::KEY_COMMA => (',', '<'),
+        Key::KEY_DOT => ('.', '>'),
+        Key::KEY_SLASH => ('/', '?'),
+        Key::KEY_SPACE => (' ', ' '),
+        Key::KEY_TAB => ('\t', '\t'),
+        Key::KEY_ENTER | Key::KEY_KPENTER => ('\n', '\n'),
+        Key::KEY_KP0 => ('0', '0'),
+        Key::KEY_KP1 => ('1', '1'),
+        Key::KEY_KP2 => ('2', '2'),
+        Key::KEY_KP3 => ('3', '3'),
+        Key::KEY_KP4 => ('4', '4'),
+        Key::KEY_KP5 => ('5', '5'),
+        Key::KEY_KP6 => ('6', '6'),
+        Key::KEY_KP7 => ('7', '7'),
+        Key::KEY_KP8 => ('8', '8'),
+        Key::KEY_KP9 => ('9', '9'),
+        Key::KEY_KPDOT => ('.', '.'),
+        Key::KEY_KPPLUS => ('+', '+'),
+        Key::KEY_KPMINUS => ('-', '-'),
+        Key::KEY_KPASTERISK => ('*', '*'),
+        Key::KEY_KPSLASH => ('/', '/'),
+        _ => return None,
+    };
+    Some(pair)
+}
+
+/// Keys whose UK legends differ from US; everything else falls back to US.
+fn uk_symbol(key: Key) -> Option<(char, char)> {
+    let pair = match key {
+        Key::KEY_2 => ('2', '"'),
+        Key::KEY_3 => ('3', '£'),
+        Key::KEY_APOSTROPHE => ('\'', '@'),
+        Key::KEY_BACKSLASH => ('#', '~'),
+        Key::KEY_GRAVE => ('`', '¬'),
+        Key::KEY_102ND => ('\\', '|'),
+        _ => return None,
+    };
+    Some(pair)
+}
+
+/// The evdev key for an SDL scancode, so window key events report the same
+/// codes as an attached keyboard. Covers the keys of a full-size keyboard.
+pub fn from_sdl_scancode(scancode: Scancode) -> Option<Key> {
+    let key = match scancode {
+        Scancode::A => Key::KEY_A,
+        Scancode::B => Key::KEY_B,
+        Scancode::C => Key::KEY_C,
+        Scancode::D => Key::KEY_D,
+        Scancode::E => Key::KEY_E,
+        Scancode::F => Key::KEY_F,
+        Scancode::G => Key::KEY_G,
+        Scancode::H => Key::KEY_H,
+        Scancode::I => Key::KEY_I,
+        Scancode::J => Key::KEY_J,
+        Scancode::K => Key::KEY_K,
+        Scancode::L => Key::KEY_L,
+        Scancode::M => Key::KEY_M,
+        Scancode::N => Key::KEY_N,
+        Scancode::O => Key::KEY_O,
+        Scancode::P => Key::KEY_P,
+        Scancode::Q => Key::KEY_Q,
+        Scancode::R => Key::KEY_R,
+        Scancode::S => Key::KEY_S,
+        Scancode::T => Key::KEY_T,
+        Scancode::U => Key::KEY_U,
+        Scancode::V => Key::KEY_V,
+        Scancode::W => Key::KEY_W,
+        Scancode::X => Key::KEY_X,
+        Scancode::Y => Key::KEY_Y,
+        Scancode::Z => Key::KEY_Z,
+        Scancode::Num1 => Key::KEY_1,
+        Scancode::Num2 => Key::KEY_2,
+        Scancode::Num3 => Key::KEY_3,
+        Scancode::Num4 => Key::KEY_4,
+        Scancode::Num5 => Key::KEY_5,
+        Scancode::Num6 => Key::KEY_6,
+        Scancode::Num7 => Key::KEY_7,
+        Scancode::Num8 => Key::KEY_8,
+        Scancode::Num9 => Key::KEY_9,
+        Scancode::Num0 => Key::KEY_0,
+        Scancode::Return => Key::KEY_ENTER,
+        Scancode::Escape => Key::KEY_ESC,
+        Scancode::Backspace => Key::KEY_BACKSPACE,
+        Scancode::Tab => Key::KEY_TAB,
+        Scancode::Space => Key::KEY_SPACE,
+        Scancode::Minus => Key::KEY_MINUS,
+        Scancode::Equals => Key::KEY_EQUAL,
+        Scancode::LeftBracket => Key::KEY_LEFTBRACE,
+        Scancode::RightBracket => Key::KEY_RIGHTBRACE,
+        Scancode::Backslash | Scancode::NonUsHash => Key::KEY_BACKSLASH,
+        Scancode::Semicolon => Key::KEY_SEMICOLON,
+        Scancode::Apostrophe => Key::KEY_APOSTROPHE,
+        Scancode::Grave => Key::KEY_GRAVE,
+        Scancode::Comma => Key::KEY_COMMA,
+        Scancode::Period => Key::KEY_DOT,
+        Scancode::Slash => Key::KEY_SLASH,
+        Scancode::NonUsBackslash => Key::KEY_102ND,
+        Scancode::CapsLock => Key::KEY_CAPSLOCK,
+        Scancode::F1 => Key::KEY_F1,
+        Scancode::F2 => Key::KEY_F2,
+        Scancode::F3 => Key::KEY_F3,
+        Scancode::F4 => Key::KEY_F4,
+        Scancode::F5 => Key::KEY_F5,
+        Scancode::F6 => Key::KEY_F6,
+        Scancode::F7 => Key::KEY_F7,
+        Scancode::F8 => Key::KEY_F8,
+        Scancode::F9 => Key::KEY_F9,
+        Scancode::F10 => Key::KEY_F10,
+        Scancode::F11 => Key::KEY_F11,
+        Scancode::F12 => Key::KEY_F12,
+        Scancode::PrintScreen => Key::KEY_SYSRQ,
+        Scancode::ScrollLock => Key::KEY_SCROLLLOCK,
+        Scancode::Pause => Key::KEY_PAUSE,
+        Scancode::Insert => Key::KEY_INSERT,
+        Scancode::Home => Key::KEY_HOME,
+        Scancode::PageUp => Key::KEY_PAGEUP,
+        Scancode::Delete => Key::KEY_DELETE,
+        Scancode::End => Key::KEY_END,
+        Scancode::PageDown => Key::KEY_PAGEDOWN,
+        Scancode::Right => Key::KEY_RIGHT,
+        Scancode::Left => Key::KEY_LEFT,
+        Scancode::Down => Key::KEY_DOWN,
+        Scancode::Up => Key::KEY_UP,
+        Scancode::NumLockClear => Key::KEY_NUMLOCK,
+        Scancode::KpDivide => Key::KEY_KPSLASH,
+        Scancode::KpMultiply => Key::KEY_KPASTERISK,
+        Scancode::KpMinus => Key::KEY_KPMINUS,
+        Scancode::KpPlus => Key::KEY_KPPLUS,
+        Scancode::KpEnter => Key::KEY_KPENTER,
+        Scancode::Kp1 => Key::KEY_KP1,
+        Scancode::Kp2 => Key::KEY_KP2,
+        Scancode::Kp3 => Key::KEY_KP3,
+        Scancode::Kp4 => Key::KEY_KP4,
+        Scancode::Kp5 => Key::KEY_KP5,
+        Scancode::Kp6 => Key::KEY_KP6,
+        Scancode::Kp7 => Key::KEY_KP7,
+        Scancode::Kp8 => Key::KEY_KP8,
+        Scancode::Kp9 => Key::KEY_KP9,
+        Scancode::Kp0 => Key::KEY_KP0,
+        Scancode::KpPeriod => Key::KEY_KPDOT,
+        Scancode::Application => Key::KEY_COMPOSE,
+        Scancode::Menu => Key::KEY_MENU,
+        Scancode::Power => Key::KEY_POWER,
+        Scancode::Mute => Key::KEY_MUTE,
+        Scancode::VolumeUp => Key::KEY_VOLUMEUP,
+        Scancode::VolumeDown => Key::KEY_VOLUMEDOWN,
+        Scancode::LCtrl => Key::KEY_LEFTCTRL,
+        Scancode::LShift => Key::KEY_LEFTSHIFT,
+        Scancode::LAlt => Key::KEY_LEFTALT,
+        Scancode::LGui => Key::KEY_LEFTMETA,
+        Scancode::RCtrl => Key::KEY_RIGHTCTRL,
+        Scancode::RShift => Key::KEY_RIGHTSHIFT,
+        Scancode::RAlt => Key::KEY_RIGHTALT,
+        Scancode::RGui => Key::KEY_RIGHTMETA,
+        Scancode::AcHome => Key::KEY_HOMEPAGE,
+        Scancode::AcBack => Key::KEY_BACK,
+        _ => return None,
+    };
+    Some(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHIFT: Modifiers = Modifiers {
+        shift: true,
+        ctrl: false,
+        alt: false,
+        altgr: false,
+        meta: false,
+        caps_lock: false,
+    };
+
+    fn typed(layout: Layout, keys: &[Key], modifiers: Modifiers) -> String {
+        keys.iter()
+            .filter_map(|&key| translate(layout, key, modifiers))
+            .collect()
+    }
+
+    const DIGITS: [Key; 10] = [
+        Key::KEY_1,
+        Key::KEY_2,
+        Key::KEY_3,
+        Key::KEY_4,
+        Key::KEY_5,
+        Key::KEY_6,
+        Key::KEY_7,
+        Key::KEY_8,
+        Key::KEY_9,
+        Key::KEY_0,
+    ];
+    const SYMBOLS: [Key; 11] = [
+        Key::KEY_MINUS,
+        Key::KEY_EQUAL,
+        Key::KEY_LEFTBRACE,
+        Key::KEY_RIGHTBRACE,
+        Key::KEY_BACKSLASH,
+        Key::KEY_SEMICOLON,
+        Key::KEY_APOSTROPHE,
+        Key::KEY_GRAVE,
+        Key::KEY_COMMA,
+        Key::KEY_DOT,
+        Key::KEY_SLASH,
+    ];
+
+    #[test]
+    fn shifted_digits_and_symbols() {
+        let plain = Modifiers::default();
+        assert_eq!(typed(Layout::Us, &DIGITS, plain), "1234567890");
+        assert_eq!(typed(Layout::Us, &DIGITS, SHIFT), "!@#$%^&*()");
+        assert_eq!(typed(Layout::Us, &SYMBOLS, plain), r"-=[]\;'`,./");
+        assert_eq!(typed(Layout::Us, &SYMBOLS, SHIFT), "_+{}|:\"~<>?");
+
+        assert_eq!(typed(Layout::Uk, &DIGITS, plain), "1234567890");
+        assert_eq!(typed(Layout::Uk, &DIGITS, SHIFT), "!\"£$%^&*()");
+        assert_eq!(typed(Layout::Uk, &SYMBOLS, plain), "-=[]#;'`,./");
+        assert_eq!(typed(Layout::Uk, &SYMBOLS, SHIFT), "_+{}~:@¬<>?");
+    }
+
+    #[test]
+    fn uk_only_keys() {
+        let plain = Modifiers::default();
+        assert_eq!(translate(Layout::Uk, Key::KEY_102ND, plain), Some('\\'));
+        assert_eq!(translate(Layout::Uk, Key::KEY_102ND, SHIFT), Some('|'));
+        assert_eq!(translate(Layout::Us, Key::KEY_102ND, plain), None);
+
+        let altgr = Modifiers {
+            altgr: true,
+            ..Modifiers::default()
+        };
+        assert_eq!(translate(Layout::Uk, Key::KEY_4, altgr), Some('€'));
+        assert_eq!(translate(Layout::Us, Key::KEY_4, altgr), None);
+        assert_eq!(translate(Layout::Uk, Key::KEY_A, altgr), None);
+    }
+
+    #[test]
+    fn caps_lock_only_changes_letters() {
+        let caps = Modifiers {
+            caps_lock: true,
+            ..Modifiers::default()
+        };
+        let both = Modifiers {
+            caps_lock: true,
+            ..SHIFT
+        };
+        let keys = [Key::KEY_Q, Key::KEY_1, Key::KEY_SLASH];
+        assert_eq!(typed(Layout::Us, &keys, Modifiers::default()), "q1/");
+        assert_eq!(typed(Layout::Us, &keys, SHIFT), "Q!?");
+        assert_eq!(typed(Layout::Us, &keys, caps), "Q1/");
+        // Shift undoes Caps Lock on letters but still shifts the rest
+        assert_eq!(typed(Layout::Us, &keys, both), "q!?");
+    }
+
+    #[test]
+    fn modifiers_follow_key_events() {
+        let mut modifiers = Modifiers::default();
+        assert!(modifiers.update(Key::KEY_RIGHTSHIFT, 1));
+        assert!(modifiers.shift);
+        assert!(modifiers.update(Key::KEY_RIGHTSHIFT, 0));
+        assert!(!modifiers.shift);
+
+        // Caps Lock toggles on the press only, not on repeat or release
+        for value in [1, 2, 0] {
+            modifiers.update(Key::KEY_CAPSLOCK, value);
+        }
+        assert!(modifiers.caps_lock);
+        modifiers.update(Key::KEY_CAPSLOCK, 1);
+        assert!(!modifiers.caps_lock);
+
+        modifiers.update(Key::KEY_LEFTCTRL, 1);
+        assert!(modifiers.is_shortcut());
+        assert_eq!(translate(Layout::Us, Key::KEY_C, modifiers), None);
+        assert!(!modifiers.update(Key::KEY_A, 1));
+
+        let sdl = Modifiers::from_sdl(Mod::LSHIFTMOD | Mod::RALTMOD | Mod::CAPSMOD);
+        assert!(sdl.shift && sdl.altgr && sdl.caps_lock);
+        assert!(!sdl.alt && !sdl.is_shortcut());
+    }
+
+    #[test]
+    fn sdl_scancodes_map_to_evdev_keys() {
+        for (scancode, key) in [
+            (Scancode::A, Key::KEY_A),
+            (Scancode::Z, Key::KEY_Z),
+            (Scancode::Num1, Key::KEY_1),
+            (Scancode::Num0, Key::KEY_0),
+            (Scancode::Return, Key::KEY_ENTER),
+            (Scancode::Backspace, Key::KEY_BACKSPACE),
+            (Scancode::Grave, Key::KEY_GRAVE),
+            // The UK # key reports as NonUsHash but sits where US \ does
+            (Scancode::NonUsHash, Key::KEY_BACKSLASH),
+            (Scancode::NonUsBackslash, Key::KEY_102ND),
+            (Scancode::KpEnter, Key::KEY_KPENTER),
+            (Scancode::Kp7, Key::KEY_KP7),
+            (Scancode::F12, Key::KEY_F12),
+            (Scancode::Up, Key::KEY_UP),
+            (Scancode::RAlt, Key::KEY_RIGHTALT),
+            (Scancode::AcBack, Key::KEY_BACK),
+        ] {
+            assert_eq!(from_sdl_scancode(scancode), Some(key), "{:?}", scancode);
+        }
+        assert_eq!(from_sdl_scancode(Scancode::International1), None);
+
+        // A window keyboard types the same as an attached one
+        let key = from_sdl_scancode(Scancode::Num3).unwrap();
+        assert_eq!(translate(Layout::Uk, key, SHIFT), Some('£'));
+    }
+}
@@ -2,81 +2,361 @@ pub mod touchscreen;
 pub mod encoder;
 pub mod button;
 pub mod keyboard;
+pub mod bindings;
+pub mod quadrature;
+pub mod gesture;
+pub mod keymap;
+pub mod hotplug;
+pub mod dev_keyboard;
+pub mod calibration;
+pub mod recording;
+pub mod source;
+pub mod gpio;
 
+use sdl2::event::{Event, WindowEvent};
+use sdl2::keyboard::Keycode;
 use sdl2::Sdl;
 use sdl2::EventPump;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
 
-use crate::input::touchscreen::TouchscreenInput;
-use crate::input::encoder::RotaryEncoder;
-use crate::input::button::ButtonInput;
-use crate::input::keyboard::KeyboardInput;
+use crate::input::touchscreen::{SwipeDirection, TouchscreenInput};
+use crate::input::bindings::{Action, InputBindings};
+use crate::input::gesture::{ButtonId, GestureDetector, GestureKind};
+use crate::input::keymap::Modifiers;
+use crate::input::hotplug::DeviceKind;
+use crate::input::calibration::{Calibration, CalibrationSession, Rotation};
+use crate::input::recording::{InputRecorder, ReplaySource};
+use crate::input::source::{DeviceStatus, InputSource, SourceRegistry};
+use serde::{Deserialize, Serialize};
 
+/// Size of the UI in logical pixels. Renderers scale it to the window, so
+/// touch positions are reported in these coordinates whatever the window size.
+pub const SCREEN_WIDTH: u32 = 800;
+pub const SCREEN_HEIGHT: u32 = 480;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum InputEvent {
+    /// A tap on the touchscreen (or a mouse click).
     Touch(i32, i32),
-    ButtonPressed(&'static str),
+    TouchLongPress(i32, i32),
+    Swipe(SwipeDirection),
+    /// Sent while a finger moves; `dx`/`dy` are since the previous `Drag`.
+    Drag { x: i32, y: i32, dx: i32, dy: i32 },
+    /// Two-finger spread change since the previous `Pinch`; above 1.0 is zoom in.
+    Pinch { scale: f32 },
+    ButtonPressed(String),
     EncoderTurned { id: u8, delta: i8 },
     EncoderPressed(u8),
+    /// Text typed on a keyboard, after layout and modifiers.
     KeyPress(char),
+    /// evdev key code, for shortcuts; `repeat` is set for auto-repeat.
+    KeyDown { key: u16, modifiers: Modifiers, repeat: bool },
+    KeyUp { key: u16, modifiers: Modifiers },
     NavigateUp,
     NavigateDown,
     NavigateLeft,
     NavigateRight,
     Activate,
     Select,
+    Back,
+    Home,
+    Menu,
+    Power,
+    /// Raw edges from buttons, plus the long/double presses derived from them.
+    Gesture { button: ButtonId, kind: GestureKind },
+    /// A calibration touch was taken; the argument is the next target index.
+    CalibrationStep(usize),
+    /// Calibration ended; `false` if the touches could not be fitted.
+    CalibrationDone(bool),
+    DeviceConnected { name: String, kind: DeviceKind },
+    DeviceDisconnected { name: String, kind: DeviceKind },
+    Quit,
+    WindowResized(u32, u32),
+    FocusGained,
+    FocusLost,
 }
 
-pub struct InputManager<'a> {
-    pub encoder1: RotaryEncoder,
-    pub encoder2: RotaryEncoder,
-    pub button: ButtonInput,
-    pub keyboard: KeyboardInput,
-    pub touchscreen: TouchscreenInput<'a>,
+/// The single input bus: owns the SDL event pump and merges window, mouse and
+/// touch events with every registered `InputSource` into one `InputEvent`
+/// stream.
+pub struct InputManager {
+    /// `None` without SDL, see `from_sources`.
+    event_pump: Option<EventPump>,
+    pub sources: Vec<Box<dyn InputSource>>,
+    pub touchscreen: TouchscreenInput,
+    pub gestures: GestureDetector,
+    pub sdl_keys: HashMap<Keycode, Action>,
+    pub calibration_path: PathBuf,
+    calibration_session: Option<CalibrationSession>,
+    recorder: Option<InputRecorder>,
+    replay: Option<ReplaySource>,
     pub dev_mode: bool,
 }
 
-impl<'a> InputManager<'a> {
-    pub fn new(_sdl_context: &Sdl, event_pump: &'a mut EventPump, dev_mode: bool) -> Self {
+impl InputManager {
+    /// `bindings` must already be validated, e.g. by `InputBindings::load`.
+    /// Touch calibration is read from, and saved to, `calibration_path`.
+    pub fn new(
+        sdl_context: &Sdl,
+        bindings: &InputBindings,
+        calibration_path: &Path,
+        dev_mode: bool,
+    ) -> Self {
+        Self::with_registry(
+            sdl_context,
+            bindings,
+            &SourceRegistry::builtin(),
+            calibration_path,
+            dev_mode,
+        )
+    }
+
+    /// Like `new`, but builds the sources from `registry` instead of the
+    /// built-in GPIO and evdev ones.
+    pub fn with_registry(
+        sdl_context: &Sdl,
+        bindings: &InputBindings,
+        registry: &SourceRegistry,
+        calibration_path: &Path,
+        dev_mode: bool,
+    ) -> Self {
         InputManager {
-            touchscreen: TouchscreenInput::new(event_pump),
-            encoder1: if dev_mode {
-                RotaryEncoder::fake()
-            } else {
-                RotaryEncoder::new(0, 17, 27, 22)
-            },
-            encoder2: if dev_mode {
-                RotaryEncoder::fake()
-            } else {
-                RotaryEncoder::new(0, 23, 24, 25)
-            },
-            button: if dev_mode {
-                ButtonInput::fake()
-            } else {
-                ButtonInput::new()
-            },
-            keyboard: if dev_mode {
-                KeyboardInput::Fake
-            } else {
-                KeyboardInput::new()
-            },
+            event_pump: Some(sdl_context.event_pump().expect("Failed to get SDL event pump")),
+            sdl_keys: bindings.sdl_keymap().expect("Invalid SDL key bindings"),
             dev_mode,
+            ..Self::from_sources(bindings, registry.build(bindings, dev_mode), calibration_path)
+        }
+    }
+
+    /// An input bus without SDL: there are no window, mouse or touch events,
+    /// only `sources` and replays. Lets tests drive the screens headlessly.
+    pub fn from_sources(
+        bindings: &InputBindings,
+        sources: Vec<Box<dyn InputSource>>,
+        calibration_path: &Path,
+    ) -> Self {
+        InputManager {
+            event_pump: None,
+            touchscreen: TouchscreenInput::new(
+                SCREEN_WIDTH,
+                SCREEN_HEIGHT,
+                Calibration::load_or_default(calibration_path),
+            ),
+            sources,
+            gestures: GestureDetector::new(bindings),
+            sdl_keys: HashMap::new(),
+            calibration_path: calibration_path.to_path_buf(),
+            calibration_session: None,
+            recorder: None,
+            replay: None,
+            dev_mode: false,
+        }
+    }
+
+    pub fn add_source(&mut self, source: Box<dyn InputSource>) {
+        self.sources.push(source);
+    }
+
+    /// Every input device and whether it works, for a diagnostics screen.
+    pub fn device_status(&self) -> Vec<DeviceStatus> {
+        self.sources.iter().flat_map(|source| source.status()).collect()
+    }
+
+    /// Starts collecting calibration touches. Until it completes, taps go to
+    /// the calibration instead of being reported and other touch gestures
+    /// are dropped.
+    pub fn start_calibration(&mut self) {
+        let (width, height) = self.touchscreen.size();
+        let current = self.touchscreen.calibration();
+        self.touchscreen.set_calibration(current.uncorrected());
+        self.calibration_session = Some(CalibrationSession::new(width, height, current.rotation));
+    }
+
+    pub fn cancel_calibration(&mut self) {
+        if self.calibration_session.take().is_some() {
+            self.touchscreen
+                .set_calibration(Calibration::load_or_default(&self.calibration_path));
         }
     }
 
+    /// Crosshair position for the running calibration, if any.
+    pub fn calibration_target(&self) -> Option<(i32, i32)> {
+        self.calibration_session.as_ref()?.target()
+    }
+
+    /// Changes the panel rotation, keeping the existing correction.
+    pub fn set_touch_rotation(&mut self, rotation: Rotation) -> anyhow::Result<()> {
+        let calibration = Calibration {
+            rotation,
+            ..self.touchscreen.calibration()
+        };
+        self.touchscreen.set_calibration(calibration);
+        calibration.save(&self.calibration_path)
+    }
+
+    /// Writes every event reported from now on to `path` as JSON lines.
+    pub fn start_recording(&mut self, path: &Path) -> anyhow::Result<()> {
+        self.recorder = Some(InputRecorder::create(path)?);
+        Ok(())
+    }
+
+    pub fn stop_recording(&mut self) {
+        self.recorder = None;
+    }
+
+    /// Plays a recording back at `speed` times its original pace. Live input
+    /// other than `Quit` is discarded until it ends, so the session repeats
+    /// exactly.
+    pub fn start_replay(&mut self, path: &Path, speed: f32) -> anyhow::Result<()> {
+        self.replay = Some(ReplaySource::load(path, speed)?);
+        Ok(())
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        self.replay.is_some()
+    }
+
     pub fn poll_inputs(&mut self) -> Vec<InputEvent> {
+        let now = Instant::now();
+        let mut events = self.poll_live(now);
+
+        if let Some(replay) = self.replay.as_mut() {
+            events.retain(|event| matches!(event, InputEvent::Quit));
+            events.extend(replay.poll(now));
+            if replay.is_finished() {
+                log::info!("Input replay finished");
+                self.replay = None;
+            }
+        }
+
+        if let Some(recorder) = self.recorder.as_mut() {
+            if let Err(e) = recorder.record(&events, now) {
+                log::error!("Stopping input recording: {:#}", e);
+                self.recorder = None;
+            }
+        }
+
+        events
+    }
+
+    fn poll_live(&mut self, now: Instant) -> Vec<InputEvent> {
         let mut events = vec![];
 
-        events.extend(self.touchscreen.poll());
-        events.extend(self.encoder1.poll());
-        events.extend(self.encoder2.poll());
-        events.extend(self.button.poll());
+        if let Some(event_pump) = self.event_pump.as_mut() {
+            let sdl_events: Vec<Event> = event_pump.poll_iter().collect();
+            for event in sdl_events {
+                self.translate_sdl_event(&event, now, &mut events);
+            }
+        }
+        events.extend(self.touchscreen.tick(now));
+        if self.calibration_session.is_some() {
+            events = self.calibrate_from(events);
+        }
 
-        // only include keyboard input in non-dev mode
-        if !self.dev_mode {
-            events.extend(self.keyboard.poll());
+        // Everything that may carry button edges goes through the gesture
+        // layer; other events pass through unchanged.
+        let mut raw = vec![];
+        for source in &mut self.sources {
+            raw.extend(source.poll(now));
         }
+        events.extend(self.gestures.process(raw, now));
 
         events
     }
+
+    fn translate_sdl_event(&mut self, event: &Event, now: Instant, events: &mut Vec<InputEvent>) {
+        let translated = match event {
+            Event::Quit { .. } => Some(InputEvent::Quit),
+            Event::Window { win_event, .. } => match *win_event {
+                WindowEvent::Resized(w, h) | WindowEvent::SizeChanged(w, h) => {
+                    self.touchscreen.set_window_size(w as u32, h as u32);
+                    Some(InputEvent::WindowResized(w as u32, h as u32))
+                }
+                WindowEvent::FocusGained => Some(InputEvent::FocusGained),
+                WindowEvent::FocusLost => Some(InputEvent::FocusLost),
+                _ => None,
+            },
+            // An attached evdev keyboard already reports these keys, so the
+            // window's copy is only used when there is none. Scancodes are
+            // reported as evdev codes, as that keyboard would; SDL applies
+            // the desktop's layout to `TextInput` itself.
+            Event::KeyDown { keycode, scancode, keymod, repeat, .. } if !self.has_evdev_keyboard() => {
+                if let Some(key) = scancode.and_then(keymap::from_sdl_scancode) {
+                    events.push(InputEvent::KeyDown {
+                        key: key.code(),
+                        modifiers: Modifiers::from_sdl(*keymod),
+                        repeat: *repeat,
+                    });
+                }
+                keycode.and_then(|keycode| self.sdl_keys.get(&keycode)).and_then(Action::to_event)
+            }
+            Event::KeyUp { scancode, keymod, .. } if !self.has_evdev_keyboard() => {
+                scancode.and_then(keymap::from_sdl_scancode).map(|key| InputEvent::KeyUp {
+                    key: key.code(),
+                    modifiers: Modifiers::from_sdl(*keymod),
+                })
+            }
+            Event::TextInput { text, .. } if !self.has_evdev_keyboard() => {
+                events.extend(text.chars().map(InputEvent::KeyPress));
+                None
+            }
+            _ => {
+                events.extend(self.touchscreen.handle(event, now));
+                None
+            }
+        };
+        events.extend(translated);
+    }
+
+    fn calibrate_from(&mut self, events: Vec<InputEvent>) -> Vec<InputEvent> {
+        let mut out = Vec::new();
+        for event in events {
+            match event {
+                InputEvent::Touch(x, y) => {
+                    let Some(session) = self.calibration_session.as_mut() else {
+                        out.push(event);
+                        continue;
+                    };
+                    if !session.record(x as f32, y as f32) {
+                        out.push(InputEvent::CalibrationStep(session.step()));
+                        continue;
+                    }
+
+                    let saved = session
+                        .finish()
+                        .and_then(|calibration| {
+                            calibration.save(&self.calibration_path)?;
+                            Ok(calibration)
+                        });
+                    self.calibration_session = None;
+                    match saved {
+                        Ok(calibration) => {
+                            self.touchscreen.set_calibration(calibration);
+                            out.push(InputEvent::CalibrationDone(true));
+                        }
+                        Err(e) => {
+                            log::warn!("Touch calibration failed: {:#}", e);
+                            self.touchscreen
+                                .set_calibration(Calibration::load_or_default(&self.calibration_path));
+                            out.push(InputEvent::CalibrationDone(false));
+                        }
+                    }
+                }
+                InputEvent::TouchLongPress(..)
+                | InputEvent::Swipe(_)
+                | InputEvent::Drag { .. }
+                | InputEvent::Pinch { .. }
+                    if self.calibration_session.is_some() => {}
+                other => out.push(other),
+            }
+        }
+        out
+    }
+
+    fn has_evdev_keyboard(&self) -> bool {
+        self.sources.iter().any(|source| source.has_keyboard())
+    }
 }
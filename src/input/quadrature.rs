@@ -0,0 +1,283 @@
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+/// How many Gray-code transitions make up one reported step.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepMode {
+    /// One step per full quadrature cycle (one detent on most encoders).
+    #[default]
+    Full,
+    /// One step per half cycle.
+    Half,
+    /// One step per transition.
+    Quarter,
+}
+
+impl StepMode {
+    fn transitions_per_step(self) -> i8 {
+        match self {
+            StepMode::Full => 4,
+            StepMode::Half => 2,
+            StepMode::Quarter => 1,
+        }
+    }
+}
+
+// Indexed by (previous << 2) | current, where a state is (clk << 1) | dt with
+// `true` meaning the line is active. Clockwise runs 00 -> 10 -> 11 -> 01 -> 00;
+// transitions that skip a state (both lines changed) are ignored.
+const TRANSITIONS: [i8; 16] = [
+    0, -1, 1, 0, //
+    1, 0, 0, -1, //
+    -1, 0, 0, 1, //
+    0, 1, -1, 0, //
+];
+
+const REST_STATE: u8 = 0b00;
+const HALF_REST_STATE: u8 = 0b11;
+
+#[derive(Debug, Clone)]
+pub struct QuadratureDecoder {
+    mode: StepMode,
+    state: u8,
+    accum: i8,
+}
+
+impl QuadratureDecoder {
+    pub fn new(mode: StepMode, clk: bool, dt: bool) -> Self {
+        Self {
+            mode,
+            state: encode(clk, dt),
+            accum: 0,
+        }
+    }
+
+    /// Feeds the current line levels and returns the whole steps completed
+    /// since the last call (positive is clockwise).
+    pub fn update(&mut self, clk: bool, dt: bool) -> i8 {
+        let next = encode(clk, dt);
+        if next == self.state {
+            return 0;
+        }
+
+        self.accum += TRANSITIONS[((self.state << 2) | next) as usize];
+        self.state = next;
+
+        let per_step = self.mode.transitions_per_step();
+        let steps = self.accum / per_step;
+        self.accum -= steps * per_step;
+
+        // Back at a detent without a full step means contact bounce or a
+        // missed transition; start counting afresh from here.
+        let at_rest = match self.mode {
+            StepMode::Full => next == REST_STATE,
+            StepMode::Half => next == REST_STATE || next == HALF_REST_STATE,
+            StepMode::Quarter => true,
+        };
+        if at_rest {
+            self.accum = 0;
+        }
+
+        steps
+    }
+}
+
+fn encode(clk: bool, dt: bool) -> u8 {
+    ((clk as u8) << 1) | dt as u8
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct AccelerationConfig {
+    /// Steps further apart than this are never scaled.
+    pub slow_ms: u64,
+    /// Steps this close together get `max_multiplier`.
+    pub fast_ms: u64,
+    pub max_multiplier: u8,
+}
+
+impl Default for AccelerationConfig {
+    fn default() -> Self {
+        Self {
+            slow_ms: 120,
+            fast_ms: 15,
+            max_multiplier: 8,
+        }
+    }
+}
+
+/// Scales steps by how quickly they follow each other, so a fast spin covers
+/// more ground than the same number of slow clicks.
+#[derive(Debug, Clone)]
+pub struct Accelerator {
+    config: Option<AccelerationConfig>,
+    last_step: Option<(Instant, i8)>,
+}
+
+impl Accelerator {
+    pub fn new(config: Option<AccelerationConfig>) -> Self {
+        Self {
+            config,
+            last_step: None,
+        }
+    }
+
+    pub fn scale(&mut self, steps: i8, now: Instant) -> i32 {
+        let Some(config) = self.config else {
+            return steps as i32;
+        };
+
+        let direction = steps.signum();
+        let multiplier = match self.last_step {
+            // Reversing always starts slow so overshoot can be corrected
+            Some((last, last_direction)) if last_direction == direction => {
+                multiplier_for(&config, now.duration_since(last))
+            }
+            _ => 1,
+        };
+        self.last_step = Some((now, direction));
+
+        steps as i32 * multiplier as i32
+    }
+}
+
+fn multiplier_for(config: &AccelerationConfig, interval: Duration) -> u8 {
+    let interval = interval.as_millis() as u64;
+    let max = config.max_multiplier.max(1);
+    if interval >= config.slow_ms {
+        1
+    } else if interval <= config.fast_ms || config.slow_ms <= config.fast_ms {
+        max
+    } else {
+        let span = (config.slow_ms - config.fast_ms) as f32;
+        let speed = (config.slow_ms - interval) as f32 / span;
+        1 + ((max - 1) as f32 * speed).round() as u8
+    }
+}
+
+/// Line levels, decoder and pending steps shared between the polling loop and,
+/// in interrupt mode, rppal's interrupt thread.
+#[derive(Debug)]
+pub struct RotationState {
+    clk: bool,
+    dt: bool,
+    decoder: QuadratureDecoder,
+    accelerator: Accelerator,
+    pending: i32,
+}
+
+impl RotationState {
+    pub fn new(
+        mode: StepMode,
+        acceleration: Option<AccelerationConfig>,
+        clk: bool,
+        dt: bool,
+    ) -> Self {
+        Self {
+            clk,
+            dt,
+            decoder: QuadratureDecoder::new(mode, clk, dt),
+            accelerator: Accelerator::new(acceleration),
+            pending: 0,
+        }
+    }
+
+    pub fn set_clk(&mut self, clk: bool, now: Instant) {
+        self.clk = clk;
+        self.advance(now);
+    }
+
+    pub fn set_dt(&mut self, dt: bool, now: Instant) {
+        self.dt = dt;
+        self.advance(now);
+    }
+
+    pub fn set_levels(&mut self, clk: bool, dt: bool, now: Instant) {
+        self.clk = clk;
+        self.dt = dt;
+        self.advance(now);
+    }
+
+    /// Returns and clears the accumulated (accelerated) steps.
+    pub fn take_pending(&mut self) -> i32 {
+        std::mem::take(&mut self.pending)
+    }
+
+    fn advance(&mut self, now: Instant) {
+        let steps = self.decoder.update(self.clk, self.dt);
+        if steps != 0 {
+            self.pending += self.accelerator.scale(steps, now);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // (clk, dt) levels for one detent, starting from and returning to rest
+    const CW: [(bool, bool); 4] = [(true, false), (true, true), (false, true), (false, false)];
+    const CCW: [(bool, bool); 4] = [(false, true), (true, true), (true, false), (false, false)];
+
+    fn feed(decoder: &mut QuadratureDecoder, levels: &[(bool, bool)]) -> Vec<i8> {
+        levels
+            .iter()
+            .map(|&(clk, dt)| decoder.update(clk, dt))
+            .collect()
+    }
+
+    #[test]
+    fn full_step_counts_one_per_detent() {
+        let mut decoder = QuadratureDecoder::new(StepMode::Full, false, false);
+        assert_eq!(feed(&mut decoder, &CW), [0, 0, 0, 1]);
+        assert_eq!(feed(&mut decoder, &CCW), [0, 0, 0, -1]);
+    }
+
+    #[test]
+    fn half_and_quarter_steps() {
+        let mut half = QuadratureDecoder::new(StepMode::Half, false, false);
+        assert_eq!(feed(&mut half, &CW), [0, 1, 0, 1]);
+        assert_eq!(feed(&mut half, &CCW), [0, -1, 0, -1]);
+
+        let mut quarter = QuadratureDecoder::new(StepMode::Quarter, false, false);
+        assert_eq!(feed(&mut quarter, &CW), [1, 1, 1, 1]);
+        assert_eq!(feed(&mut quarter, &CCW), [-1, -1, -1, -1]);
+    }
+
+    #[test]
+    fn bounce_at_a_detent_counts_nothing() {
+        let mut decoder = QuadratureDecoder::new(StepMode::Full, false, false);
+        let bounce = [(true, false), (false, false), (true, false), (false, false)];
+        assert_eq!(feed(&mut decoder, &bounce), [0, 0, 0, 0]);
+        // Half a turn and back again
+        let back = [(true, false), (true, true), (true, false), (false, false)];
+        assert_eq!(feed(&mut decoder, &back), [0, 0, 0, 0]);
+        assert_eq!(feed(&mut decoder, &CW), [0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn skipped_states_are_ignored() {
+        let mut decoder = QuadratureDecoder::new(StepMode::Quarter, false, false);
+        assert_eq!(decoder.update(true, true), 0);
+        assert_eq!(decoder.update(true, true), 0);
+        assert_eq!(decoder.update(false, true), 1);
+    }
+
+    #[test]
+    fn acceleration_scales_fast_steps_only() {
+        let config = AccelerationConfig::default();
+        let mut accelerator = Accelerator::new(Some(config));
+        let start = Instant::now();
+        assert_eq!(accelerator.scale(1, start), 1);
+        let slow = start + Duration::from_millis(config.slow_ms);
+        assert_eq!(accelerator.scale(1, slow), 1);
+        let fast = slow + Duration::from_millis(config.fast_ms);
+        assert_eq!(accelerator.scale(1, fast), config.max_multiplier as i32);
+        // Reversing starts slow however quick it is
+        assert_eq!(accelerator.scale(-1, fast + Duration::from_millis(1)), -1);
+
+        let mut off = Accelerator::new(None);
+        assert_eq!(off.scale(1, start), 1);
+        assert_eq!(off.scale(1, start), 1);
+    }
+}
@@ -0,0 +1,165 @@
+use crate::input::InputEvent;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::time::Instant;
+
+/// One line of a recording: an event and when it was reported, in
+/// milliseconds since the recording started.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedEvent {
+    pub t_ms: u64,
+    pub event: InputEvent,
+}
+
+/// Appends every event `InputManager` reports to a JSON-lines file. Lines are
+/// flushed after each poll so a crash still leaves a usable recording.
+pub struct InputRecorder {
+    writer: BufWriter<File>,
+    started: Instant,
+}
+
+impl InputRecorder {
+    pub fn create(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)
+                .with_context(|| format!("creating {}", dir.display()))?;
+        }
+        let file = File::create(path)
+            .with_context(|| format!("creating input recording {}", path.display()))?;
+        Ok(Self {
+            writer: BufWriter::new(file),
+            started: Instant::now(),
+        })
+    }
+
+    pub fn record(&mut self, events: &[InputEvent], now: Instant) -> Result<()> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        let t_ms = now.duration_since(self.started).as_millis() as u64;
+        for event in events {
+            let line = serde_json::to_string(&RecordedEvent {
+                t_ms,
+                event: event.clone(),
+            })?;
+            writeln!(self.writer, "{}", line)?;
+        }
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Reads a whole recording, e.g. to drive a UI state machine in a test
+/// without waiting for the original timing.
+pub fn load_recording(path: impl AsRef<Path>) -> Result<Vec<RecordedEvent>> {
+    let path = path.as_ref();
+    let file = File::open(path)
+        .with_context(|| format!("opening input recording {}", path.display()))?;
+
+    let mut events = Vec::new();
+    for (number, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.with_context(|| format!("reading {}", path.display()))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let event: RecordedEvent = serde_json::from_str(&line)
+            .with_context(|| format!("{}:{}: invalid event", path.display(), number + 1))?;
+        events.push(event);
+    }
+    Ok(events)
+}
+
+/// Feeds a recording back in, releasing each event once its timestamp has
+/// passed. `speed` scales playback: 1.0 is the original pace, 4.0 four times
+/// faster and `f32::INFINITY` everything on the first poll.
+pub struct ReplaySource {
+    pending: VecDeque<RecordedEvent>,
+    started: Instant,
+    speed: f32,
+}
+
+impl ReplaySource {
+    pub fn load(path: impl AsRef<Path>, speed: f32) -> Result<Self> {
+        if speed.is_nan() || speed <= 0.0 {
+            bail!("replay speed must be positive, not {}", speed);
+        }
+        Ok(Self {
+            pending: load_recording(path)?.into(),
+            started: Instant::now(),
+            speed,
+        })
+    }
+
+    pub fn poll(&mut self, now: Instant) -> Vec<InputEvent> {
+        let elapsed_ms = now.duration_since(self.started).as_secs_f64() * 1000.0;
+        let mut events = Vec::new();
+        while let Some(next) = self.pending.front() {
+            if next.t_ms as f64 / self.speed as f64 > elapsed_ms {
+                break;
+            }
+            events.extend(self.pending.pop_front().map(|recorded| recorded.event));
+        }
+        events
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn recordings_replay_at_any_speed() {
+        let path = std::env::temp_dir()
+            .join(format!("kips_ui-recording-{}", std::process::id()))
+            .join("session.jsonl");
+        let mut recorder = InputRecorder::create(&path).unwrap();
+        let start = recorder.started;
+        recorder.record(&[], start).unwrap();
+        recorder
+            .record(
+                &[InputEvent::NavigateDown],
+                start + Duration::from_millis(100),
+            )
+            .unwrap();
+        recorder
+            .record(
+                &[InputEvent::Select, InputEvent::KeyPress('a')],
+                start + Duration::from_millis(300),
+            )
+            .unwrap();
+        drop(recorder);
+
+        let recorded = load_recording(&path).unwrap();
+        let times: Vec<u64> = recorded.iter().map(|r| r.t_ms).collect();
+        assert_eq!(times, [100, 300, 300]);
+
+        // Twice as fast: everything is due by 150 ms
+        let mut replay = ReplaySource::load(&path, 2.0).unwrap();
+        let at = |ms| replay.started + Duration::from_millis(ms);
+        let (early, first, rest) = (at(49), at(50), at(150));
+        assert!(replay.poll(early).is_empty());
+        assert_eq!(replay.poll(first), [InputEvent::NavigateDown]);
+        assert!(!replay.is_finished());
+        assert_eq!(
+            replay.poll(rest),
+            [InputEvent::Select, InputEvent::KeyPress('a')]
+        );
+        assert!(replay.is_finished());
+
+        let mut instant = ReplaySource::load(&path, f32::INFINITY).unwrap();
+        assert_eq!(instant.poll(instant.started).len(), 3);
+        assert!(ReplaySource::load(&path, 0.0).is_err());
+
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+}
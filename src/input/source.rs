@@ -0,0 +1,196 @@
+use crate::input::bindings::InputBindings;
+use crate::input::button::ButtonInput;
+use crate::input::dev_keyboard::DevKeyboard;
+use crate::input::encoder::RotaryEncoder;
+use crate::input::gpio::{GpioBackend, RppalGpio};
+use crate::input::keyboard::KeyboardInput;
+use crate::input::InputEvent;
+use std::fmt;
+use std::time::Instant;
+
+/// Anything that produces input: GPIO encoders and buttons, evdev devices,
+/// the dev-mode terminal, or a scripted source in a test. `InputManager`
+/// polls every source once per frame and passes the events through the
+/// gesture layer, so sources report raw button edges as
+/// `InputEvent::Gesture` and leave long/double presses and chords to it.
+pub trait InputSource {
+    /// Shown in logs and the device status list.
+    fn name(&self) -> String;
+
+    /// Called once per frame; must not block.
+    fn poll(&mut self, now: Instant) -> Vec<InputEvent>;
+
+    fn health(&self) -> SourceHealth {
+        SourceHealth::Ok
+    }
+
+    /// One entry per device behind this source, for a diagnostics screen.
+    fn status(&self) -> Vec<DeviceStatus> {
+        vec![DeviceStatus {
+            name: self.name(),
+            health: self.health(),
+        }]
+    }
+
+    /// Whether a physical keyboard is attached through this source. While
+    /// one is, the window's own key events are ignored so keys are not
+    /// reported twice.
+    fn has_keyboard(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SourceHealth {
+    Ok,
+    /// Working, but not as configured (e.g. polling instead of interrupts).
+    Degraded(String),
+    /// Produces no more input.
+    Failed(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DeviceStatus {
+    pub name: String,
+    pub health: SourceHealth,
+}
+
+/// Stands in for hardware that could not be set up, so the rest of the input
+/// keeps working and the reason shows in the status list.
+pub struct DisabledSource {
+    name: String,
+    reason: String,
+}
+
+impl DisabledSource {
+    /// Logs why `name` is unavailable.
+    pub fn new(name: String, reason: String) -> Self {
+        log::warn!("{} disabled: {}", name, reason);
+        Self { name, reason }
+    }
+}
+
+impl InputSource for DisabledSource {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn poll(&mut self, _now: Instant) -> Vec<InputEvent> {
+        vec![]
+    }
+
+    fn health(&self) -> SourceHealth {
+        SourceHealth::Failed(self.reason.clone())
+    }
+}
+
+impl fmt::Display for SourceHealth {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SourceHealth::Ok => f.write_str("ok"),
+            SourceHealth::Degraded(reason) => write!(f, "degraded: {}", reason),
+            SourceHealth::Failed(reason) => write!(f, "failed: {}", reason),
+        }
+    }
+}
+
+/// Builds the sources for a set of bindings; `true` means dev mode.
+pub type SourceFactory = Box<dyn Fn(&InputBindings, bool) -> Vec<Box<dyn InputSource>>>;
+
+/// The named factories `InputManager` builds its sources from. New hardware
+/// registers a factory here instead of adding a field to `InputManager`.
+pub struct SourceRegistry {
+    factories: Vec<(String, SourceFactory)>,
+}
+
+impl SourceRegistry {
+    pub fn empty() -> Self {
+        Self {
+            factories: Vec::new(),
+        }
+    }
+
+    /// GPIO encoders and buttons plus evdev keyboards, gamepads and remotes;
+    /// in dev mode only the terminal keyboard.
+    pub fn builtin() -> Self {
+        let mut registry = Self::empty();
+        registry.register("gpio", |bindings, dev_mode| {
+            if dev_mode {
+                return vec![];
+            }
+            match RppalGpio::new() {
+                Ok(gpio) => gpio_sources(bindings, &gpio),
+                // Without GPIO every pin is unavailable for the same reason
+                Err(e) => gpio_names(bindings)
+                    .map(|name| disabled(name, &e))
+                    .collect(),
+            }
+        });
+        registry.register("keyboard", |bindings, dev_mode| {
+            if dev_mode {
+                return vec![Box::new(DevKeyboard::spawn())];
+            }
+            match bindings.evdev_keymap() {
+                Ok(keymap) => vec![Box::new(KeyboardInput::new(keymap, bindings.keyboard_layout))],
+                Err(e) => vec![disabled("Keyboards, gamepads and remotes".to_string(), &e)],
+            }
+        });
+        registry
+    }
+
+    /// Like `builtin`, but encoders and buttons read `gpio`, even in dev
+    /// mode; e.g. a `SimulatedGpio` driven by a test.
+    pub fn with_gpio(gpio: impl GpioBackend + 'static) -> Self {
+        let mut registry = Self::builtin();
+        registry.register("gpio", move |bindings, _| gpio_sources(bindings, &gpio));
+        registry
+    }
+
+    /// Adds a factory, replacing any registered under the same name.
+    pub fn register(
+        &mut self,
+        name: &str,
+        factory: impl Fn(&InputBindings, bool) -> Vec<Box<dyn InputSource>> + 'static,
+    ) {
+        let factory: SourceFactory = Box::new(factory);
+        match self.factories.iter_mut().find(|(existing, _)| existing == name) {
+            Some((_, existing)) => *existing = factory,
+            None => self.factories.push((name.to_string(), factory)),
+        }
+    }
+
+    pub fn build(&self, bindings: &InputBindings, dev_mode: bool) -> Vec<Box<dyn InputSource>> {
+        self.factories
+            .iter()
+            .flat_map(|(_, factory)| factory(bindings, dev_mode))
+            .collect()
+    }
+}
+
+/// Each encoder and button is set up on its own, so one bad pin only
+/// disables that control.
+fn gpio_sources(bindings: &InputBindings, gpio: &dyn GpioBackend) -> Vec<Box<dyn InputSource>> {
+    let encoders = bindings.encoders.iter().map(|binding| {
+        RotaryEncoder::new(binding, gpio).map_or_else(
+            |e| disabled(format!("Encoder {}", binding.id), &e),
+            |encoder| Box::new(encoder) as Box<dyn InputSource>,
+        )
+    });
+    let buttons = bindings.buttons.iter().map(|binding| {
+        ButtonInput::new(binding, gpio).map_or_else(
+            |e| disabled(format!("Button {}", binding.id), &e),
+            |button| Box::new(button) as Box<dyn InputSource>,
+        )
+    });
+    encoders.chain(buttons).collect()
+}
+
+fn gpio_names(bindings: &InputBindings) -> impl Iterator<Item = String> + '_ {
+    let encoders = bindings.encoders.iter().map(|b| format!("Encoder {}", b.id));
+    let buttons = bindings.buttons.iter().map(|b| format!("Button {}", b.id));
+    encoders.chain(buttons)
+}
+
+fn disabled(name: String, error: &anyhow::Error) -> Box<dyn InputSource> {
+    Box::new(DisabledSource::new(name, format!("{:#}", error)))
+}
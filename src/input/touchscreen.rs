@@ -1,24 +1,360 @@
 use sdl2::{event::Event, mouse::MouseButton};
-use sdl2::EventPump;
+use crate::input::calibration::Calibration;
 use crate::input::InputEvent;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
 
-pub struct TouchscreenInput<'a> {
-    event_pump: &'a mut EventPump,
+// SDL reports mouse events synthesized from touches with this device id
+const SDL_TOUCH_MOUSEID: u32 = u32::MAX;
+// The mouse is tracked as one more finger so desktop drags work too
+const MOUSE_FINGER: i64 = -1;
+
+// Movement below this (in pixels) still counts as a tap
+const TAP_SLOP: f32 = 12.0;
+const LONG_PRESS: Duration = Duration::from_millis(500);
+const SWIPE_MIN_DISTANCE: f32 = 80.0;
+// Pixels per millisecond
+const SWIPE_MIN_VELOCITY: f32 = 0.4;
+// Ignore pinch jitter smaller than this fraction
+const PINCH_THRESHOLD: f32 = 0.02;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SwipeDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+struct Finger {
+    start: (f32, f32),
+    last: (f32, f32),
+    /// Position of the last `Drag`, so steps under a pixel add up rather
+    /// than being rounded away one at a time.
+    reported: (i32, i32),
+    started_at: Instant,
+    moved: bool,
+}
+
+/// Tracks fingers (and the left mouse button) and recognizes tap, long
+/// press, swipe, drag and two-finger pinch. Taps are reported as `Touch`.
+/// Positions are rotated and calibrated before anything else sees them.
+pub struct TouchscreenInput {
+    width: u32,
+    height: u32,
+    /// Finger positions are fractions of the window, which shows the
+    /// `width` x `height` canvas letterboxed in the middle.
+    window: (u32, u32),
+    calibration: Calibration,
+    /// In the order they landed; the first two pinch.
+    fingers: Vec<(i64, Finger)>,
+    /// Set once a second finger lands; the gesture then only pinches until
+    /// every finger is lifted.
+    multi_touch: bool,
+    long_press_fired: bool,
+    pinch_distance: Option<f32>,
 }
 
-impl<'a> TouchscreenInput<'a> {
-    pub fn new(event_pump: &'a mut EventPump) -> Self {
-        Self { event_pump }
+impl TouchscreenInput {
+    pub fn new(width: u32, height: u32, calibration: Calibration) -> Self {
+        Self {
+            width,
+            height,
+            window: (width, height),
+            calibration,
+            fingers: Vec::new(),
+            multi_touch: false,
+            long_press_fired: false,
+            pinch_distance: None,
+        }
+    }
+
+    pub fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    pub fn set_window_size(&mut self, width: u32, height: u32) {
+        if width > 0 && height > 0 {
+            self.window = (width, height);
+        }
+    }
+
+    pub fn calibration(&self) -> Calibration {
+        self.calibration
+    }
+
+    pub fn set_calibration(&mut self, calibration: Calibration) {
+        self.calibration = calibration;
     }
 
-    pub fn poll(&mut self) -> Vec<InputEvent> {
-        let mut events = Vec::new();
-        for event in self.event_pump.poll_iter() {
-            if let Event::MouseButtonDown { x, y, mouse_btn: MouseButton::Left, .. } = event {
-                events.push(InputEvent::Touch(x, y));
+    pub fn handle(&mut self, event: &Event, now: Instant) -> Vec<InputEvent> {
+        match *event {
+            Event::MouseButtonDown { x, y, which, mouse_btn: MouseButton::Left, .. }
+                if which != SDL_TOUCH_MOUSEID =>
+            {
+                let pos = self.calibrate(x as f32, y as f32);
+                self.finger_down(MOUSE_FINGER, pos, now)
+            }
+            Event::MouseMotion { x, y, which, mousestate, .. }
+                if which != SDL_TOUCH_MOUSEID && mousestate.left() =>
+            {
+                let pos = self.calibrate(x as f32, y as f32);
+                self.finger_motion(MOUSE_FINGER, pos)
+            }
+            Event::MouseButtonUp { x, y, which, mouse_btn: MouseButton::Left, .. }
+                if which != SDL_TOUCH_MOUSEID =>
+            {
+                let pos = self.calibrate(x as f32, y as f32);
+                self.finger_up(MOUSE_FINGER, pos, now)
+            }
+            // Finger coordinates are normalized to 0.0..1.0
+            Event::FingerDown { finger_id, x, y, .. } => {
+                let pos = self.to_pixels(x, y);
+                self.finger_down(finger_id, pos, now)
+            }
+            Event::FingerMotion { finger_id, x, y, .. } => {
+                let pos = self.to_pixels(x, y);
+                self.finger_motion(finger_id, pos)
             }
+            Event::FingerUp { finger_id, x, y, .. } => {
+                let pos = self.to_pixels(x, y);
+                self.finger_up(finger_id, pos, now)
+            }
+            _ => vec![],
+        }
+    }
+
+    /// Reports a long press once a single finger has rested long enough.
+    pub fn tick(&mut self, now: Instant) -> Vec<InputEvent> {
+        if self.multi_touch || self.long_press_fired || self.fingers.len() != 1 {
+            return vec![];
+        }
+        let Some((_, finger)) = self.fingers.first() else {
+            return vec![];
+        };
+        if finger.moved || now.duration_since(finger.started_at) < LONG_PRESS {
+            return vec![];
         }
 
-        events
+        self.long_press_fired = true;
+        let (x, y) = finger.last;
+        vec![InputEvent::TouchLongPress(x as i32, y as i32)]
     }
-}
\ No newline at end of file
+
+    /// Undoes the canvas scaling, as SDL already does for mouse positions.
+    fn to_pixels(&self, x: f32, y: f32) -> (f32, f32) {
+        let (window_width, window_height) = (self.window.0 as f32, self.window.1 as f32);
+        let (width, height) = (self.width as f32, self.height as f32);
+        let scale = (window_width / width).min(window_height / height);
+        let left = (window_width - width * scale) / 2.0;
+        let top = (window_height - height * scale) / 2.0;
+        self.calibrate(
+            (x * window_width - left) / scale,
+            (y * window_height - top) / scale,
+        )
+    }
+
+    fn calibrate(&self, x: f32, y: f32) -> (f32, f32) {
+        self.calibration.apply(x, y, self.width, self.height)
+    }
+
+    fn finger_down(&mut self, id: i64, pos: (f32, f32), now: Instant) -> Vec<InputEvent> {
+        if self.fingers.is_empty() {
+            self.multi_touch = false;
+            self.long_press_fired = false;
+        }
+        // A finger id SDL reuses without an up in between starts over
+        self.fingers.retain(|(finger_id, _)| *finger_id != id);
+        self.fingers.push((
+            id,
+            Finger {
+                start: pos,
+                last: pos,
+                reported: (pos.0 as i32, pos.1 as i32),
+                started_at: now,
+                moved: false,
+            },
+        ));
+
+        if self.fingers.len() >= 2 {
+            self.multi_touch = true;
+            self.pinch_distance = self.finger_spread();
+        }
+        vec![]
+    }
+
+    fn finger_motion(&mut self, id: i64, pos: (f32, f32)) -> Vec<InputEvent> {
+        let finger = self.fingers.iter_mut().find(|(finger_id, _)| *finger_id == id);
+        let Some((_, finger)) = finger else {
+            return vec![];
+        };
+        finger.last = pos;
+
+        if self.multi_touch {
+            return self.pinch();
+        }
+
+        if !finger.moved && distance(finger.start, pos) > TAP_SLOP {
+            finger.moved = true;
+        }
+        let (x, y) = (pos.0 as i32, pos.1 as i32);
+        if !finger.moved || (x, y) == finger.reported {
+            return vec![];
+        }
+
+        let (dx, dy) = (x - finger.reported.0, y - finger.reported.1);
+        finger.reported = (x, y);
+        vec![InputEvent::Drag { x, y, dx, dy }]
+    }
+
+    fn finger_up(&mut self, id: i64, pos: (f32, f32), now: Instant) -> Vec<InputEvent> {
+        let Some(index) = self.fingers.iter().position(|(finger_id, _)| *finger_id == id) else {
+            return vec![];
+        };
+        let (_, finger) = self.fingers.remove(index);
+
+        if self.multi_touch {
+            self.pinch_distance = self.finger_spread();
+            return vec![];
+        }
+        if self.long_press_fired {
+            return vec![];
+        }
+
+        let travelled = distance(finger.start, pos);
+        if !finger.moved && travelled <= TAP_SLOP {
+            return vec![InputEvent::Touch(pos.0 as i32, pos.1 as i32)];
+        }
+
+        let elapsed_ms = now.duration_since(finger.started_at).as_millis().max(1) as f32;
+        if travelled >= SWIPE_MIN_DISTANCE && travelled / elapsed_ms >= SWIPE_MIN_VELOCITY {
+            let (dx, dy) = (pos.0 - finger.start.0, pos.1 - finger.start.1);
+            let direction = if dx.abs() > dy.abs() {
+                if dx > 0.0 {
+                    SwipeDirection::Right
+                } else {
+                    SwipeDirection::Left
+                }
+            } else if dy > 0.0 {
+                SwipeDirection::Down
+            } else {
+                SwipeDirection::Up
+            };
+            return vec![InputEvent::Swipe(direction)];
+        }
+
+        vec![]
+    }
+
+    fn pinch(&mut self) -> Vec<InputEvent> {
+        let (Some(previous), Some(current)) = (self.pinch_distance, self.finger_spread()) else {
+            return vec![];
+        };
+        if previous <= 0.0 {
+            self.pinch_distance = Some(current);
+            return vec![];
+        }
+
+        let scale = current / previous;
+        if (scale - 1.0).abs() < PINCH_THRESHOLD {
+            return vec![];
+        }
+        self.pinch_distance = Some(current);
+        vec![InputEvent::Pinch { scale }]
+    }
+
+    /// Distance between the first two fingers down.
+    fn finger_spread(&self) -> Option<f32> {
+        match self.fingers.as_slice() {
+            [(_, a), (_, b), ..] => Some(distance(a.last, b.last)),
+            _ => None,
+        }
+    }
+}
+
+fn distance(a: (f32, f32), b: (f32, f32)) -> f32 {
+    ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn touchscreen() -> TouchscreenInput {
+        TouchscreenInput::new(800, 480, Calibration::default())
+    }
+
+    #[test]
+    fn slow_drags_add_up() {
+        let mut touch = touchscreen();
+        let now = Instant::now();
+        touch.finger_down(1, (100.0, 100.0), now);
+        // Past the tap slop, then a third of a pixel at a time
+        let mut drags = touch.finger_motion(1, (120.0, 100.0));
+        for step in 1..=9 {
+            drags.extend(touch.finger_motion(1, (120.0 + step as f32 / 3.0, 100.0)));
+        }
+        let moved: i32 = drags
+            .iter()
+            .map(|event| match event {
+                InputEvent::Drag { dx, .. } => *dx,
+                other => panic!("unexpected {:?}", other),
+            })
+            .sum();
+        assert_eq!(moved, 23);
+        assert_eq!(
+            drags.last(),
+            Some(&InputEvent::Drag {
+                x: 123,
+                y: 100,
+                dx: 1,
+                dy: 0
+            })
+        );
+    }
+
+    #[test]
+    fn fingers_map_through_the_letterbox() {
+        let mut touch = touchscreen();
+        let at = |touch: &TouchscreenInput, x, y| {
+            let (x, y) = touch.to_pixels(x, y);
+            (x.round() as i32, y.round() as i32)
+        };
+        assert_eq!(at(&touch, 0.5, 0.25), (400, 120));
+
+        // 1920x1080 shows the canvas 1800 wide with 60 pixel bars either side
+        touch.set_window_size(1920, 1080);
+        assert_eq!(at(&touch, 60.0 / 1920.0, 0.0), (0, 0));
+        assert_eq!(at(&touch, 0.5, 0.5), (400, 240));
+        assert_eq!(at(&touch, 1860.0 / 1920.0, 1.0), (800, 480));
+
+        // A tall window letterboxes top and bottom instead
+        touch.set_window_size(800, 800);
+        assert_eq!(at(&touch, 0.25, 160.0 / 800.0), (200, 0));
+    }
+
+    #[test]
+    fn the_first_two_fingers_pinch() {
+        let mut touch = touchscreen();
+        let now = Instant::now();
+        touch.finger_down(7, (100.0, 200.0), now);
+        touch.finger_down(3, (200.0, 200.0), now);
+        touch.finger_down(5, (700.0, 200.0), now);
+        // The third finger moving changes nothing
+        assert!(touch.finger_motion(5, (750.0, 200.0)).is_empty());
+        assert_eq!(
+            touch.finger_motion(3, (300.0, 200.0)),
+            [InputEvent::Pinch { scale: 2.0 }]
+        );
+
+        // With one of them lifted the next finger down takes its place
+        touch.finger_up(7, (100.0, 200.0), now);
+        assert_eq!(
+            touch.finger_motion(5, (600.0, 200.0)),
+            [InputEvent::Pinch {
+                scale: 300.0 / 450.0
+            }]
+        );
+    }
+}
@@ -1,125 +1,102 @@
+use crate::registry::{AppExec, AppManifest};
+use anyhow::{bail, Context, Result};
+use std::ffi::{OsStr, OsString};
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
-pub struct Launcher;
+/// Placeholder in manifest arguments for the file being opened.
+pub const FILE_ARG: &str = "{file}";
 
-impl Launcher {
-    pub fn open_file_explorer() {
-        Self::spawn("pcmanfm");
-    }
-
-    pub fn open_terminal() {
-        Self::spawn("lxterminal");
-    }
-
-    pub fn open_ide() {
-        Self::spawn("thonny");
-    }
-
-    pub fn open_text_editor() {
-        Self::spawn("mousepad");
-    }
+/// Set for launched apps to the output device chosen in the sound settings.
+pub const AUDIO_OUTPUT_ENV: &str = "KIPS_AUDIO_OUTPUT";
 
-    pub fn open_calendar() {
-        Self::spawn_flatpak("org.gnome.Calendar");
-    }
+/// Apps built into kips_ui, each a `kips_<name>` binary installed beside it.
+pub const INTERNAL_APPS: &[&str] = &["audio", "video", "weather"];
 
-    pub fn open_maps() {
-        Self::spawn_flatpak("org.gnome.Maps");
-    }
-
-    pub fn open_notes() {
-        Self::spawn_flatpak("com.github.flxzt.rnote");
+/// The command that starts `app`, opening `file` if given.
+pub fn command(app: &AppManifest, file: Option<&Path>) -> Result<Command> {
+    let (mut command, args) = match &app.exec {
+        AppExec::Command { program, args } => (Command::new(program), args.clone()),
+        AppExec::Flatpak { app_id, args } => {
+            let mut command = Command::new("flatpak");
+            command.args(["run", app_id]);
+            (command, args.clone())
+        }
+        AppExec::Internal { name } => {
+            if !INTERNAL_APPS.contains(&name.as_str()) {
+                bail!("{}: no internal app called `{}`", app.id, name);
+            }
+            (Command::new(internal_binary(name)?), Vec::new())
+        }
+        AppExec::Screen { .. } => bail!("{}: opens in the shell, not as a program", app.id),
+    };
+
+    // Kept as an OsStr: names on FAT drives are often not UTF-8
+    let mut used_file = false;
+    for arg in args {
+        if !arg.contains(FILE_ARG) {
+            command.arg(arg);
+        } else if let Some(file) = file {
+            command.arg(substitute_file(&arg, file.as_os_str()));
+            used_file = true;
+        }
     }
-
-    pub fn open_todo_list() {
-        Self::spawn_flatpak("io.github.mrvladus.List");
+    if let Some(file) = file.filter(|_| !used_file) {
+        command.arg(file);
     }
+    Ok(command)
+}
 
-    pub fn open_writer(file: Option<&str>) {
-        Self::spawn_with_args("libreoffice", &["--writer"], file);
+/// `arg` with each `{file}` replaced by `file`.
+fn substitute_file(arg: &str, file: &OsStr) -> OsString {
+    let mut parts = arg.split(FILE_ARG);
+    let mut substituted = OsString::from(parts.next().unwrap_or_default());
+    for part in parts {
+        substituted.push(file);
+        substituted.push(part);
     }
+    substituted
+}
 
-    pub fn open_calc(file: Option<&str>) {
-        Self::spawn_with_args("libreoffice", &["--calc"], file);
+/// The executable of internal app `name`, next to the running one.
+pub fn internal_binary(name: &str) -> Result<PathBuf> {
+    let exe = std::env::current_exe().context("finding the kips_ui executable")?;
+    let path = exe.with_file_name(format!("kips_{}{}", name, std::env::consts::EXE_SUFFIX));
+    if !path.is_file() {
+        bail!("{} is not installed", path.display());
     }
+    Ok(path)
+}
 
-    pub fn open_impress(file: Option<&str>) {
-        Self::spawn_with_args("libreoffice", &["--impress"], file);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::ffi::OsStrExt;
+
+    fn app(args: &[&str]) -> AppManifest {
+        let exec = serde_json::json!({ "type": "command", "program": "viewer", "args": args });
+        serde_json::from_value(serde_json::json!({
+            "id": "viewer", "name": "Viewer", "category": "Tools", "exec": exec
+        }))
+        .unwrap()
     }
 
-    fn spawn(cmd: &str) {
-        if let Err(e) = Command::new(cmd).spawn() {
-            eprintln!("Failed to launch `{}`: {}", cmd, e);
-        }
-    }
+    #[test]
+    fn file_names_are_passed_through_unchanged() {
+        // Latin-1 "café.txt", as a FAT drive may name it
+        let file = Path::new(OsStr::from_bytes(b"/media/usb/caf\xe9.txt"));
 
-    fn spawn_flatpak(app_id: &str) {
-        if let Err(e) = Command::new("flatpak")
-            .args(["run", app_id])
-            .spawn()
-        {
-            eprintln!("Failed to launch flatpak `{}`: {}", app_id, e);
-        }
-    }
+        let launched = command(&app(&["--open={file}", "-v"]), Some(file)).unwrap();
+        let mut expected = OsString::from("--open=");
+        expected.push(file);
+        let args: Vec<&OsStr> = launched.get_args().collect();
+        assert_eq!(args, [expected.as_os_str(), OsStr::new("-v")]);
 
-    fn spawn_with_args(base: &str, args: &[&str], file: Option<&str>) {
-        let mut command = Command::new(base);
-        command.args(args);
-        if let Some(f) = file {
-            command.arg(f);
-        }
+        let launched = command(&app(&["-v"]), Some(file)).unwrap();
+        let args: Vec<&OsStr> = launched.get_args().collect();
+        assert_eq!(args, [OsStr::new("-v"), file.as_os_str()]);
 
-        if let Err(e) = command.spawn() {
-            eprintln!("Failed to launch `{}` with args {:?}: {}", base, args, e);
-        }
+        let launched = command(&app(&["{file}"]), None).unwrap();
+        assert_eq!(launched.get_args().count(), 0);
     }
 }
-
-/// Optional enum to map UI IDs
-pub enum LaunchableApp {
-    Weather,
-    AudioPlayer,
-    VideoPlayer,
-    FileExplorer,
-    Terminal,
-    IDE,
-    TextEditor,
-    Calendar,
-    Maps,
-    Notes,
-    TodoList,
-    Writer(Option<String>),
-    Calc(Option<String>),
-    Impress(Option<String>),
-}
-
-pub fn launch_app(app: LaunchableApp) {
-    match app {
-        LaunchableApp::Weather => {
-            let _ = Command::new("cargo")
-                .args(&["run", "--bin", "weather"])
-                .spawn();
-        }
-        LaunchableApp::AudioPlayer => {
-            let _ = Command::new("cargo")
-                .args(&["run", "--bin", "audio"])
-                .spawn();
-        }
-        LaunchableApp::VideoPlayer => {
-            let _ = Command::new("cargo")
-                .args(&["run", "--bin", "video"])
-                .spawn();
-        }
-        LaunchableApp::FileExplorer => Launcher::open_file_explorer(),
-        LaunchableApp::Terminal => Launcher::open_terminal(),
-        LaunchableApp::IDE => Launcher::open_ide(),
-        LaunchableApp::TextEditor => Launcher::open_text_editor(),
-        LaunchableApp::Calendar => Launcher::open_calendar(),
-        LaunchableApp::Maps => Launcher::open_maps(),
-        LaunchableApp::Notes => Launcher::open_notes(),
-        LaunchableApp::TodoList => Launcher::open_todo_list(),
-        LaunchableApp::Writer(file) => Launcher::open_writer(file.as_deref()),
-        LaunchableApp::Calc(file) => Launcher::open_calc(file.as_deref()),
-        LaunchableApp::Impress(file) => Launcher::open_impress(file.as_deref()),
-    }
-}
\ No newline at end of file
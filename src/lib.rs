@@ -1,4 +1,15 @@
 // src/lib.rs
-pub fn hello_from_rust() {
-    println!("Rust lib is working!");
-}
\ No newline at end of file
+pub mod app;
+pub mod apps;
+pub mod associations;
+pub mod cli;
+pub mod files;
+pub mod hardware;
+pub mod holotape;
+pub mod input;
+pub mod launcher;
+pub mod profiles;
+pub mod registry;
+pub mod settings;
+pub mod supervisor;
+pub mod ui;
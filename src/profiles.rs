@@ -0,0 +1,386 @@
+use crate::holotape;
+use crate::settings::{self, write_atomic};
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+pub const PROFILES_FILE: &str = "profiles.json";
+/// Each profile keeps its settings and avatar under `profiles/<id>/`.
+pub const PROFILES_DIR: &str = "profiles";
+pub const MAX_NAME_LEN: usize = 24;
+pub const MAX_RECENTS: usize = 10;
+const DEFAULT_NAME: &str = "User";
+const AVATAR_EXTENSIONS: [&str; 3] = ["png", "jpg", "jpeg"];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    pub id: u32,
+    pub name: String,
+    /// A copy inside the profile directory, so it survives the USB drive
+    /// it came from being unplugged.
+    #[serde(default)]
+    pub avatar: Option<PathBuf>,
+    /// App ids, in the order they were added.
+    #[serde(default)]
+    pub favourites: Vec<String>,
+    /// App ids, most recent first, at most `MAX_RECENTS`.
+    #[serde(default)]
+    pub recents: Vec<String>,
+}
+
+impl Profile {
+    fn new(id: u32, name: String) -> Self {
+        Self {
+            id,
+            name,
+            avatar: None,
+            favourites: Vec::new(),
+            recents: Vec::new(),
+        }
+    }
+
+    pub fn is_favourite(&self, app: &str) -> bool {
+        self.favourites.iter().any(|f| f == app)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ProfilesFile {
+    active: u32,
+    next_id: u32,
+    profiles: Vec<Profile>,
+}
+
+/// Every profile and which one is in use. Each change is saved straight
+/// away; there is always at least one profile.
+pub struct ProfileStore {
+    dir: PathBuf,
+    file: ProfilesFile,
+}
+
+impl ProfileStore {
+    /// Loads `dir/profiles.json`. The first run creates a default profile
+    /// that inherits any settings saved before profiles existed.
+    pub fn open(dir: impl Into<PathBuf>) -> Self {
+        let dir = dir.into();
+        let path = dir.join(PROFILES_FILE);
+        let loaded = if path.exists() {
+            Self::load(&path)
+                .map_err(|e| log::warn!("Starting with a fresh profile: {:#}", e))
+                .ok()
+        } else {
+            None
+        };
+
+        let mut store = Self {
+            dir,
+            file: loaded.unwrap_or_else(|| ProfilesFile {
+                active: 1,
+                next_id: 2,
+                profiles: vec![Profile::new(1, DEFAULT_NAME.to_string())],
+            }),
+        };
+        store.repair();
+
+        if !path.exists() {
+            let legacy = store.dir.join(settings::SETTINGS_FILE);
+            let target = store.settings_path();
+            if legacy.exists() && !target.exists() {
+                if let Err(e) = copy_into(&legacy, &target) {
+                    log::warn!("Cannot carry over {}: {:#}", legacy.display(), e);
+                }
+            }
+            store.save_or_warn();
+        }
+        store
+    }
+
+    fn load(path: &Path) -> Result<ProfilesFile> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("reading profiles {}", path.display()))?;
+        serde_json::from_str(&text).with_context(|| format!("parsing profiles {}", path.display()))
+    }
+
+    /// Fixes a hand-edited file: no profiles, a missing active profile or
+    /// ids that `next_id` would hand out again.
+    fn repair(&mut self) {
+        if self.file.profiles.is_empty() {
+            self.file
+                .profiles
+                .push(Profile::new(1, DEFAULT_NAME.to_string()));
+        }
+        if self.get(self.file.active).is_none() {
+            self.file.active = self.file.profiles[0].id;
+        }
+        let highest = self.file.profiles.iter().map(|p| p.id).max().unwrap_or(0);
+        self.file.next_id = self.file.next_id.max(highest + 1);
+    }
+
+    fn save(&self) -> Result<()> {
+        let text = serde_json::to_string_pretty(&self.file)?;
+        write_atomic(&self.dir.join(PROFILES_FILE), text.as_bytes())
+    }
+
+    /// For changes that only lose a little history if they fail to save.
+    fn save_or_warn(&self) {
+        if let Err(e) = self.save() {
+            log::warn!("Failed to save profiles: {:#}", e);
+        }
+    }
+
+    pub fn profiles(&self) -> &[Profile] {
+        &self.file.profiles
+    }
+
+    pub fn get(&self, id: u32) -> Option<&Profile> {
+        self.file.profiles.iter().find(|p| p.id == id)
+    }
+
+    fn get_mut(&mut self, id: u32) -> Result<&mut Profile> {
+        match self.file.profiles.iter_mut().find(|p| p.id == id) {
+            Some(profile) => Ok(profile),
+            None => bail!("no profile with id {}", id),
+        }
+    }
+
+    pub fn active(&self) -> &Profile {
+        self.get(self.file.active)
+            .expect("the active profile always exists")
+    }
+
+    pub fn profile_dir(&self, id: u32) -> PathBuf {
+        self.dir.join(PROFILES_DIR).join(id.to_string())
+    }
+
+    /// Settings file of the active profile.
+    pub fn settings_path(&self) -> PathBuf {
+        self.profile_dir(self.file.active)
+            .join(settings::SETTINGS_FILE)
+    }
+
+    /// Trimmed `name`, if it is non-empty, short enough and not already
+    /// used by a profile other than `except`.
+    fn check_name(&self, name: &str, except: Option<u32>) -> Result<String> {
+        let name = name.trim();
+        if name.is_empty() {
+            bail!("the name is empty");
+        }
+        if name.chars().count() > MAX_NAME_LEN {
+            bail!("the name is longer than {} characters", MAX_NAME_LEN);
+        }
+        let taken = self
+            .file
+            .profiles
+            .iter()
+            .any(|p| Some(p.id) != except && p.name.eq_ignore_ascii_case(name));
+        if taken {
+            bail!("there is already a profile called {}", name);
+        }
+        Ok(name.to_string())
+    }
+
+    /// Adds a profile and returns its id. It is not switched to.
+    pub fn create(&mut self, name: &str) -> Result<u32> {
+        let name = self.check_name(name, None)?;
+        let id = self.file.next_id;
+        self.file.next_id += 1;
+        self.file.profiles.push(Profile::new(id, name));
+        self.save()?;
+        Ok(id)
+    }
+
+    pub fn rename(&mut self, id: u32, name: &str) -> Result<()> {
+        let name = self.check_name(name, Some(id))?;
+        self.get_mut(id)?.name = name;
+        self.save()
+    }
+
+    /// Removes a profile and its directory. The last profile cannot be
+    /// deleted; deleting the active one switches to the first remaining.
+    pub fn delete(&mut self, id: u32) -> Result<()> {
+        if self.file.profiles.len() <= 1 {
+            bail!("the last profile cannot be deleted");
+        }
+        let index = self
+            .file
+            .profiles
+            .iter()
+            .position(|p| p.id == id)
+            .with_context(|| format!("no profile with id {}", id))?;
+        self.file.profiles.remove(index);
+        if self.file.active == id {
+            self.file.active = self.file.profiles[0].id;
+        }
+        self.save()?;
+
+        let dir = self.profile_dir(id);
+        if dir.exists() {
+            if let Err(e) = std::fs::remove_dir_all(&dir) {
+                log::warn!("Cannot remove {}: {}", dir.display(), e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Makes `id` the active profile. The caller reopens the settings from
+    /// `settings_path`.
+    pub fn switch(&mut self, id: u32) -> Result<()> {
+        self.get_mut(id)?;
+        self.file.active = id;
+        self.save()
+    }
+
+    /// Copies `source` in as the profile's avatar, or clears it with `None`.
+    pub fn set_avatar(&mut self, id: u32, source: Option<&Path>) -> Result<()> {
+        let old = self.get_mut(id)?.avatar.clone();
+        let avatar = match source {
+            Some(source) => {
+                let extension = source
+                    .extension()
+                    .and_then(|e| e.to_str())
+                    .map(str::to_ascii_lowercase)
+                    .filter(|e| AVATAR_EXTENSIONS.contains(&e.as_str()))
+                    .with_context(|| format!("{} is not a PNG or JPEG", source.display()))?;
+                let target = self.profile_dir(id).join(format!("avatar.{}", extension));
+                if source != target {
+                    copy_into(source, &target)?;
+                }
+                Some(target)
+            }
+            None => None,
+        };
+        // A different extension leaves the previous copy behind
+        if let Some(old) = old.filter(|old| Some(old) != avatar.as_ref()) {
+            if old.starts_with(self.profile_dir(id)) {
+                let _ = std::fs::remove_file(old);
+            }
+        }
+        self.get_mut(id)?.avatar = avatar;
+        self.save()
+    }
+
+    /// Adds or removes `app` from the active profile's favourites and
+    /// returns whether it is now a favourite.
+    pub fn toggle_favourite(&mut self, app: &str) -> bool {
+        let active = self.file.active;
+        let Ok(profile) = self.get_mut(active) else {
+            return false;
+        };
+        let added = match profile.favourites.iter().position(|f| f == app) {
+            Some(index) => {
+                profile.favourites.remove(index);
+                false
+            }
+            None => {
+                profile.favourites.push(app.to_string());
+                true
+            }
+        };
+        self.save_or_warn();
+        added
+    }
+
+    /// Moves `app` to the front of the active profile's recents.
+    pub fn record_launch(&mut self, app: &str) {
+        let active = self.file.active;
+        let Ok(profile) = self.get_mut(active) else {
+            return;
+        };
+        profile.recents.retain(|r| r != app);
+        profile.recents.insert(0, app.to_string());
+        profile.recents.truncate(MAX_RECENTS);
+        self.save_or_warn();
+    }
+}
+
+/// Images that can be picked as an avatar: those in `assets/avatars`, then
+/// any at the top level or in an `avatars` folder of a removable drive.
+pub fn avatar_candidates(assets: &Path) -> Vec<PathBuf> {
+    let mut candidates = images_in(&assets.join("avatars"));
+
+    for root in holotape::mount_points() {
+        candidates.extend(images_in(&root));
+        candidates.extend(images_in(&root.join("avatars")));
+    }
+    candidates
+}
+
+fn images_in(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut images: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .and_then(|e| e.to_str())
+                    .is_some_and(|e| AVATAR_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+        })
+        .collect();
+    images.sort();
+    images
+}
+
+fn copy_into(source: &Path, target: &Path) -> Result<()> {
+    if let Some(dir) = target.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+    }
+    std::fs::copy(source, target)
+        .with_context(|| format!("copying {} to {}", source.display(), target.display()))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("kips_ui-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn created_profiles_can_be_switched_to_and_are_kept() {
+        let dir = temp_dir("profiles-switch");
+        let mut store = ProfileStore::open(&dir);
+        assert_eq!(store.active().name, DEFAULT_NAME);
+
+        let id = store.create("  Vault Dweller ").unwrap();
+        assert_eq!(store.active().name, DEFAULT_NAME);
+        assert!(store.create("vault dweller").is_err());
+        store.switch(id).unwrap();
+        store.record_launch("audio");
+
+        let store = ProfileStore::open(&dir);
+        assert_eq!(store.active().id, id);
+        assert_eq!(store.active().name, "Vault Dweller");
+        assert_eq!(store.active().recents, ["audio"]);
+        assert_eq!(
+            store.settings_path(),
+            store.profile_dir(id).join(settings::SETTINGS_FILE)
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn the_last_profile_cannot_be_deleted() {
+        let dir = temp_dir("profiles-delete");
+        let mut store = ProfileStore::open(&dir);
+        let first = store.active().id;
+        let second = store.create("Second").unwrap();
+        std::fs::create_dir_all(store.profile_dir(first)).unwrap();
+
+        // Deleting the active profile falls back to the one left
+        store.delete(first).unwrap();
+        assert_eq!(store.active().id, second);
+        assert!(!store.profile_dir(first).exists());
+
+        let err = store.delete(second).unwrap_err();
+        assert_eq!(err.to_string(), "the last profile cannot be deleted");
+        assert_eq!(store.profiles().len(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,367 @@
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// Where `--config` keeps one manifest per app.
+pub const APPS_DIR: &str = "apps";
+
+/// How an app is started.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AppExec {
+    /// A program on `PATH`, or an absolute path.
+    Command {
+        program: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+    Flatpak {
+        app_id: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+    /// One of the apps built into kips_ui, by name.
+    Internal { name: String },
+    /// A screen of the shell itself, such as `files`.
+    Screen { name: String },
+}
+
+/// One app, as read from `apps/<id>.json`. Arguments may contain `{file}`,
+/// replaced by the file being opened; without it the file is appended.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AppManifest {
+    /// Stable name, used by profiles' favourites and recents.
+    pub id: String,
+    pub name: String,
+    /// Image shown next to the name, relative to the assets directory.
+    #[serde(default)]
+    pub icon: Option<PathBuf>,
+    pub category: String,
+    /// Position in the menus; ties sort by name.
+    #[serde(default)]
+    pub order: i32,
+    pub exec: AppExec,
+    /// Extensions (`odt`) or MIME types (`text/plain`, `image/*`) the app
+    /// can open.
+    #[serde(default)]
+    pub file_types: Vec<String>,
+    /// Launching the app again while it runs brings the running copy
+    /// forward instead of starting another.
+    #[serde(default = "single_instance")]
+    pub single_instance: bool,
+}
+
+fn single_instance() -> bool {
+    true
+}
+
+impl AppManifest {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("reading app manifest {}", path.display()))?;
+        let manifest: AppManifest = serde_json::from_str(&text)
+            .with_context(|| format!("parsing app manifest {}", path.display()))?;
+        manifest
+            .validate()
+            .with_context(|| format!("invalid app manifest {}", path.display()))?;
+        Ok(manifest)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.id.trim().is_empty() {
+            bail!("`id` is empty");
+        }
+        if self.name.trim().is_empty() {
+            bail!("{}: `name` is empty", self.id);
+        }
+        if self.category.trim().is_empty() {
+            bail!("{}: `category` is empty", self.id);
+        }
+        let empty = match &self.exec {
+            AppExec::Command { program, .. } => program.trim().is_empty(),
+            AppExec::Flatpak { app_id, .. } => app_id.trim().is_empty(),
+            AppExec::Internal { name } | AppExec::Screen { name } => name.trim().is_empty(),
+        };
+        if empty {
+            bail!("{}: `exec` names nothing to run", self.id);
+        }
+        Ok(())
+    }
+
+    /// `icon` resolved against `assets`.
+    pub fn icon_path(&self, assets: &Path) -> Option<PathBuf> {
+        self.icon.as_ref().map(|icon| assets.join(icon))
+    }
+}
+
+/// Every app the shell can launch, sorted for the menus.
+#[derive(Debug, Clone)]
+pub struct AppRegistry {
+    apps: Vec<AppManifest>,
+}
+
+impl AppRegistry {
+    pub fn new(mut apps: Vec<AppManifest>) -> Self {
+        apps.sort_by(|a, b| a.order.cmp(&b.order).then_with(|| a.name.cmp(&b.name)));
+        Self { apps }
+    }
+
+    /// Reads every `*.json` in `dir`. A manifest that does not load, or
+    /// repeats an id, is skipped with a warning so one bad file does not
+    /// empty the menus.
+    pub fn load_dir(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref();
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)
+            .with_context(|| format!("listing apps in {}", dir.display()))?
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|e| e == "json"))
+            .collect();
+        paths.sort();
+
+        let mut ids = HashSet::new();
+        let mut apps = Vec::new();
+        for path in paths {
+            match AppManifest::load(&path) {
+                Ok(app) if !ids.insert(app.id.clone()) => {
+                    log::warn!(
+                        "Skipping {}: app `{}` is already defined",
+                        path.display(),
+                        app.id
+                    );
+                }
+                Ok(app) => apps.push(app),
+                Err(e) => log::warn!("Skipping app: {:#}", e),
+            }
+        }
+        Ok(Self::new(apps))
+    }
+
+    /// Uses the built-in apps when `dir` does not exist.
+    pub fn load_or_default(dir: impl AsRef<Path>) -> Result<Self> {
+        if dir.as_ref().exists() {
+            Self::load_dir(dir)
+        } else {
+            Ok(Self::default())
+        }
+    }
+
+    pub fn apps(&self) -> &[AppManifest] {
+        &self.apps
+    }
+
+    pub fn get(&self, id: &str) -> Option<&AppManifest> {
+        self.apps.iter().find(|app| app.id == id)
+    }
+
+    /// Categories in menu order: that of each one's first app.
+    pub fn categories(&self) -> Vec<&str> {
+        let mut categories: Vec<&str> = Vec::new();
+        for app in &self.apps {
+            if !categories.contains(&app.category.as_str()) {
+                categories.push(&app.category);
+            }
+        }
+        categories
+    }
+
+    pub fn in_category<'a>(&'a self, category: &'a str) -> impl Iterator<Item = &'a AppManifest> {
+        self.apps.iter().filter(move |app| app.category == category)
+    }
+}
+
+impl Default for AppRegistry {
+    fn default() -> Self {
+        fn app(id: &str, name: &str, category: &str, order: i32, exec: AppExec) -> AppManifest {
+            AppManifest {
+                id: id.to_string(),
+                name: name.to_string(),
+                icon: None,
+                category: category.to_string(),
+                order,
+                exec,
+                file_types: Vec::new(),
+                single_instance: true,
+            }
+        }
+        fn command(program: &str, args: &[&str]) -> AppExec {
+            AppExec::Command {
+                program: program.to_string(),
+                args: args.iter().map(|a| a.to_string()).collect(),
+            }
+        }
+        fn flatpak(app_id: &str) -> AppExec {
+            AppExec::Flatpak {
+                app_id: app_id.to_string(),
+                args: Vec::new(),
+            }
+        }
+        fn internal(name: &str) -> AppExec {
+            AppExec::Internal {
+                name: name.to_string(),
+            }
+        }
+        fn screen(name: &str) -> AppExec {
+            AppExec::Screen {
+                name: name.to_string(),
+            }
+        }
+        fn types(mut app: AppManifest, file_types: &[&str]) -> AppManifest {
+            app.file_types = file_types.iter().map(|t| t.to_string()).collect();
+            app
+        }
+        fn multi_instance(mut app: AppManifest) -> AppManifest {
+            app.single_instance = false;
+            app
+        }
+
+        Self::new(vec![
+            types(
+                app("audio", "Audio player", "Media", 10, internal("audio")),
+                &["wav", "audio/wav"],
+            ),
+            types(
+                app("video", "Video player", "Media", 20, internal("video")),
+                &["mp4", "mkv", "video/*"],
+            ),
+            types(
+                app(
+                    "image-viewer",
+                    "Image viewer",
+                    "Media",
+                    25,
+                    command("gpicview", &[]),
+                ),
+                &["png", "jpg", "jpeg", "gif", "bmp", "image/*"],
+            ),
+            app(
+                "calendar",
+                "Calendar",
+                "Organiser",
+                30,
+                flatpak("org.gnome.Calendar"),
+            ),
+            app(
+                "notes",
+                "Notes",
+                "Organiser",
+                40,
+                flatpak("com.github.flxzt.rnote"),
+            ),
+            app(
+                "todo",
+                "To-do list",
+                "Organiser",
+                50,
+                flatpak("io.github.mrvladus.List"),
+            ),
+            app("maps", "Maps", "Organiser", 60, flatpak("org.gnome.Maps")),
+            app("weather", "Weather", "Organiser", 70, internal("weather")),
+            types(
+                app(
+                    "writer",
+                    "Writer",
+                    "Office",
+                    80,
+                    command("libreoffice", &["--writer"]),
+                ),
+                &["odt", "doc", "docx", "rtf"],
+            ),
+            types(
+                app(
+                    "calc",
+                    "Calc",
+                    "Office",
+                    90,
+                    command("libreoffice", &["--calc"]),
+                ),
+                &["ods", "xls", "xlsx", "csv"],
+            ),
+            types(
+                app(
+                    "impress",
+                    "Impress",
+                    "Office",
+                    100,
+                    command("libreoffice", &["--impress"]),
+                ),
+                &["odp", "ppt", "pptx"],
+            ),
+            types(
+                app(
+                    "text-editor",
+                    "Text editor",
+                    "Office",
+                    110,
+                    command("mousepad", &[]),
+                ),
+                &["txt", "md", "text/*"],
+            ),
+            types(
+                app("files", "Files", "System", 120, screen("files")),
+                &["inode/directory"],
+            ),
+            multi_instance(app(
+                "terminal",
+                "Terminal",
+                "System",
+                130,
+                command("lxterminal", &[]),
+            )),
+            types(
+                app("ide", "IDE", "System", 140, command("thonny", &[])),
+                &["py"],
+            ),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn manifest(id: &str, name: &str, order: i32) -> String {
+        format!(
+            r#"{{ "id": "{}", "name": "{}", "category": "Tools", "order": {},
+                 "exec": {{ "type": "command", "program": "{}" }} }}"#,
+            id, name, order, id
+        )
+    }
+
+    #[test]
+    fn bad_manifests_are_skipped() {
+        let dir = std::env::temp_dir().join(format!("kips_ui-registry-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("b-notes.json"), manifest("notes", "Notes", 2)).unwrap();
+        fs::write(dir.join("a-clock.json"), manifest("clock", "Clock", 1)).unwrap();
+        fs::write(dir.join("broken.json"), r#"{ "id": "broken", "name": "#).unwrap();
+        fs::write(dir.join("empty.json"), manifest("empty", " ", 0)).unwrap();
+        // Loads after a-clock.json, so the first `clock` wins
+        fs::write(
+            dir.join("c-clock.json"),
+            manifest("clock", "Other clock", 0),
+        )
+        .unwrap();
+        fs::write(dir.join("readme.txt"), "not a manifest").unwrap();
+
+        let registry = AppRegistry::load_dir(&dir).unwrap();
+        let names: Vec<&str> = registry
+            .apps()
+            .iter()
+            .map(|app| app.name.as_str())
+            .collect();
+        assert_eq!(names, ["Clock", "Notes"]);
+        assert_eq!(registry.categories(), ["Tools"]);
+        assert!(AppRegistry::load_dir(dir.join("missing")).is_err());
+        assert_eq!(
+            AppRegistry::load_or_default(dir.join("missing"))
+                .unwrap()
+                .apps(),
+            AppRegistry::default().apps()
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
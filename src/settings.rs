@@ -0,0 +1,363 @@
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+
+pub const SETTINGS_FILE: &str = "settings.json";
+
+/// Bumped whenever a field is renamed, moved or changes meaning; each bump
+/// adds a step to `MIGRATIONS`. New fields with a default need no bump.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// `MIGRATIONS[n]` turns a version `n` file into version `n + 1`.
+const MIGRATIONS: [fn(&mut Value); SCHEMA_VERSION as usize] = [
+    // Version 0 is a file saved before `version` existed; the layout is
+    // otherwise the same.
+    |_| {},
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Theme {
+    #[default]
+    Green,
+    Amber,
+    Blue,
+    White,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DisplaySettings {
+    /// Percent, 0-100.
+    pub brightness: u8,
+    pub theme: Theme,
+    /// Blank the screen after this long without input; 0 never blanks.
+    pub screen_timeout_secs: u32,
+}
+
+impl Default for DisplaySettings {
+    fn default() -> Self {
+        Self {
+            brightness: 80,
+            theme: Theme::Green,
+            screen_timeout_secs: 300,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SoundSettings {
+    /// Percent, 0-100.
+    pub volume: u8,
+    pub muted: bool,
+    /// Output device name, passed to the apps the shell starts; `None` uses
+    /// the system default.
+    pub output: Option<String>,
+}
+
+impl Default for SoundSettings {
+    fn default() -> Self {
+        Self {
+            volume: 60,
+            muted: false,
+            output: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StorageSettings {
+    /// Offer to open a USB drive (holotape) when one is plugged in.
+    pub usb_popup: bool,
+}
+
+impl Default for StorageSettings {
+    fn default() -> Self {
+        Self { usb_popup: true }
+    }
+}
+
+/// Everything the user can change on the settings screen. Missing fields
+/// take their defaults, so older files keep loading as fields are added.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub version: u32,
+    pub display: DisplaySettings,
+    pub sound: SoundSettings,
+    pub storage: StorageSettings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            version: SCHEMA_VERSION,
+            display: DisplaySettings::default(),
+            sound: SoundSettings::default(),
+            storage: StorageSettings::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Section {
+    Display,
+    Sound,
+    Storage,
+}
+
+/// Sent to subscribers after a change is saved.
+#[derive(Debug, Clone)]
+pub struct SettingsChange {
+    pub sections: Vec<Section>,
+    pub settings: Settings,
+}
+
+impl Settings {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("reading settings {}", path.display()))?;
+        Self::from_json(&text).with_context(|| format!("parsing settings {}", path.display()))
+    }
+
+    /// Parses any schema version up to `SCHEMA_VERSION`, migrating older ones.
+    pub fn from_json(text: &str) -> Result<Self> {
+        let mut value: Value = serde_json::from_str(text)?;
+        if !value.is_object() {
+            bail!("expected a JSON object");
+        }
+
+        let version = value.get("version").and_then(Value::as_u64).unwrap_or(0);
+        if version > SCHEMA_VERSION as u64 {
+            bail!(
+                "saved by a newer version (schema {}, this build reads up to {})",
+                version,
+                SCHEMA_VERSION
+            );
+        }
+        for migrate in &MIGRATIONS[version as usize..] {
+            migrate(&mut value);
+        }
+        value["version"] = SCHEMA_VERSION.into();
+
+        let mut settings: Settings = serde_json::from_value(value)?;
+        settings.clamp();
+        Ok(settings)
+    }
+
+    /// Writes to a temporary file and renames it over `path`, so a power cut
+    /// leaves either the old or the new settings, never half of each.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        write_atomic(path.as_ref(), serde_json::to_string_pretty(self)?.as_bytes())
+    }
+
+    /// Sections whose values differ from `other`.
+    pub fn changed_sections(&self, other: &Settings) -> Vec<Section> {
+        let mut sections = Vec::new();
+        if self.display != other.display {
+            sections.push(Section::Display);
+        }
+        if self.sound != other.sound {
+            sections.push(Section::Sound);
+        }
+        if self.storage != other.storage {
+            sections.push(Section::Storage);
+        }
+        sections
+    }
+
+    fn clamp(&mut self) {
+        self.display.brightness = self.display.brightness.min(100);
+        self.sound.volume = self.sound.volume.min(100);
+    }
+}
+
+/// Writes `contents` to a temporary file next to `path`, syncs it and
+/// renames it into place, creating the parent directory if needed.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir).with_context(|| format!("creating {}", dir.display()))?;
+    }
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    let mut file = File::create(&tmp).with_context(|| format!("creating {}", tmp.display()))?;
+    file.write_all(contents)
+        .and_then(|_| file.sync_all())
+        .with_context(|| format!("writing {}", tmp.display()))?;
+    std::fs::rename(&tmp, path).with_context(|| format!("replacing {}", path.display()))
+}
+
+/// Owns the current settings and their file. Every change goes through
+/// `update`, which saves it and tells subscribers what changed.
+pub struct SettingsStore {
+    path: PathBuf,
+    settings: Settings,
+    subscribers: Vec<Sender<SettingsChange>>,
+}
+
+impl SettingsStore {
+    /// Loads `path`, starting from defaults when it does not exist. A file
+    /// that cannot be read is moved aside to `*.bak` rather than overwritten
+    /// by the next save.
+    pub fn open(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let settings = if path.exists() {
+            Settings::load(&path).unwrap_or_else(|e| {
+                let backup = path.with_extension("json.bak");
+                log::warn!("Using default settings: {:#}", e);
+                if let Err(e) = std::fs::rename(&path, &backup) {
+                    log::warn!("Cannot move {} aside: {}", path.display(), e);
+                }
+                Settings::default()
+            })
+        } else {
+            Settings::default()
+        };
+
+        Self {
+            path,
+            settings,
+            subscribers: Vec::new(),
+        }
+    }
+
+    pub fn get(&self) -> &Settings {
+        &self.settings
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Receives a `SettingsChange` after every saved change. Dropping the
+    /// receiver unsubscribes.
+    pub fn subscribe(&mut self) -> Receiver<SettingsChange> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers.push(sender);
+        receiver
+    }
+
+    /// Applies `change`, saves and notifies subscribers. Nothing is saved or
+    /// sent when the values end up the same. On a failed save the change is
+    /// kept in memory and the error returned.
+    pub fn update(&mut self, change: impl FnOnce(&mut Settings)) -> Result<()> {
+        let mut updated = self.settings.clone();
+        change(&mut updated);
+        updated.version = SCHEMA_VERSION;
+        updated.clamp();
+
+        let sections = updated.changed_sections(&self.settings);
+        if sections.is_empty() {
+            return Ok(());
+        }
+        self.settings = updated;
+
+        let saved = self.settings.save(&self.path);
+        let change = SettingsChange {
+            sections,
+            settings: self.settings.clone(),
+        };
+        self.subscribers
+            .retain(|subscriber| subscriber.send(change.clone()).is_ok());
+        saved
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("kips_ui-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn old_files_are_migrated() {
+        // Saved before `version` existed, with the `time` section since dropped
+        let settings = Settings::from_json(
+            r#"{ "display": { "theme": "amber" }, "time": { "use_24_hour": true },
+                 "storage": { "usb_popup": false } }"#,
+        )
+        .unwrap();
+        assert_eq!(settings.version, SCHEMA_VERSION);
+        assert_eq!(settings.display.theme, Theme::Amber);
+        assert_eq!(settings.display.brightness, 80);
+        assert!(!settings.storage.usb_popup);
+        assert_eq!(settings.sound, SoundSettings::default());
+
+        let newer = format!(r#"{{ "version": {} }}"#, SCHEMA_VERSION + 1);
+        assert!(Settings::from_json(&newer).is_err());
+    }
+
+    #[test]
+    fn out_of_range_values_are_clamped() {
+        let settings = Settings::from_json(
+            r#"{ "version": 1, "display": { "brightness": 250 }, "sound": { "volume": 101 } }"#,
+        )
+        .unwrap();
+        assert_eq!(settings.display.brightness, 100);
+        assert_eq!(settings.sound.volume, 100);
+
+        assert!(Settings::from_json(r#"{ "display": { "brightness": 300 } }"#).is_err());
+        assert!(Settings::from_json("[]").is_err());
+    }
+
+    #[test]
+    fn unreadable_files_are_moved_aside() {
+        let dir = temp_dir("settings-bak");
+        let path = dir.join(SETTINGS_FILE);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(&path, "{ not json").unwrap();
+
+        let mut store = SettingsStore::open(&path);
+        assert_eq!(store.get(), &Settings::default());
+        assert!(!path.exists());
+        let backup = dir.join("settings.json.bak");
+        assert_eq!(std::fs::read_to_string(&backup).unwrap(), "{ not json");
+
+        // The next save starts a fresh file and leaves the backup alone
+        store
+            .update(|settings| settings.sound.muted = true)
+            .unwrap();
+        assert!(Settings::load(&path).unwrap().sound.muted);
+        assert!(backup.exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn subscribers_hear_saved_changes() {
+        let dir = temp_dir("settings-subscribe");
+        let mut store = SettingsStore::open(dir.join(SETTINGS_FILE));
+        let changes = store.subscribe();
+
+        store
+            .update(|settings| settings.display.theme = Theme::Blue)
+            .unwrap();
+        store
+            .update(|settings| settings.display.theme = Theme::Blue)
+            .unwrap();
+        store
+            .update(|settings| settings.sound.volume = 200)
+            .unwrap();
+
+        let change = changes.try_recv().unwrap();
+        assert_eq!(change.sections, [Section::Display]);
+        assert_eq!(change.settings.display.theme, Theme::Blue);
+        let change = changes.try_recv().unwrap();
+        assert_eq!(change.sections, [Section::Sound]);
+        assert_eq!(change.settings.sound.volume, 100);
+        // Nothing changed the second time, so nothing was sent
+        assert!(changes.try_recv().is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
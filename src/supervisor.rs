@@ -0,0 +1,316 @@
+// src/supervisor.rs
+use crate::launcher;
+use crate::registry::AppManifest;
+use anyhow::{bail, Context, Result};
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Read};
+use std::os::unix::process::ExitStatusExt;
+use std::path::Path;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Lines of stderr kept for each app.
+const STDERR_LINES: usize = 20;
+/// How many exited apps are remembered for the running-apps screen.
+const MAX_EXITED: usize = 10;
+/// How long an exited app's stderr reader gets to read its last lines.
+const STDERR_DRAIN: Duration = Duration::from_millis(500);
+
+type StderrTail = Arc<Mutex<VecDeque<String>>>;
+
+/// An app the shell started that has not exited yet.
+#[derive(Debug)]
+pub struct RunningApp {
+    pub app_id: String,
+    pub name: String,
+    pub pid: u32,
+    pub started: Instant,
+    child: Child,
+    stderr: StderrTail,
+    stderr_reader: Option<JoinHandle<()>>,
+}
+
+impl RunningApp {
+    /// The last lines the app wrote to stderr, oldest first.
+    pub fn stderr_tail(&self) -> Vec<String> {
+        tail_lines(&self.stderr)
+    }
+}
+
+/// An app that has exited, with what it left behind.
+#[derive(Debug, Clone)]
+pub struct ExitedApp {
+    pub app_id: String,
+    pub name: String,
+    pub pid: u32,
+    /// `None` if the status could not be read.
+    pub status: Option<ExitStatus>,
+    /// Stopped from the shell rather than exiting by itself.
+    pub killed: bool,
+    pub ran_for: Duration,
+    pub stderr: Vec<String>,
+}
+
+impl ExitedApp {
+    /// Exited successfully, or was stopped on purpose.
+    pub fn ok(&self) -> bool {
+        self.killed || self.status.is_some_and(|status| status.success())
+    }
+
+    /// "exit 0", "signal 11", "stopped"…
+    pub fn describe(&self) -> String {
+        if self.killed {
+            return "stopped".to_string();
+        }
+        match self.status {
+            Some(status) => match (status.code(), status.signal()) {
+                (Some(code), _) => format!("exit {}", code),
+                (None, Some(signal)) => format!("signal {}", signal),
+                (None, None) => "exited".to_string(),
+            },
+            None => "unknown".to_string(),
+        }
+    }
+}
+
+/// What `Supervisor::launch` did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Launch {
+    Started(u32),
+    /// The app is single-instance and was already running with this pid;
+    /// it was brought forward instead.
+    AlreadyRunning(u32),
+}
+
+/// Owns every app the shell launches: starts them, reaps them when they
+/// exit so none is left a zombie, and keeps the tail of their stderr.
+#[derive(Debug, Default)]
+pub struct Supervisor {
+    running: Vec<RunningApp>,
+    /// The app that owns the screen, if any; the shell stays paused behind
+    /// it until it exits or the shell is called back.
+    foreground: Option<u32>,
+    /// Newest first.
+    exited: VecDeque<ExitedApp>,
+}
+
+impl Supervisor {
+    /// Starts `app`, opening `file` if given. `audio_output` is the sound
+    /// output device to tell it about, if not the system default.
+    pub fn launch(
+        &mut self,
+        app: &AppManifest,
+        file: Option<&Path>,
+        audio_output: Option<&str>,
+    ) -> Result<Launch> {
+        if app.single_instance {
+            if let Some(pid) = self.find(&app.id).map(|running| running.pid) {
+                log::info!("{} is already running (pid {})", app.name, pid);
+                if let Err(e) = self.focus(pid) {
+                    log::debug!("Cannot focus {}: {:#}", app.name, e);
+                }
+                return Ok(Launch::AlreadyRunning(pid));
+            }
+        }
+
+        let mut command = launcher::command(app, file)?;
+        if let Some(output) = audio_output {
+            command.env(launcher::AUDIO_OUTPUT_ENV, output);
+        }
+        let mut child = command
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("starting {}", app.name))?;
+        let pid = child.id();
+        let stderr = StderrTail::default();
+        let stderr_reader = child.stderr.take().map(|pipe| {
+            let tail = Arc::clone(&stderr);
+            let id = app.id.clone();
+            thread::spawn(move || read_stderr(pipe, &id, &tail))
+        });
+        log::info!("Started {} (pid {})", app.name, pid);
+
+        self.running.push(RunningApp {
+            app_id: app.id.clone(),
+            name: app.name.clone(),
+            pid,
+            started: Instant::now(),
+            child,
+            stderr,
+            stderr_reader,
+        });
+        self.foreground = Some(pid);
+        Ok(Launch::Started(pid))
+    }
+
+    /// Collects the apps that exited since the last call. Call it every
+    /// frame.
+    pub fn reap(&mut self) -> Vec<ExitedApp> {
+        let mut exited = Vec::new();
+        let mut i = 0;
+        while i < self.running.len() {
+            match self.running[i].child.try_wait() {
+                Ok(None) => i += 1,
+                Ok(Some(status)) => exited.push(self.finish(i, Some(status), false)),
+                Err(e) => {
+                    log::warn!("Lost track of {}: {}", self.running[i].name, e);
+                    exited.push(self.finish(i, None, false));
+                }
+            }
+        }
+        exited
+    }
+
+    /// Stops the app with `pid` and waits for it.
+    pub fn kill(&mut self, pid: u32) -> Result<()> {
+        let Some(i) = self.running.iter().position(|app| app.pid == pid) else {
+            bail!("no running app has pid {}", pid);
+        };
+        let child = &mut self.running[i].child;
+        child
+            .kill()
+            .with_context(|| format!("stopping pid {}", pid))?;
+        let status = child.wait().ok();
+        self.finish(i, status, true);
+        Ok(())
+    }
+
+    /// Raises the app's window over the shell and hands it the screen.
+    pub fn focus(&mut self, pid: u32) -> Result<()> {
+        let status = Command::new("xdotool")
+            .args(["search", "--pid", &pid.to_string(), "windowactivate"])
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .context("running xdotool")?;
+        if !status.success() {
+            bail!("no window found for pid {}", pid);
+        }
+        self.foreground = Some(pid);
+        Ok(())
+    }
+
+    /// The app that owns the screen.
+    pub fn foreground(&self) -> Option<&RunningApp> {
+        let pid = self.foreground?;
+        self.running.iter().find(|app| app.pid == pid)
+    }
+
+    /// Takes the screen back from the foreground app, which keeps running.
+    pub fn release_foreground(&mut self) {
+        self.foreground = None;
+    }
+
+    pub fn running(&self) -> &[RunningApp] {
+        &self.running
+    }
+
+    /// Recently exited apps, newest first.
+    pub fn exited(&self) -> impl Iterator<Item = &ExitedApp> {
+        self.exited.iter()
+    }
+
+    pub fn find(&self, app_id: &str) -> Option<&RunningApp> {
+        self.running.iter().find(|app| app.app_id == app_id)
+    }
+
+    fn finish(&mut self, index: usize, status: Option<ExitStatus>, killed: bool) -> ExitedApp {
+        let mut app = self.running.remove(index);
+        if self.foreground == Some(app.pid) {
+            self.foreground = None;
+        }
+        // The last lines, usually the crash message, are still in the pipe
+        if let Some(reader) = app.stderr_reader.take() {
+            join_reader(reader);
+        }
+        let exited = ExitedApp {
+            stderr: app.stderr_tail(),
+            app_id: app.app_id,
+            name: app.name,
+            pid: app.pid,
+            status,
+            killed,
+            ran_for: app.started.elapsed(),
+        };
+        if exited.ok() {
+            log::info!("{} exited ({})", exited.name, exited.describe());
+        } else {
+            log::warn!("{} exited ({})", exited.name, exited.describe());
+            for line in &exited.stderr {
+                log::warn!("  {}", line);
+            }
+        }
+
+        self.exited.push_front(exited.clone());
+        self.exited.truncate(MAX_EXITED);
+        exited
+    }
+}
+
+fn read_stderr(pipe: impl Read, app_id: &str, tail: &StderrTail) {
+    for line in BufReader::new(pipe).lines().map_while(Result::ok) {
+        log::debug!("{}: {}", app_id, line);
+        if let Ok(mut tail) = tail.lock() {
+            if tail.len() == STDERR_LINES {
+                tail.pop_front();
+            }
+            tail.push_back(line);
+        }
+    }
+}
+
+/// Joins a stderr reader once the pipe closes. A grandchild can hold the
+/// pipe open long after the app has gone, so this gives up after
+/// `STDERR_DRAIN` and leaves the reader to finish on its own.
+fn join_reader(reader: JoinHandle<()>) {
+    let deadline = Instant::now() + STDERR_DRAIN;
+    while !reader.is_finished() {
+        if Instant::now() >= deadline {
+            return;
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+    let _ = reader.join();
+}
+
+fn tail_lines(tail: &StderrTail) -> Vec<String> {
+    tail.lock()
+        .map(|tail| tail.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exits_keep_their_status_and_last_stderr() {
+        let app: AppManifest = serde_json::from_str(
+            r#"{ "id": "crash", "name": "Crash", "category": "Test",
+                 "exec": { "type": "command", "program": "sh",
+                           "args": ["-c", "echo boom >&2; exit 3"] } }"#,
+        )
+        .unwrap();
+        let mut supervisor = Supervisor::default();
+        let Launch::Started(pid) = supervisor.launch(&app, None, None).unwrap() else {
+            panic!("not started");
+        };
+
+        let deadline = Instant::now() + Duration::from_secs(10);
+        let exited = loop {
+            if let Some(exited) = supervisor.reap().pop() {
+                break exited;
+            }
+            assert!(Instant::now() < deadline, "the app never exited");
+            thread::sleep(Duration::from_millis(1));
+        };
+        assert_eq!(exited.pid, pid);
+        assert!(!exited.ok());
+        assert_eq!(exited.describe(), "exit 3");
+        assert_eq!(exited.stderr, ["boom"]);
+        assert!(supervisor.running().is_empty());
+        assert!(supervisor.foreground().is_none());
+    }
+}
@@ -0,0 +1,54 @@
+// src/ui/calibration.rs
+use crate::input::InputEvent;
+use crate::ui::global_renderer::GlobalRenderer;
+use crate::ui::screen::{Screen, ScreenContext, Transition};
+
+/// Touchscreen calibration: shows each target in turn while `InputManager`
+/// collects the touches. Leaving early cancels the session.
+#[derive(Debug, Clone, Default)]
+pub struct CalibrationScreen {
+    /// Index into `input::calibration::TARGETS`
+    step: usize,
+    done: bool,
+}
+
+impl CalibrationScreen {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Screen for CalibrationScreen {
+    fn handle_input(&mut self, event: &InputEvent, ctx: &mut ScreenContext) -> Transition {
+        match *event {
+            InputEvent::CalibrationStep(step) => {
+                self.step = step;
+                Transition::Stay
+            }
+            InputEvent::CalibrationDone(saved) => {
+                self.done = true;
+                if !saved {
+                    ctx.notify("Calibration failed");
+                }
+                Transition::Pop
+            }
+            _ => Transition::Unhandled,
+        }
+    }
+
+    fn render(&self, renderer: &mut GlobalRenderer) {
+        renderer.render_calibration(self.step);
+    }
+
+    fn on_enter(&mut self, ctx: &mut ScreenContext) {
+        if !self.done {
+            ctx.input.start_calibration();
+        }
+    }
+
+    fn on_exit(&mut self, ctx: &mut ScreenContext) {
+        if !self.done {
+            ctx.input.cancel_calibration();
+        }
+    }
+}
@@ -0,0 +1,684 @@
+// src/ui/files.rs
+use crate::files::{self, Entry, FileKind, SortOrder};
+use crate::holotape;
+use crate::input::InputEvent;
+use crate::ui::global_renderer::GlobalRenderer;
+use crate::ui::list::{ListState, NameEditor};
+use crate::ui::open_with;
+use crate::ui::screen::{Screen, ScreenContext, Transition};
+use crate::ui::settings::{BACK_WIDTH, ROW_LEFT, ROW_TOP, ROW_WIDTH};
+use std::path::{Path, PathBuf};
+
+// Layout shared with `GlobalRenderer::render_files` for touch hit-testing
+pub const ENTRY_HEIGHT: i32 = 50;
+pub const VISIBLE_ENTRIES: usize = 7;
+pub const ICON_SIZE: u32 = 36;
+
+/// What a clipboard paste does with its file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PasteMode {
+    Copy,
+    Move,
+}
+
+/// A change to the disk, held until it is confirmed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileOp {
+    Paste {
+        from: PathBuf,
+        into: PathBuf,
+        mode: PasteMode,
+    },
+    Rename {
+        path: PathBuf,
+        name: String,
+    },
+    Delete(PathBuf),
+}
+
+impl FileOp {
+    fn title(&self) -> &'static str {
+        match self {
+            FileOp::Paste {
+                mode: PasteMode::Copy,
+                ..
+            } => "COPY HERE?",
+            FileOp::Paste {
+                mode: PasteMode::Move,
+                ..
+            } => "MOVE HERE?",
+            FileOp::Rename { .. } => "RENAME?",
+            FileOp::Delete(_) => "DELETE?",
+        }
+    }
+
+    /// The confirm row's label and detail; `mounts` are the holotapes'
+    /// mount points, for naming where a file comes from.
+    fn describe(&self, mounts: &[PathBuf]) -> (String, String) {
+        match self {
+            FileOp::Paste { from, mode, .. } => {
+                let verb = match mode {
+                    PasteMode::Copy => "Copy",
+                    PasteMode::Move => "Move",
+                };
+                (
+                    format!("{} {}", verb, file_name(from)),
+                    display_dir(from, mounts),
+                )
+            }
+            FileOp::Rename { path, name } => (
+                format!("Rename to {}", name),
+                format!("was {}", file_name(path)),
+            ),
+            FileOp::Delete(path) => (
+                format!("Delete {}", file_name(path)),
+                if path.is_dir() {
+                    "and everything in it".to_string()
+                } else {
+                    "cannot be undone".to_string()
+                },
+            ),
+        }
+    }
+
+    /// Carries out the change, returning the path to select afterwards
+    /// and what to tell the user.
+    fn run(&self) -> anyhow::Result<(Option<PathBuf>, String)> {
+        match self {
+            FileOp::Paste { from, into, mode } => {
+                let (target, verb) = match mode {
+                    PasteMode::Copy => (files::copy_to(from, into)?, "Copied"),
+                    PasteMode::Move => (files::move_to(from, into)?, "Moved"),
+                };
+                let message = format!("{} {}", verb, file_name(&target));
+                Ok((Some(target), message))
+            }
+            FileOp::Rename { path, name } => {
+                let target = files::rename(path, name)?;
+                Ok((Some(target), format!("Renamed to {}", name)))
+            }
+            FileOp::Delete(path) => {
+                files::delete(path)?;
+                Ok((None, format!("Deleted {}", file_name(path))))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BrowserPage {
+    List,
+    /// What can be done with an entry, or with the folder when `None`.
+    Actions(Option<PathBuf>),
+    /// Editing a new name. Only the stem is edited so the extension, which
+    /// picks the app that opens the file, survives encoder editing.
+    Rename {
+        path: PathBuf,
+        stem: NameEditor,
+        extension: String,
+    },
+    Confirm(FileOp),
+}
+
+/// What a row does when selected.
+#[derive(Debug, Clone, PartialEq)]
+enum RowAction {
+    Open(PathBuf),
+    Copy(PathBuf),
+    Cut(PathBuf),
+    Paste,
+    Rename(PathBuf),
+    Delete(PathBuf),
+    Sort,
+    Hidden,
+    SaveName,
+    Confirm,
+    Cancel,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BrowserRow {
+    pub label: String,
+    /// Shown right-aligned and dimmed.
+    pub detail: String,
+    pub kind: Option<FileKind>,
+    action: RowAction,
+}
+
+impl BrowserRow {
+    fn new(action: RowAction, label: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            detail: String::new(),
+            kind: None,
+            action,
+        }
+    }
+
+    fn detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = detail.into();
+        self
+    }
+}
+
+/// Browses folders on the device and its holotapes. Select opens a folder,
+/// or hands a file to open-with; Left goes up. Menu lists what can be done
+/// with the selected entry: copy or cut it for pasting elsewhere, rename or
+/// delete it, each confirmed first, or change the sort order.
+#[derive(Debug, Clone)]
+pub struct FileBrowser {
+    pub page: BrowserPage,
+    pub dir: PathBuf,
+    /// Back leaves the browser from here rather than going up.
+    start: PathBuf,
+    /// The folder as HOME, HOLOTAPE or / followed by the folders below it.
+    pub crumbs: Vec<String>,
+    pub title: String,
+    pub rows: Vec<BrowserRow>,
+    pub list: ListState,
+    pub sort: SortOrder,
+    pub hidden: bool,
+    pub clipboard: Option<(PathBuf, PasteMode)>,
+    pub message: Option<String>,
+    entries: Vec<Entry>,
+    /// Where holotapes are mounted, read again on each folder change.
+    mount_points: Vec<PathBuf>,
+    /// Selection in the listing while another page is open.
+    listing_selected: usize,
+}
+
+impl FileBrowser {
+    /// Opens at `path` if it is a folder, or at its folder with it selected;
+    /// the home folder when `None`.
+    pub fn new(path: Option<&Path>) -> Self {
+        let path = path.map_or_else(home_dir, Path::to_path_buf);
+        let (dir, select) = if path.is_dir() {
+            (path, None)
+        } else {
+            let dir = path.parent().map_or_else(home_dir, Path::to_path_buf);
+            (dir, Some(path))
+        };
+        let mut browser = Self {
+            page: BrowserPage::List,
+            start: dir.clone(),
+            dir: dir.clone(),
+            crumbs: Vec::new(),
+            title: String::new(),
+            rows: Vec::new(),
+            list: ListState::new(VISIBLE_ENTRIES),
+            sort: SortOrder::default(),
+            hidden: false,
+            clipboard: None,
+            message: None,
+            entries: Vec::new(),
+            mount_points: Vec::new(),
+            listing_selected: 0,
+        };
+        browser.enter(dir, select.as_deref());
+        browser
+    }
+
+    /// Lists `dir`, selecting `select` if it is there.
+    fn enter(&mut self, dir: PathBuf, select: Option<&Path>) {
+        self.mount_points = holotape::mount_points();
+        self.crumbs = crumbs(&dir, &self.mount_points);
+        self.dir = dir;
+        self.page = BrowserPage::List;
+        self.reload(select);
+    }
+
+    /// Rereads the folder, keeping the selection on `select` when given.
+    fn reload(&mut self, select: Option<&Path>) {
+        self.entries = match files::list_dir(&self.dir, self.sort, self.hidden) {
+            Ok(entries) => entries,
+            Err(e) => {
+                log::warn!("{:#}", e);
+                self.message = Some(format!("Cannot open {}", file_name(&self.dir)));
+                Vec::new()
+            }
+        };
+        if let Some(select) = select {
+            self.list.selected = self
+                .entries
+                .iter()
+                .position(|entry| entry.path == select)
+                .unwrap_or(0);
+        }
+        self.refresh();
+    }
+
+    fn up(&mut self) {
+        if let Some(parent) = self.dir.parent() {
+            let from = self.dir.clone();
+            self.enter(parent.to_path_buf(), Some(&from));
+        }
+    }
+
+    fn open_page(&mut self, page: BrowserPage) {
+        if self.page == BrowserPage::List {
+            self.listing_selected = self.list.selected;
+        }
+        self.page = page;
+        self.list.reset();
+        self.refresh();
+    }
+
+    fn close_page(&mut self) {
+        self.page = BrowserPage::List;
+        self.list.selected = self.listing_selected;
+        self.refresh();
+    }
+
+    /// Back closes a page, then climbs towards where the browser opened.
+    fn back(&mut self) -> Transition {
+        if self.page != BrowserPage::List {
+            self.close_page();
+        } else if self.dir == self.start || self.dir.parent().is_none() {
+            return Transition::Pop;
+        } else {
+            self.up();
+        }
+        Transition::Stay
+    }
+
+    fn selected_entry(&self) -> Option<&Entry> {
+        match self.page {
+            BrowserPage::List => self.entries.get(self.list.selected),
+            _ => None,
+        }
+    }
+
+    fn open(&mut self, path: PathBuf, ctx: &mut ScreenContext) -> Transition {
+        if path.is_dir() {
+            self.enter(path, None);
+            return Transition::Stay;
+        }
+        if self.page != BrowserPage::List {
+            self.close_page();
+        }
+        open_with::open(&path, ctx)
+    }
+
+    fn activate(&mut self, ctx: &mut ScreenContext) -> Transition {
+        let Some(row) = self.rows.get(self.list.selected) else {
+            return Transition::Stay;
+        };
+        match row.action.clone() {
+            RowAction::Open(path) => return self.open(path, ctx),
+            RowAction::Copy(path) => {
+                self.message = Some(format!(
+                    "Copied {}; paste it from the menu",
+                    file_name(&path)
+                ));
+                self.clipboard = Some((path, PasteMode::Copy));
+                self.close_page();
+            }
+            RowAction::Cut(path) => {
+                self.message = Some(format!("Cut {}; paste it from the menu", file_name(&path)));
+                self.clipboard = Some((path, PasteMode::Move));
+                self.close_page();
+            }
+            RowAction::Paste => {
+                if let Some((from, mode)) = self.clipboard.clone() {
+                    self.open_page(BrowserPage::Confirm(FileOp::Paste {
+                        from,
+                        into: self.dir.clone(),
+                        mode,
+                    }));
+                }
+            }
+            RowAction::Rename(path) => {
+                let stem = path
+                    .file_stem()
+                    .map_or_else(String::new, |s| s.to_string_lossy().into_owned());
+                let stem = NameEditor::file_name(stem);
+                let extension = path
+                    .extension()
+                    .map_or_else(String::new, |e| format!(".{}", e.to_string_lossy()));
+                self.open_page(BrowserPage::Rename {
+                    path,
+                    stem,
+                    extension,
+                });
+            }
+            RowAction::Delete(path) => self.open_page(BrowserPage::Confirm(FileOp::Delete(path))),
+            RowAction::Sort => {
+                self.sort = self.sort.next();
+                self.reload(None);
+            }
+            RowAction::Hidden => {
+                self.hidden = !self.hidden;
+                self.reload(None);
+            }
+            RowAction::SaveName => self.save_name(),
+            RowAction::Confirm => self.confirm(),
+            RowAction::Cancel => self.close_page(),
+        }
+        Transition::Stay
+    }
+
+    fn save_name(&mut self) {
+        let BrowserPage::Rename {
+            path,
+            stem,
+            extension,
+        } = &self.page
+        else {
+            return;
+        };
+        let name = format!("{}{}", stem.text.trim(), extension);
+        let dir = path.parent().unwrap_or(&self.dir);
+        if let Err(e) = files::validate_name(dir, &name) {
+            self.message = Some(capitalise(&e.to_string()));
+            return;
+        }
+        let op = FileOp::Rename {
+            path: path.clone(),
+            name,
+        };
+        self.open_page(BrowserPage::Confirm(op));
+    }
+
+    fn confirm(&mut self) {
+        let BrowserPage::Confirm(op) = self.page.clone() else {
+            return;
+        };
+        // Back on the listing where the confirmed entry was; a new path to
+        // select replaces this below
+        self.page = BrowserPage::List;
+        self.list.selected = self.listing_selected;
+        match op.run() {
+            Ok((select, message)) => {
+                log::info!("{}", message);
+                if let FileOp::Paste {
+                    mode: PasteMode::Move,
+                    ..
+                } = op
+                {
+                    self.clipboard = None;
+                }
+                self.message = Some(message);
+                self.reload(select.as_deref());
+            }
+            Err(e) => {
+                log::error!("{:#}", e);
+                self.message = Some(capitalise(&format!("{}", e)));
+                self.reload(None);
+            }
+        }
+    }
+
+    fn move_selection(&mut self, delta: i32) {
+        self.list.move_by(delta, self.rows.len());
+    }
+
+    fn handle_name(&mut self, event: &InputEvent) -> Transition {
+        let BrowserPage::Rename { stem, .. } = &mut self.page else {
+            return Transition::Stay;
+        };
+        if stem.handle(event) {
+            return Transition::Stay;
+        }
+        match *event {
+            InputEvent::Select | InputEvent::EncoderPressed(_) => self.save_name(),
+            InputEvent::Back => return self.back(),
+            _ => return Transition::Unhandled,
+        }
+        Transition::Stay
+    }
+
+    fn touch(&mut self, x: i32, y: i32, ctx: &mut ScreenContext) -> Transition {
+        if y < ROW_TOP - 10 {
+            return if x < BACK_WIDTH {
+                self.back()
+            } else if self.page == BrowserPage::List {
+                // The breadcrumbs: up a folder
+                self.up();
+                Transition::Stay
+            } else {
+                Transition::Stay
+            };
+        }
+        if !(ROW_LEFT..ROW_LEFT + ROW_WIDTH as i32).contains(&x) {
+            return Transition::Stay;
+        }
+        let Some(index) = self.list.row_at(y, ROW_TOP, ENTRY_HEIGHT, self.rows.len()) else {
+            return Transition::Stay;
+        };
+        // Listings select first so a stray tap opens nothing
+        if self.page == BrowserPage::List && index != self.list.selected {
+            self.list.selected = index;
+            return Transition::Stay;
+        }
+        self.list.selected = index;
+        self.activate(ctx)
+    }
+
+    fn actions(&mut self) {
+        let path = self.selected_entry().map(|entry| entry.path.clone());
+        self.open_page(BrowserPage::Actions(path));
+    }
+
+    /// Rebuilds the title and rows for the page and keeps the selection
+    /// visible.
+    fn refresh(&mut self) {
+        self.title = match &self.page {
+            BrowserPage::List => String::new(),
+            BrowserPage::Actions(Some(path)) => file_name(path),
+            BrowserPage::Actions(None) => file_name(&self.dir),
+            BrowserPage::Rename { .. } => "RENAME".to_string(),
+            BrowserPage::Confirm(op) => op.title().to_string(),
+        };
+        self.rows = match &self.page {
+            BrowserPage::List => self.entries.iter().map(entry_row).collect(),
+            BrowserPage::Actions(path) => self.action_rows(path.as_deref()),
+            BrowserPage::Rename {
+                stem, extension, ..
+            } => vec![
+                BrowserRow::new(RowAction::SaveName, format!("{}_", stem.text)).detail(extension),
+                BrowserRow::new(RowAction::SaveName, "Save"),
+                BrowserRow::new(RowAction::Cancel, "Cancel"),
+            ],
+            BrowserPage::Confirm(op) => {
+                let (label, detail) = op.describe(&self.mount_points);
+                vec![
+                    BrowserRow::new(RowAction::Cancel, "Cancel"),
+                    BrowserRow::new(RowAction::Confirm, label).detail(detail),
+                ]
+            }
+        };
+
+        self.list.clamp(self.rows.len());
+    }
+
+    fn action_rows(&self, path: Option<&Path>) -> Vec<BrowserRow> {
+        let mut rows = Vec::new();
+        if let Some(path) = path {
+            rows.push(BrowserRow::new(RowAction::Open(path.to_path_buf()), "Open"));
+            rows.push(BrowserRow::new(RowAction::Copy(path.to_path_buf()), "Copy"));
+            rows.push(BrowserRow::new(RowAction::Cut(path.to_path_buf()), "Cut"));
+            rows.push(BrowserRow::new(
+                RowAction::Rename(path.to_path_buf()),
+                "Rename",
+            ));
+            rows.push(BrowserRow::new(
+                RowAction::Delete(path.to_path_buf()),
+                "Delete",
+            ));
+        }
+        if let Some((from, _)) = &self.clipboard {
+            rows.push(
+                BrowserRow::new(RowAction::Paste, format!("Paste {}", file_name(from)))
+                    .detail("into this folder"),
+            );
+        }
+        rows.push(BrowserRow::new(RowAction::Sort, "Sort by").detail(self.sort.label()));
+        rows.push(
+            BrowserRow::new(RowAction::Hidden, "Hidden files").detail(if self.hidden {
+                "Shown"
+            } else {
+                "Hidden"
+            }),
+        );
+        rows
+    }
+}
+
+impl Screen for FileBrowser {
+    fn handle_input(&mut self, event: &InputEvent, ctx: &mut ScreenContext) -> Transition {
+        self.message = None;
+        if let BrowserPage::Rename { .. } = self.page {
+            let transition = match *event {
+                InputEvent::Touch(x, y) => self.touch(x, y, ctx),
+                _ => self.handle_name(event),
+            };
+            self.refresh();
+            return transition;
+        }
+
+        let transition = match *event {
+            InputEvent::NavigateUp => {
+                self.move_selection(-1);
+                Transition::Stay
+            }
+            InputEvent::NavigateDown => {
+                self.move_selection(1);
+                Transition::Stay
+            }
+            InputEvent::EncoderTurned { delta, .. } => {
+                self.move_selection(delta as i32);
+                Transition::Stay
+            }
+            InputEvent::Select | InputEvent::Activate | InputEvent::EncoderPressed(_) => {
+                self.activate(ctx)
+            }
+            InputEvent::NavigateLeft if self.page == BrowserPage::List => {
+                self.up();
+                Transition::Stay
+            }
+            InputEvent::NavigateRight => match self.selected_entry() {
+                Some(entry) if entry.is_dir() => {
+                    let path = entry.path.clone();
+                    self.enter(path, None);
+                    Transition::Stay
+                }
+                _ => Transition::Stay,
+            },
+            InputEvent::Menu if self.page == BrowserPage::List => {
+                self.actions();
+                Transition::Stay
+            }
+            InputEvent::TouchLongPress(_, y) if self.page == BrowserPage::List && y >= ROW_TOP => {
+                if let Some(index) = self.list.row_at(y, ROW_TOP, ENTRY_HEIGHT, self.rows.len()) {
+                    self.list.selected = index;
+                }
+                self.actions();
+                Transition::Stay
+            }
+            InputEvent::Back => self.back(),
+            InputEvent::Touch(x, y) => self.touch(x, y, ctx),
+            _ => Transition::Unhandled,
+        };
+        self.refresh();
+        transition
+    }
+
+    fn render(&self, renderer: &mut GlobalRenderer) {
+        renderer.render_files(self);
+    }
+
+    fn on_enter(&mut self, _ctx: &mut ScreenContext) {
+        // An app may have changed the folder while it was covered
+        if self.page == BrowserPage::List {
+            let select = self.entries.get(self.list.selected).map(|e| e.path.clone());
+            self.reload(select.as_deref());
+        }
+    }
+}
+
+fn entry_row(entry: &Entry) -> BrowserRow {
+    let detail = if entry.is_dir() {
+        String::new()
+    } else {
+        files::format_size(entry.size)
+    };
+    BrowserRow {
+        label: entry.name.clone(),
+        detail,
+        kind: Some(entry.kind),
+        action: RowAction::Open(entry.path.clone()),
+    }
+}
+
+fn home_dir() -> PathBuf {
+    std::env::var_os("HOME").map_or_else(|| PathBuf::from("/"), PathBuf::from)
+}
+
+/// HOLOTAPE for a removable drive, HOME for the home folder, otherwise the
+/// root, followed by each folder below it.
+fn crumbs(dir: &Path, mounts: &[PathBuf]) -> Vec<String> {
+    let home = home_dir();
+    let roots = mounts
+        .iter()
+        .map(|mount| (mount.clone(), "HOLOTAPE"))
+        .chain([(home, "HOME"), (PathBuf::from("/"), "/")]);
+    for (root, label) in roots {
+        if let Ok(rest) = dir.strip_prefix(&root) {
+            let mut crumbs = vec![label.to_string()];
+            crumbs.extend(
+                rest.components()
+                    .map(|c| c.as_os_str().to_string_lossy().into_owned()),
+            );
+            return crumbs;
+        }
+    }
+    vec![dir.display().to_string()]
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map_or_else(
+        || path.display().to_string(),
+        |n| n.to_string_lossy().into_owned(),
+    )
+}
+
+/// The folder `path` is in, as the user would read it.
+fn display_dir(path: &Path, mounts: &[PathBuf]) -> String {
+    path.parent().map_or_else(String::new, |dir| {
+        format!("from {}", crumbs(dir, mounts).join(" > "))
+    })
+}
+
+/// Errors read as sentences in the status line.
+fn capitalise(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn deleting_leaves_the_cursor_where_the_entry_was() {
+        let dir = std::env::temp_dir().join(format!("kips_ui-browser-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for name in ["a.txt", "b.txt", "c.txt", "d.txt"] {
+            fs::write(dir.join(name), name).unwrap();
+        }
+
+        let mut browser = FileBrowser::new(Some(&dir.join("c.txt")));
+        assert_eq!(browser.list.selected, 2);
+        browser.actions();
+        browser.open_page(BrowserPage::Confirm(FileOp::Delete(dir.join("c.txt"))));
+        browser.confirm();
+        assert_eq!(browser.page, BrowserPage::List);
+        assert_eq!(browser.rows[browser.list.selected].label, "d.txt");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -6,26 +6,80 @@ use sdl2::render::TextureCreator;
 use sdl2::video::WindowContext;
 use sdl2::ttf::Font;
 use sdl2::pixels::Color;
-use crate::ui::state::UIScreen;
+use crate::input::calibration::TARGETS;
+use crate::ui::main_menu::{
+    MainMenuScreen, ICON_SIZE, ITEM_HEIGHT, ITEM_LEFT, ITEM_TOP, RUNNING_LEFT, TITLE_Y,
+};
+use crate::files::FileKind;
+use crate::ui::files::{
+    BrowserPage, FileBrowser, ENTRY_HEIGHT, ICON_SIZE as FILE_ICON_SIZE, VISIBLE_ENTRIES,
+};
+use crate::ui::open_with::OpenWithScreen;
+use crate::ui::profiles::{ProfileMenu, ProfilePage};
+use crate::ui::running::{RunningAppsScreen, STDERR_SHOWN, STOP_LEFT, VISIBLE_APPS};
+use crate::ui::settings::{
+    RowKind, SettingsMenu, SettingsPage, BACK_WIDTH, ROW_HEIGHT, ROW_LEFT, ROW_TOP, ROW_WIDTH,
+    SLIDER_LEFT, SLIDER_WIDTH, VISIBLE_ROWS,
+};
+use crate::ui::screen::Screen;
+use crate::settings::Theme;
+use std::path::{Path, PathBuf};
+
+/// The shades screens are drawn in, all taken from the theme's colour.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Palette {
+    pub primary: Color,
+    /// Secondary text and outlines.
+    pub dim: Color,
+    /// Behind the selected row.
+    pub highlight: Color,
+    /// Empty slider tracks and switched-off toggles.
+    pub faint: Color,
+    pub background: Color,
+}
+
+impl Palette {
+    pub fn for_theme(theme: Theme) -> Self {
+        let primary = match theme {
+            Theme::Green => Color::RGB(0, 255, 0),
+            Theme::Amber => Color::RGB(255, 176, 0),
+            Theme::Blue => Color::RGB(46, 194, 255),
+            Theme::White => Color::RGB(255, 255, 255),
+        };
+        let shade = |level: u16| {
+            let scale = |channel: u8| (channel as u16 * level / 255) as u8;
+            Color::RGB(scale(primary.r), scale(primary.g), scale(primary.b))
+        };
+        Self {
+            primary,
+            dim: shade(150),
+            highlight: shade(100),
+            faint: shade(60),
+            background: shade(30),
+        }
+    }
+}
 
 pub struct GlobalRenderer<'a> {
     pub canvas: Canvas<Window>,
     pub texture_creator: &'a TextureCreator<WindowContext>,
     pub font: Font<'a, 'static>,
+    pub asset_dir: PathBuf,
+    /// Active profile's name, for the welcome greeting
+    pub user_name: String,
+    /// Follows `display.theme`.
+    pub palette: Palette,
     pub show_popup: bool,
     pub popup_selection: Option<String>,
 }
 
 impl<'a> GlobalRenderer<'a> {
-    pub fn render(&mut self, screen: &UIScreen) {
-        self.canvas.set_draw_color(Color::RGB(0, 30, 0));
+    pub fn render(&mut self, screen: &dyn Screen) {
+        self.canvas.set_draw_color(self.palette.background);
         self.canvas.clear();
         
         // Render the current screen
-        match screen {
-            UIScreen::Welcome => self.render_welcome(),
-            UIScreen::MainMenu(selected) => self.render_main_menu(*selected),
-        }
+        screen.render(self);
         
         // Render notification popup if active
         if self.show_popup {
@@ -35,6 +89,11 @@ impl<'a> GlobalRenderer<'a> {
         self.canvas.present();
     }
     
+    /// Puts the shell's window back over a launched app's.
+    pub fn raise(&mut self) {
+        self.canvas.window_mut().raise();
+    }
+
     pub fn render_notification(&mut self) {
         if let Some(message) = &self.popup_selection {
             let popup_x = 150;
@@ -83,9 +142,9 @@ impl<'a> GlobalRenderer<'a> {
                 let dismiss_x = 510;
                 let btn_y = 60;
                 
-                self.canvas.set_draw_color(Color::RGB(0, 100, 0));
+                self.canvas.set_draw_color(self.palette.highlight);
                 self.canvas.fill_rect(Rect::new(open_x, btn_y, btn_width as u32, btn_height as u32)).unwrap();
-                self.canvas.set_draw_color(Color::GREEN);
+                self.canvas.set_draw_color(self.palette.primary);
                 self.canvas.draw_rect(Rect::new(open_x, btn_y, btn_width as u32, btn_height as u32)).unwrap();
                 
                 // Dismiss button
@@ -114,8 +173,8 @@ impl<'a> GlobalRenderer<'a> {
         }
     }
 
-    fn render_welcome(&mut self) {
-        let texture = self.texture_creator.load_texture("assets/sit.png").unwrap();
+    pub fn render_welcome(&mut self) {
+        let texture = self.texture_creator.load_texture(self.asset_dir.join("sit.png")).unwrap();
         let query = texture.query();
         let (original_width, original_height) = (query.width, query.height);
         // Define the resizing percentage (e.g., 0.8 for 80%)
@@ -130,7 +189,8 @@ impl<'a> GlobalRenderer<'a> {
             height,
         );
         self.canvas.copy(&texture, None, Some(dst)).unwrap();
-        let surface = self.font.render("WELCOME, USER!").blended(Color::GREEN).unwrap();
+        let greeting = format!("WELCOME, {}!", self.user_name.to_uppercase());
+        let surface = self.font.render(&greeting).blended(self.palette.primary).unwrap();
         let texture = self.texture_creator.create_texture_from_surface(&surface).unwrap();
         let text_width = surface.width() as i32;
         let target = Rect::new((800 - text_width) / 2, 50, surface.width(), surface.height());
@@ -143,7 +203,7 @@ impl<'a> GlobalRenderer<'a> {
         let box_width = spacing;
         for (i, label) in labels.iter().enumerate() {
             let x = i as i32 * spacing;
-            let surface = self.font.render(label).blended(Color::GREEN).unwrap();
+            let surface = self.font.render(label).blended(self.palette.primary).unwrap();
             let texture = self.texture_creator.create_texture_from_surface(&surface).unwrap();
             let text_width = surface.width();
             let text_height = surface.height();
@@ -151,9 +211,9 @@ impl<'a> GlobalRenderer<'a> {
             let text_y = y + (box_height - text_height as i32) / 2;
             // Draw bounding box
             let rect = Rect::new(x, y, box_width as u32, box_height as u32);
-            self.canvas.set_draw_color(Color::RGB(0, 100, 0));
+            self.canvas.set_draw_color(self.palette.highlight);
             self.canvas.fill_rect(rect).unwrap();
-            self.canvas.set_draw_color(Color::GREEN);
+            self.canvas.set_draw_color(self.palette.primary);
             self.canvas.draw_rect(rect).unwrap();
             // Draw label centered in the box
             let target = Rect::new(text_x, text_y, surface.width(), surface.height());
@@ -161,16 +221,384 @@ impl<'a> GlobalRenderer<'a> {
         }
     }
     
-    fn render_main_menu(&mut self, selected: usize) {
-        let options = ["Calendar", "Media", "Gallery", "Terminal", "IDE"];
-        for (i, option) in options.iter().enumerate() {
-            let y = 100 + (i * 50) as i32;
-            let surface = self.font.render(option).blended(
-                if i == selected { Color::WHITE } else { Color::GREEN }
+    pub fn render_calibration(&mut self, step: usize) {
+        let message = format!("Tap the centre of the cross ({}/{})", step + 1, TARGETS.len());
+        let surface = self.font.render(&message).blended(self.palette.primary).unwrap();
+        let texture = self.texture_creator.create_texture_from_surface(&surface).unwrap();
+        let text_width = surface.width() as i32;
+        let target = Rect::new((800 - text_width) / 2, 200, surface.width(), surface.height());
+        self.canvas.copy(&texture, None, Some(target)).unwrap();
+
+        let surface = self.font.render("BACK to cancel").blended(self.palette.dim).unwrap();
+        let texture = self.texture_creator.create_texture_from_surface(&surface).unwrap();
+        let text_width = surface.width() as i32;
+        let target = Rect::new((800 - text_width) / 2, 250, surface.width(), surface.height());
+        self.canvas.copy(&texture, None, Some(target)).unwrap();
+
+        // Crosshair
+        if let Some(&(u, v)) = TARGETS.get(step) {
+            let x = (u * 800.0) as i32;
+            let y = (v * 480.0) as i32;
+            self.canvas.set_draw_color(Color::WHITE);
+            self.canvas.fill_rect(Rect::new(x - 20, y - 1, 41, 3)).unwrap();
+            self.canvas.fill_rect(Rect::new(x - 1, y - 20, 3, 41)).unwrap();
+            self.canvas.draw_rect(Rect::new(x - 8, y - 8, 17, 17)).unwrap();
+        }
+    }
+
+    pub fn render_settings(&mut self, menu: &SettingsMenu) {
+        let dim = self.palette.dim;
+
+        // Title bar
+        if menu.page == SettingsPage::Root {
+            self.draw_text(menu.page.title(), ROW_LEFT, 25, self.palette.primary);
+        } else {
+            self.draw_text("< BACK", ROW_LEFT, 25, dim);
+            self.draw_text(menu.page.title(), BACK_WIDTH + 20, 25, self.palette.primary);
+        }
+        self.canvas.set_draw_color(self.palette.primary);
+        self.canvas.fill_rect(Rect::new(ROW_LEFT, ROW_TOP - 10, ROW_WIDTH, 2)).unwrap();
+
+        let visible = menu.list.shown(&menu.rows);
+        for (slot, (index, row)) in visible.enumerate() {
+            let y = ROW_TOP + slot as i32 * ROW_HEIGHT;
+            let selected = index == menu.list.selected;
+            let rect = Rect::new(ROW_LEFT, y, ROW_WIDTH, (ROW_HEIGHT - 8) as u32);
+            if selected {
+                self.canvas.set_draw_color(self.palette.highlight);
+                self.canvas.fill_rect(rect).unwrap();
+                self.canvas.set_draw_color(if menu.editing { Color::WHITE } else { self.palette.primary });
+                self.canvas.draw_rect(rect).unwrap();
+            }
+
+            let text_y = y + 12;
+            self.draw_text(&row.label, ROW_LEFT + 20, text_y, if selected { Color::WHITE } else { self.palette.primary });
+
+            match &row.kind {
+                RowKind::Page => {
+                    self.draw_text(">", ROW_LEFT + ROW_WIDTH as i32 - 40, text_y, self.palette.primary);
+                }
+                RowKind::Slider { value, max, .. } => {
+                    let track_y = y + (ROW_HEIGHT - 8) / 2 - 4;
+                    let filled = SLIDER_WIDTH * *value as u32 / (*max).max(1) as u32;
+                    self.canvas.set_draw_color(self.palette.faint);
+                    self.canvas.fill_rect(Rect::new(SLIDER_LEFT, track_y, SLIDER_WIDTH, 8)).unwrap();
+                    if filled > 0 {
+                        self.canvas.set_draw_color(self.palette.primary);
+                        self.canvas.fill_rect(Rect::new(SLIDER_LEFT, track_y, filled, 8)).unwrap();
+                    }
+                    // Knob
+                    self.canvas.set_draw_color(Color::WHITE);
+                    self.canvas.fill_rect(Rect::new(SLIDER_LEFT + filled as i32 - 4, track_y - 6, 8, 20)).unwrap();
+                    self.draw_text(&value.to_string(), SLIDER_LEFT + SLIDER_WIDTH as i32 + 12, text_y, self.palette.primary);
+                }
+                RowKind::Toggle(on) => {
+                    let pill = Rect::new(ROW_LEFT + ROW_WIDTH as i32 - 110, y + 10, 80, 32);
+                    self.canvas.set_draw_color(if *on { self.palette.primary } else { self.palette.faint });
+                    self.canvas.fill_rect(pill).unwrap();
+                    self.canvas.set_draw_color(self.palette.primary);
+                    self.canvas.draw_rect(pill).unwrap();
+                    let label = if *on { "ON" } else { "OFF" };
+                    let color = if *on { self.palette.background } else { self.palette.primary };
+                    self.draw_text(label, pill.x() + 18, text_y, color);
+                }
+                RowKind::Picker { options, selected: option } => {
+                    let value = options.get(*option).map_or("", String::as_str);
+                    let text = if selected && menu.editing {
+                        format!("< {} >", value)
+                    } else {
+                        value.to_string()
+                    };
+                    self.draw_text_right(&text, ROW_LEFT + ROW_WIDTH as i32 - 20, text_y, self.palette.primary);
+                }
+                RowKind::Button => {}
+                RowKind::Info(text) => {
+                    self.draw_text_right(text, ROW_LEFT + ROW_WIDTH as i32 - 20, text_y, dim);
+                }
+            }
+        }
+
+        // Scroll hint
+        if menu.list.more_below(menu.rows.len()) {
+            self.draw_text("...", ROW_LEFT + ROW_WIDTH as i32 / 2, ROW_TOP + VISIBLE_ROWS as i32 * ROW_HEIGHT - 10, dim);
+        }
+    }
+
+    pub fn render_profiles(&mut self, menu: &ProfileMenu) {
+        let dim = self.palette.dim;
+
+        // Title bar
+        if menu.page == ProfilePage::List {
+            self.draw_text(&menu.title, ROW_LEFT, 25, self.palette.primary);
+        } else {
+            self.draw_text("< BACK", ROW_LEFT, 25, dim);
+            self.draw_text(&menu.title, BACK_WIDTH + 20, 25, self.palette.primary);
+        }
+        self.canvas.set_draw_color(self.palette.primary);
+        self.canvas.fill_rect(Rect::new(ROW_LEFT, ROW_TOP - 10, ROW_WIDTH, 2)).unwrap();
+
+        let visible = menu.list.shown(&menu.rows);
+        for (slot, (index, row)) in visible.enumerate() {
+            let y = ROW_TOP + slot as i32 * ROW_HEIGHT;
+            let selected = index == menu.list.selected;
+            let rect = Rect::new(ROW_LEFT, y, ROW_WIDTH, (ROW_HEIGHT - 8) as u32);
+            if selected {
+                self.canvas.set_draw_color(self.palette.highlight);
+                self.canvas.fill_rect(rect).unwrap();
+                self.canvas.set_draw_color(self.palette.primary);
+                self.canvas.draw_rect(rect).unwrap();
+            }
+
+            let mut text_x = ROW_LEFT + 20;
+            if let Some(avatar) = &row.avatar {
+                self.draw_image(avatar, Rect::new(ROW_LEFT + 8, y + 4, 44, 44));
+                text_x = ROW_LEFT + 64;
+            }
+            let text_y = y + 12;
+            self.draw_text(&row.label, text_x, text_y, if selected { Color::WHITE } else { self.palette.primary });
+            self.draw_text_right(&row.detail, ROW_LEFT + ROW_WIDTH as i32 - 20, text_y, dim);
+        }
+
+        if let Some(message) = &menu.message {
+            self.draw_text(message, ROW_LEFT, 440, Color::RGB(255, 80, 80));
+        } else if let ProfilePage::Name { .. } = menu.page {
+            self.draw_text("UP/DOWN change letter, RIGHT add, LEFT remove", ROW_LEFT, 440, dim);
+        }
+    }
+
+    pub fn render_running_apps(&mut self, screen: &RunningAppsScreen) {
+        let dim = self.palette.dim;
+
+        // Title bar
+        self.draw_text("< BACK", ROW_LEFT, 25, dim);
+        self.draw_text("RUNNING APPS", BACK_WIDTH + 20, 25, self.palette.primary);
+        self.canvas.set_draw_color(self.palette.primary);
+        self.canvas.fill_rect(Rect::new(ROW_LEFT, ROW_TOP - 10, ROW_WIDTH, 2)).unwrap();
+        if screen.rows.is_empty() {
+            self.draw_text("No apps running", ROW_LEFT + 20, ROW_TOP + 12, dim);
+        }
+
+        let visible = screen.list.shown(&screen.rows);
+        for (slot, (index, row)) in visible.enumerate() {
+            let y = ROW_TOP + slot as i32 * ROW_HEIGHT;
+            let selected = index == screen.list.selected;
+            let rect = Rect::new(ROW_LEFT, y, ROW_WIDTH, (ROW_HEIGHT - 8) as u32);
+            if selected {
+                self.canvas.set_draw_color(self.palette.highlight);
+                self.canvas.fill_rect(rect).unwrap();
+                self.canvas.set_draw_color(self.palette.primary);
+                self.canvas.draw_rect(rect).unwrap();
+            }
+
+            let text_y = y + 12;
+            let label_color = match (selected, row.pid) {
+                (true, _) => Color::WHITE,
+                (false, Some(_)) => self.palette.primary,
+                (false, None) => dim,
+            };
+            self.draw_text(&row.label, ROW_LEFT + 20, text_y, label_color);
+            if row.pid.is_some() {
+                self.draw_text_right(&row.detail, STOP_LEFT - 20, text_y, dim);
+                self.draw_text("STOP", STOP_LEFT + 10, text_y, Color::RGB(255, 80, 80));
+            } else {
+                self.draw_text_right(&row.detail, ROW_LEFT + ROW_WIDTH as i32 - 20, text_y, dim);
+            }
+        }
+
+        // The end of the selected app's stderr, which usually says why it died
+        let stderr_top = ROW_TOP + VISIBLE_APPS as i32 * ROW_HEIGHT + 10;
+        if let Some(row) = screen.rows.get(screen.list.selected) {
+            let lines = row.stderr.iter().skip(row.stderr.len().saturating_sub(STDERR_SHOWN));
+            for (i, line) in lines.enumerate() {
+                self.draw_text(line, ROW_LEFT, stderr_top + i as i32 * 28, dim);
+            }
+        }
+
+        if let Some(message) = &screen.message {
+            self.draw_text(message, ROW_LEFT, 440, Color::RGB(255, 80, 80));
+        }
+    }
+
+    pub fn render_open_with(&mut self, screen: &OpenWithScreen) {
+        let dim = self.palette.dim;
+
+        // Title bar
+        self.draw_text("< BACK", ROW_LEFT, 25, dim);
+        self.draw_text("OPEN WITH", BACK_WIDTH + 20, 25, self.palette.primary);
+        self.draw_text_right(&screen.file_name, ROW_LEFT + ROW_WIDTH as i32 - 20, 25, dim);
+        self.canvas.set_draw_color(self.palette.primary);
+        self.canvas.fill_rect(Rect::new(ROW_LEFT, ROW_TOP - 10, ROW_WIDTH, 2)).unwrap();
+
+        let visible = screen.list.shown(&screen.choices);
+        for (slot, (index, choice)) in visible.enumerate() {
+            let y = ROW_TOP + slot as i32 * ROW_HEIGHT;
+            let selected = index == screen.list.selected;
+            if selected {
+                let rect = Rect::new(ROW_LEFT, y, ROW_WIDTH, (ROW_HEIGHT - 8) as u32);
+                self.canvas.set_draw_color(self.palette.highlight);
+                self.canvas.fill_rect(rect).unwrap();
+                self.canvas.set_draw_color(self.palette.primary);
+                self.canvas.draw_rect(rect).unwrap();
+            }
+            self.draw_text(&choice.name, ROW_LEFT + 20, y + 12, if selected { Color::WHITE } else { self.palette.primary });
+            if index == 0 {
+                self.draw_text_right("best match", ROW_LEFT + ROW_WIDTH as i32 - 20, y + 12, dim);
+            }
+        }
+    }
+
+    pub fn render_files(&mut self, browser: &FileBrowser) {
+        let dim = self.palette.dim;
+        let right = ROW_LEFT + ROW_WIDTH as i32 - 20;
+
+        // Title bar: the breadcrumbs, losing their start when too long
+        self.draw_text("< BACK", ROW_LEFT, 25, dim);
+        let title = if browser.page == BrowserPage::List {
+            let mut crumbs = browser.crumbs.join(" > ");
+            let room = (right - BACK_WIDTH - 20) as u32;
+            while self.font.size_of(&crumbs).map_or(0, |(w, _)| w) > room && crumbs.chars().count() > 1 {
+                crumbs = format!("...{}", crumbs.chars().skip(4).collect::<String>());
+            }
+            crumbs
+        } else {
+            browser.title.clone()
+        };
+        self.draw_text(&title, BACK_WIDTH + 20, 25, self.palette.primary);
+        self.canvas.set_draw_color(self.palette.primary);
+        self.canvas.fill_rect(Rect::new(ROW_LEFT, ROW_TOP - 10, ROW_WIDTH, 2)).unwrap();
+        if browser.page == BrowserPage::List && browser.rows.is_empty() {
+            self.draw_text("Empty folder", ROW_LEFT + 20, ROW_TOP + 12, dim);
+        }
+
+        let visible = browser.list.shown(&browser.rows);
+        for (slot, (index, row)) in visible.enumerate() {
+            let y = ROW_TOP + slot as i32 * ENTRY_HEIGHT;
+            let selected = index == browser.list.selected;
+            if selected {
+                let rect = Rect::new(ROW_LEFT, y, ROW_WIDTH, (ENTRY_HEIGHT - 6) as u32);
+                self.canvas.set_draw_color(self.palette.highlight);
+                self.canvas.fill_rect(rect).unwrap();
+                self.canvas.set_draw_color(self.palette.primary);
+                self.canvas.draw_rect(rect).unwrap();
+            }
+
+            let mut text_x = ROW_LEFT + 20;
+            if let Some(kind) = row.kind {
+                self.draw_file_icon(kind, Rect::new(ROW_LEFT + 8, y + 4, FILE_ICON_SIZE, FILE_ICON_SIZE));
+                text_x = ROW_LEFT + 56;
+            }
+            let text_y = y + 10;
+            self.draw_text(&row.label, text_x, text_y, if selected { Color::WHITE } else { self.palette.primary });
+            self.draw_text_right(&row.detail, right, text_y, dim);
+        }
+
+        // Status line
+        let status_y = ROW_TOP + VISIBLE_ENTRIES as i32 * ENTRY_HEIGHT + 10;
+        if let Some(message) = &browser.message {
+            self.draw_text(message, ROW_LEFT, status_y, Color::RGB(255, 80, 80));
+        } else if let BrowserPage::Rename { .. } = browser.page {
+            self.draw_text("UP/DOWN change letter, RIGHT add, LEFT remove", ROW_LEFT, status_y, dim);
+        } else if browser.page == BrowserPage::List {
+            let status = format!("{} items, by {}  MENU for actions", browser.rows.len(), browser.sort.label().to_lowercase());
+            self.draw_text(&status, ROW_LEFT, status_y, dim);
+        }
+    }
+
+    /// `assets/icons/<kind>.png`, or a text tag when there is no such image.
+    fn draw_file_icon(&mut self, kind: FileKind, target: Rect) {
+        let path = self.asset_dir.join("icons").join(format!("{}.png", kind.name()));
+        if path.is_file() {
+            self.draw_image(&path, target);
+            return;
+        }
+        let tag = match kind {
+            FileKind::Folder => "DIR",
+            FileKind::Audio => "AUD",
+            FileKind::Video => "VID",
+            FileKind::Image => "IMG",
+            FileKind::Document => "DOC",
+            FileKind::Text => "TXT",
+            FileKind::Other => "---",
+        };
+        self.canvas.set_draw_color(self.palette.dim);
+        self.canvas.draw_rect(target).unwrap();
+        let (width, height) = self.font.size_of(tag).unwrap_or((0, 0));
+        let x = target.x() + (target.width() as i32 - width as i32) / 2;
+        let y = target.y() + (target.height() as i32 - height as i32) / 2;
+        self.draw_text(tag, x, y, self.palette.dim);
+    }
+
+    /// Draws the image at `path` scaled into `target`; an unreadable file
+    /// (say, from a drive that was just unplugged) draws nothing.
+    fn draw_image(&mut self, path: &Path, target: Rect) {
+        match self.texture_creator.load_texture(path) {
+            Ok(texture) => self.canvas.copy(&texture, None, Some(target)).unwrap(),
+            Err(e) => log::debug!("Cannot load {}: {}", path.display(), e),
+        }
+    }
+
+    /// Draws `text` with its top-left at (`x`, `y`) and returns its width.
+    fn draw_text(&mut self, text: &str, x: i32, y: i32, color: Color) -> u32 {
+        if text.is_empty() {
+            return 0;
+        }
+        let surface = self.font.render(text).blended(color).unwrap();
+        let texture = self.texture_creator.create_texture_from_surface(&surface).unwrap();
+        let target = Rect::new(x, y, surface.width(), surface.height());
+        self.canvas.copy(&texture, None, Some(target)).unwrap();
+        surface.width()
+    }
+
+    /// Like `draw_text`, but `right` is where the text ends.
+    fn draw_text_right(&mut self, text: &str, right: i32, y: i32, color: Color) {
+        let (width, _) = self.font.size_of(text).unwrap_or((0, 0));
+        self.draw_text(text, right - width as i32, y, color);
+    }
+
+    pub fn render_main_menu(&mut self, menu: &MainMenuScreen) {
+        let title = menu.categories.get(menu.category).map_or("APPS".to_string(), |c| c.to_uppercase());
+        self.draw_text(&format!("< {} >", title), ITEM_LEFT, TITLE_Y, self.palette.primary);
+        if menu.running > 0 {
+            self.draw_text(&format!("RUNNING {}", menu.running), RUNNING_LEFT, TITLE_Y, self.palette.dim);
+        }
+        if menu.items.is_empty() {
+            self.draw_text("No apps installed", ITEM_LEFT, ITEM_TOP, self.palette.dim);
+        }
+
+        let visible = menu.list.shown(&menu.items);
+        for (slot, (i, item)) in visible.enumerate() {
+            let y = ITEM_TOP + slot as i32 * ITEM_HEIGHT;
+            let mut x = ITEM_LEFT;
+            if let Some(icon) = &item.icon {
+                self.draw_image(icon, Rect::new(x, y, ICON_SIZE, ICON_SIZE));
+                x += ICON_SIZE as i32 + 12;
+            }
+            let label = if item.favourite { format!("{} *", item.label) } else { item.label.clone() };
+            let surface = self.font.render(&label).blended(
+                if i == menu.list.selected { Color::WHITE } else { self.palette.primary }
             ).unwrap();
             let texture = self.texture_creator.create_texture_from_surface(&surface).unwrap();
-            let target = Rect::new(100, y, surface.width(), surface.height());
+            let target = Rect::new(x, y, surface.width(), surface.height());
             self.canvas.copy(&texture, None, Some(target)).unwrap();
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_theme_shades_its_own_colour() {
+        let green = Palette::for_theme(Theme::Green);
+        assert_eq!(green.primary, Color::GREEN);
+        assert_eq!(green.dim, Color::RGB(0, 150, 0));
+        assert_eq!(green.highlight, Color::RGB(0, 100, 0));
+        assert_eq!(green.faint, Color::RGB(0, 60, 0));
+        assert_eq!(green.background, Color::RGB(0, 30, 0));
+
+        let amber = Palette::for_theme(Theme::Amber);
+        assert_eq!(amber.background, Color::RGB(30, 20, 0));
+        assert_ne!(amber, Palette::for_theme(Theme::Blue));
+    }
+}
@@ -0,0 +1,220 @@
+// src/ui/list.rs
+use crate::input::InputEvent;
+use evdev::Key;
+
+/// Up/down cycles the last letter of a name through these, for entering a
+/// name without a keyboard.
+const NAME_CHARS: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789 -";
+
+/// Which row of a scrolling list is selected and which is shown first.
+/// Every list screen keeps one, and the renderer draws from it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ListState {
+    pub selected: usize,
+    /// First row shown.
+    pub scroll: usize,
+    /// Rows shown at once.
+    pub visible: usize,
+}
+
+impl ListState {
+    pub fn new(visible: usize) -> Self {
+        Self {
+            selected: 0,
+            scroll: 0,
+            visible,
+        }
+    }
+
+    /// Moves the selection `delta` rows, stopping at either end of `len`.
+    pub fn move_by(&mut self, delta: i32, len: usize) {
+        let last = len.saturating_sub(1) as i32;
+        self.select((self.selected as i32 + delta).clamp(0, last) as usize, len);
+    }
+
+    pub fn select(&mut self, index: usize, len: usize) {
+        self.selected = index;
+        self.clamp(len);
+    }
+
+    /// Back to the first row, as when a page opens.
+    pub fn reset(&mut self) {
+        self.selected = 0;
+        self.scroll = 0;
+    }
+
+    /// Keeps the selection on one of `len` rows and scrolled into view.
+    pub fn clamp(&mut self, len: usize) {
+        self.selected = self.selected.min(len.saturating_sub(1));
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.selected >= self.scroll + self.visible {
+            self.scroll = self.selected + 1 - self.visible;
+        }
+    }
+
+    /// The row drawn at screen `y` when rows of `row_height` start at `top`.
+    pub fn row_at(&self, y: i32, top: i32, row_height: i32, len: usize) -> Option<usize> {
+        if y < top {
+            return None;
+        }
+        let slot = ((y - top) / row_height) as usize;
+        let index = self.scroll + slot;
+        (slot < self.visible && index < len).then_some(index)
+    }
+
+    /// The rows on screen, with their indices.
+    pub fn shown<'a, T>(&self, rows: &'a [T]) -> impl Iterator<Item = (usize, &'a T)> {
+        rows.iter().enumerate().skip(self.scroll).take(self.visible)
+    }
+
+    /// Whether rows are hidden below the last one shown.
+    pub fn more_below(&self, len: usize) -> bool {
+        self.scroll + self.visible < len
+    }
+}
+
+/// A name being typed on a keyboard or, without one, built a letter at a
+/// time: up/down (or the encoder) changes the last letter, right adds one
+/// and left removes one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NameEditor {
+    pub text: String,
+    /// In characters.
+    max_len: usize,
+    /// File names cannot contain `/`.
+    file_name: bool,
+}
+
+impl NameEditor {
+    pub fn new(text: impl Into<String>, max_len: usize) -> Self {
+        Self {
+            text: text.into(),
+            max_len,
+            file_name: false,
+        }
+    }
+
+    /// A file name, as long as the file system allows.
+    pub fn file_name(text: impl Into<String>) -> Self {
+        Self {
+            file_name: true,
+            ..Self::new(text, 255)
+        }
+    }
+
+    /// Applies a typing or letter-stepping event. Returns `false` for
+    /// anything else, such as Select or Back, which the screen handles.
+    pub fn handle(&mut self, event: &InputEvent) -> bool {
+        let room = self.text.chars().count() < self.max_len;
+        match *event {
+            InputEvent::KeyPress(c) if self.accepts(c) => {
+                if room {
+                    self.text.push(c);
+                }
+            }
+            InputEvent::KeyDown { key, .. } if key == Key::KEY_BACKSPACE.code() => {
+                self.text.pop();
+            }
+            InputEvent::NavigateLeft => {
+                self.text.pop();
+            }
+            InputEvent::NavigateRight => {
+                if room {
+                    self.text.push('A');
+                }
+            }
+            InputEvent::NavigateUp => self.cycle_last(1),
+            InputEvent::NavigateDown => self.cycle_last(-1),
+            InputEvent::EncoderTurned { delta, .. } => self.cycle_last(delta as i32),
+            _ => return false,
+        }
+        true
+    }
+
+    fn accepts(&self, c: char) -> bool {
+        match c {
+            '/' => !self.file_name,
+            c => !c.is_control(),
+        }
+    }
+
+    /// Steps the last character through `NAME_CHARS`, starting a new one
+    /// when the name is empty.
+    fn cycle_last(&mut self, delta: i32) {
+        let chars: Vec<char> = NAME_CHARS.chars().collect();
+        let index = match self.text.pop() {
+            Some(last) => match chars.iter().position(|&c| c == last) {
+                Some(i) => (i as i32 + delta).rem_euclid(chars.len() as i32) as usize,
+                None => 0,
+            },
+            None => 0,
+        };
+        self.text.push(chars[index]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selection_stays_in_view() {
+        let mut list = ListState::new(3);
+        list.move_by(4, 10);
+        assert_eq!((list.selected, list.scroll), (4, 2));
+        list.move_by(-3, 10);
+        assert_eq!((list.selected, list.scroll), (1, 1));
+        list.move_by(100, 10);
+        assert_eq!((list.selected, list.scroll), (9, 7));
+        assert!(!list.more_below(10));
+
+        // The list shrinking pulls the selection back onto it
+        list.clamp(5);
+        assert_eq!((list.selected, list.scroll), (4, 4));
+        list.clamp(0);
+        assert_eq!(list.selected, 0);
+
+        let rows = ["a", "b", "c", "d", "e"];
+        list.reset();
+        let shown: Vec<usize> = list.shown(&rows).map(|(index, _)| index).collect();
+        assert_eq!(shown, [0, 1, 2]);
+        assert!(list.more_below(rows.len()));
+    }
+
+    #[test]
+    fn rows_are_hit_by_position() {
+        let mut list = ListState::new(3);
+        list.select(3, 5);
+        assert_eq!(list.scroll, 1);
+        assert_eq!(list.row_at(79, 80, 60, 5), None);
+        assert_eq!(list.row_at(80, 80, 60, 5), Some(1));
+        assert_eq!(list.row_at(259, 80, 60, 5), Some(3));
+        // Below the last row shown, and past the end of the list
+        assert_eq!(list.row_at(260, 80, 60, 5), None);
+        assert_eq!(list.row_at(200, 80, 60, 3), None);
+    }
+
+    #[test]
+    fn names_are_built_a_letter_at_a_time() {
+        let mut name = NameEditor::new("", 3);
+        assert!(name.handle(&InputEvent::NavigateUp));
+        assert_eq!(name.text, "A");
+        name.handle(&InputEvent::NavigateDown);
+        assert_eq!(name.text, "-");
+        name.handle(&InputEvent::NavigateRight);
+        name.handle(&InputEvent::EncoderTurned { id: 0, delta: 2 });
+        assert_eq!(name.text, "-C");
+        name.handle(&InputEvent::KeyPress('x'));
+        name.handle(&InputEvent::KeyPress('y'));
+        assert_eq!(name.text, "-Cx");
+        name.handle(&InputEvent::NavigateLeft);
+        assert_eq!(name.text, "-C");
+        assert!(!name.handle(&InputEvent::Select));
+
+        let mut file = NameEditor::file_name("notes");
+        file.handle(&InputEvent::KeyPress('/'));
+        file.handle(&InputEvent::KeyPress('2'));
+        assert_eq!(file.text, "notes2");
+    }
+}
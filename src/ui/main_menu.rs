@@ -1,51 +1,167 @@
 // src/ui/main_menu.rs
-use crate::ui::state::UIScreen;
-
-pub fn handle_main_menu_input(selected: usize, input: InputEvent) -> UIScreen {
-    match input {
-        InputEvent::Up => UIScreen::MainMenu(selected.saturating_sub(1)),
-        InputEvent::Down => UIScreen::MainMenu((selected + 1) % 5),
-        InputEvent::Click(x, y) => {
-            // Calculate which menu item was clicked based on coordinates
-            // This is a simplified example - you'll need to adjust based on your UI layout
-            let item_height = 50; // Approximate height of each menu item
-            let start_y = 100;    // Approximate Y position where menu starts
-            
-            if x >= 100 && x <= 700 { // Check if click is within menu width
-                let index = (y - start_y) / item_height;
-                if index < 5 { // Number of menu items
-                    // If valid menu item was clicked, select it
-                    if index as usize == selected {
-                        // If clicking on already selected item, treat as Select
-                        return handle_main_menu_input(selected, InputEvent::Select);
-                    } else {
-                        // Otherwise just select the item
-                        return UIScreen::MainMenu(index as usize);
-                    }
+use crate::input::touchscreen::SwipeDirection;
+use crate::input::InputEvent;
+use crate::ui::global_renderer::GlobalRenderer;
+use crate::ui::list::ListState;
+use crate::ui::open_with;
+use crate::ui::running::RunningAppsScreen;
+use crate::ui::screen::{Screen, ScreenContext, Transition};
+use std::path::PathBuf;
+use std::time::Duration;
+
+// Layout shared with `GlobalRenderer::render_main_menu` for touch hit-testing
+pub const ITEM_TOP: i32 = 100;
+pub const ITEM_HEIGHT: i32 = 50;
+pub const ITEM_LEFT: i32 = 100;
+pub const ITEM_RIGHT: i32 = 700;
+pub const ICON_SIZE: u32 = 40;
+pub const VISIBLE_ITEMS: usize = 7;
+/// The RUNNING label right of the title, which opens the running apps.
+pub const RUNNING_LEFT: i32 = 520;
+pub const TITLE_Y: i32 = 40;
+
+/// One app as the menu shows it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MenuItem {
+    pub id: String,
+    pub label: String,
+    pub icon: Option<PathBuf>,
+    pub favourite: bool,
+}
+
+/// The apps in the registry, one category at a time. Up/down (or an
+/// encoder) picks an app, left/right changes category, Select launches and
+/// Menu toggles the app in the profile's favourites. Tapping an app selects
+/// it; tapping it again launches it. Swiping up, or tapping RUNNING, lists
+/// the apps that are running.
+#[derive(Debug, Clone)]
+pub struct MainMenuScreen {
+    pub category: usize,
+    pub categories: Vec<String>,
+    pub items: Vec<MenuItem>,
+    pub list: ListState,
+    /// How many launched apps are still running.
+    pub running: usize,
+}
+
+impl MainMenuScreen {
+    pub fn new(category: usize) -> Self {
+        Self {
+            category,
+            categories: Vec::new(),
+            items: Vec::new(),
+            list: ListState::new(VISIBLE_ITEMS),
+            running: 0,
+        }
+    }
+
+    /// Rebuilds the items from the registry and the active profile.
+    fn refresh(&mut self, ctx: &ScreenContext) {
+        self.categories = ctx
+            .apps
+            .categories()
+            .into_iter()
+            .map(String::from)
+            .collect();
+        self.category = self.category.min(self.categories.len().saturating_sub(1));
+        let profile = ctx.profiles.active();
+        self.items = match self.categories.get(self.category) {
+            Some(category) => ctx
+                .apps
+                .in_category(category)
+                .map(|app| MenuItem {
+                    id: app.id.clone(),
+                    label: app.name.clone(),
+                    icon: app.icon_path(&ctx.cli.assets),
+                    favourite: profile.is_favourite(&app.id),
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+
+        self.list.clamp(self.items.len());
+    }
+
+    fn move_selection(&mut self, delta: i32) {
+        self.list.move_by(delta, self.items.len());
+    }
+
+    fn change_category(&mut self, delta: i32) {
+        let count = self.categories.len().max(1) as i32;
+        self.category = (self.category as i32 + delta).rem_euclid(count) as usize;
+        self.list.reset();
+    }
+
+    fn launch(&self, ctx: &mut ScreenContext) -> Transition {
+        match self.items.get(self.list.selected) {
+            Some(item) => open_with::launch(&item.id, None, ctx),
+            None => Transition::Stay,
+        }
+    }
+}
+
+impl Screen for MainMenuScreen {
+    fn handle_input(&mut self, event: &InputEvent, ctx: &mut ScreenContext) -> Transition {
+        match *event {
+            InputEvent::NavigateUp => self.move_selection(-1),
+            InputEvent::NavigateDown => self.move_selection(1),
+            InputEvent::EncoderTurned { delta, .. } => self.move_selection(delta as i32),
+            InputEvent::NavigateLeft => self.change_category(-1),
+            InputEvent::NavigateRight => self.change_category(1),
+            InputEvent::Select | InputEvent::Activate | InputEvent::EncoderPressed(_) => {
+                let transition = self.launch(ctx);
+                self.refresh(ctx);
+                return transition;
+            }
+            InputEvent::Menu => {
+                if let Some(item) = self.items.get(self.list.selected) {
+                    let added = ctx.profiles.toggle_favourite(&item.id);
+                    log::info!(
+                        "{} {} favourites",
+                        item.id,
+                        if added { "added to" } else { "removed from" }
+                    );
                 }
             }
-            
-            UIScreen::MainMenu(selected) // Default: no change
-        },
-        InputEvent::Select => {
-            match selected {
-                0 => UIScreen::Welcome, // Calendar placeholder
-                1 => UIScreen::Welcome, // Media placeholder
-                2 => UIScreen::Welcome, // Gallery placeholder
-                3 => UIScreen::Welcome, // Terminal placeholder
-                4 => UIScreen::Welcome, // IDE placeholder
-                _ => UIScreen::MainMenu(0),
+            InputEvent::Swipe(SwipeDirection::Up) => {
+                return Transition::Push(Box::new(RunningAppsScreen::default()));
+            }
+            InputEvent::Touch(x, y) if y < ITEM_TOP && x >= RUNNING_LEFT => {
+                return Transition::Push(Box::new(RunningAppsScreen::default()));
+            }
+            InputEvent::Touch(x, y) => {
+                if !(ITEM_LEFT..=ITEM_RIGHT).contains(&x) {
+                    return Transition::Stay;
+                }
+                let Some(index) = self.list.row_at(y, ITEM_TOP, ITEM_HEIGHT, self.items.len())
+                else {
+                    return Transition::Stay;
+                };
+                if index == self.list.selected {
+                    let transition = self.launch(ctx);
+                    self.refresh(ctx);
+                    return transition;
+                } else {
+                    self.list.selected = index;
+                }
             }
-        },
-        _ => UIScreen::MainMenu(selected),
+            // The hardware buttons lead back to the welcome screen
+            InputEvent::ButtonPressed(_) => return Transition::Home,
+            _ => return Transition::Unhandled,
+        }
+        self.refresh(ctx);
+        Transition::Stay
+    }
+
+    fn update(&mut self, ctx: &mut ScreenContext, _elapsed: Duration) {
+        self.running = ctx.supervisor.running().len();
     }
-}
 
-#[derive(Debug, Clone, Copy)]
-pub enum InputEvent {
-    Up,
-    Down,
-    Select,
-    Click(i32, i32), // Added Click variant with x, y coordinates
-    None,
-}
\ No newline at end of file
+    fn render(&self, renderer: &mut GlobalRenderer) {
+        renderer.render_main_menu(self);
+    }
+
+    fn on_enter(&mut self, ctx: &mut ScreenContext) {
+        self.refresh(ctx);
+    }
+}
@@ -1,2 +1,13 @@
 // src/ui/mod.rs
-pub mod state;
\ No newline at end of file
+pub mod screen;
+pub mod navigator;
+pub mod global_renderer;
+pub mod list;
+pub mod welcome;
+pub mod main_menu;
+pub mod calibration;
+pub mod settings;
+pub mod profiles;
+pub mod running;
+pub mod open_with;
+pub mod files;
@@ -0,0 +1,171 @@
+// src/ui/navigator.rs
+use crate::input::InputEvent;
+use crate::ui::global_renderer::GlobalRenderer;
+use crate::ui::screen::{Screen, ScreenContext, Transition};
+use std::time::Duration;
+
+/// A stack of screens, the top one showing. The first screen is never
+/// popped, so Back and Home always have somewhere to land.
+pub struct Navigator {
+    stack: Vec<Box<dyn Screen>>,
+}
+
+impl Navigator {
+    pub fn new(mut root: Box<dyn Screen>, ctx: &mut ScreenContext) -> Self {
+        root.on_enter(ctx);
+        Self { stack: vec![root] }
+    }
+
+    pub fn push(&mut self, mut screen: Box<dyn Screen>, ctx: &mut ScreenContext) {
+        self.top_mut().on_exit(ctx);
+        screen.on_enter(ctx);
+        self.stack.push(screen);
+    }
+
+    /// Returns `false`, doing nothing, on the first screen.
+    pub fn pop(&mut self, ctx: &mut ScreenContext) -> bool {
+        if self.stack.len() <= 1 {
+            return false;
+        }
+        if let Some(mut screen) = self.stack.pop() {
+            screen.on_exit(ctx);
+        }
+        self.top_mut().on_enter(ctx);
+        true
+    }
+
+    pub fn replace(&mut self, mut screen: Box<dyn Screen>, ctx: &mut ScreenContext) {
+        self.top_mut().on_exit(ctx);
+        screen.on_enter(ctx);
+        *self.top_mut() = screen;
+    }
+
+    /// Pops back to the first screen.
+    pub fn home(&mut self, ctx: &mut ScreenContext) {
+        if self.stack.len() <= 1 {
+            return;
+        }
+        while self.stack.len() > 1 {
+            if let Some(mut screen) = self.stack.pop() {
+                screen.on_exit(ctx);
+            }
+        }
+        self.top_mut().on_enter(ctx);
+    }
+
+    /// Gives `event` to the top screen and applies its transition. Home is
+    /// handled here, the same on every screen.
+    pub fn handle(&mut self, event: &InputEvent, ctx: &mut ScreenContext) {
+        if let InputEvent::Home = event {
+            self.home(ctx);
+            return;
+        }
+        match self.top_mut().handle_input(event, ctx) {
+            Transition::Unhandled => {
+                if let InputEvent::Back = event {
+                    self.pop(ctx);
+                }
+            }
+            transition => self.apply(transition, ctx),
+        }
+    }
+
+    /// Carries out a transition that did not come from input, such as one
+    /// from `open_with::open`.
+    pub fn apply(&mut self, transition: Transition, ctx: &mut ScreenContext) {
+        match transition {
+            Transition::Stay | Transition::Unhandled => {}
+            Transition::Push(screen) => self.push(screen, ctx),
+            Transition::Pop => {
+                self.pop(ctx);
+            }
+            Transition::Replace(screen) => self.replace(screen, ctx),
+            Transition::Home => self.home(ctx),
+        }
+    }
+
+    pub fn update(&mut self, ctx: &mut ScreenContext, elapsed: Duration) {
+        self.top_mut().update(ctx, elapsed);
+    }
+
+    pub fn render(&self, renderer: &mut GlobalRenderer) {
+        renderer.render(self.top());
+    }
+
+    fn top(&self) -> &dyn Screen {
+        self.stack
+            .last()
+            .expect("the first screen is never popped")
+            .as_ref()
+    }
+
+    fn top_mut(&mut self) -> &mut Box<dyn Screen> {
+        self.stack
+            .last_mut()
+            .expect("the first screen is never popped")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::Cli;
+    use crate::input::bindings::InputBindings;
+    use crate::input::recording::InputRecorder;
+    use crate::input::InputManager;
+    use crate::profiles::ProfileStore;
+    use crate::settings::Settings;
+    use crate::ui::settings::SettingsMenu;
+    use crate::ui::welcome::WelcomeScreen;
+    use clap::Parser;
+    use std::time::Instant;
+
+    #[test]
+    fn a_recording_drives_the_screens_without_sdl() {
+        let dir = std::env::temp_dir().join(format!("kips_ui-navigator-{}", std::process::id()));
+        let recording = dir.join("session.jsonl");
+        let settings = dir.join("settings.json");
+
+        // Into Sound, mute and back out of settings
+        let session = [
+            InputEvent::NavigateDown,
+            InputEvent::Select,
+            InputEvent::NavigateDown,
+            InputEvent::Select,
+            InputEvent::Back,
+            InputEvent::Back,
+        ];
+        let mut recorder = InputRecorder::create(&recording).unwrap();
+        recorder.record(&session, Instant::now()).unwrap();
+        drop(recorder);
+
+        let cli = Cli::parse_from([
+            "kips_ui".as_ref(),
+            "--config".as_ref(),
+            dir.as_os_str(),
+            "--settings".as_ref(),
+            settings.as_os_str(),
+        ]);
+        let mut input = InputManager::from_sources(
+            &InputBindings::default(),
+            Vec::new(),
+            &cli.calibration_path(),
+        );
+        input.start_replay(&recording, f32::INFINITY).unwrap();
+        let profiles = ProfileStore::open(dir.join("profiles"));
+        let mut ctx = ScreenContext::new(cli, profiles, input);
+
+        let mut navigator = Navigator::new(Box::new(WelcomeScreen), &mut ctx);
+        let menu = SettingsMenu::new(&ctx.settings, &ctx.input);
+        navigator.push(Box::new(menu), &mut ctx);
+        for event in ctx.input.poll_inputs() {
+            navigator.handle(&event, &mut ctx);
+        }
+
+        assert!(!ctx.input.is_replaying());
+        assert_eq!(navigator.stack.len(), 1);
+        assert!(ctx.settings.get().sound.muted);
+        assert!(Settings::load(&settings).unwrap().sound.muted);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,132 @@
+// src/ui/open_with.rs
+use crate::input::InputEvent;
+use crate::registry::AppExec;
+use crate::ui::files::FileBrowser;
+use crate::ui::global_renderer::GlobalRenderer;
+use crate::ui::list::ListState;
+use crate::ui::screen::{Screen, ScreenContext, Transition};
+use crate::ui::settings::{BACK_WIDTH, ROW_HEIGHT, ROW_LEFT, ROW_TOP, ROW_WIDTH, VISIBLE_ROWS};
+use std::path::{Path, PathBuf};
+
+/// Opens `path` in the app associated with its type. When several apps
+/// fit, the returned transition pushes a chooser.
+pub fn open(path: &Path, ctx: &mut ScreenContext) -> Transition {
+    let choices: Vec<OpenWithChoice> = ctx
+        .associations
+        .handlers(&ctx.apps, path)
+        .into_iter()
+        .map(|app| OpenWithChoice {
+            id: app.id.clone(),
+            name: app.name.clone(),
+        })
+        .collect();
+    match choices.as_slice() {
+        [] => {
+            log::info!("No app opens {}", path.display());
+            ctx.notify(format!("No app opens {}", file_name(path)));
+            Transition::Stay
+        }
+        [only] => launch(&only.id, Some(path), ctx),
+        _ => Transition::Push(Box::new(OpenWithScreen::new(path, choices))),
+    }
+}
+
+/// Starts the app with `app_id`, or, for one of the shell's own screens,
+/// returns the transition that shows it.
+pub fn launch(app_id: &str, file: Option<&Path>, ctx: &mut ScreenContext) -> Transition {
+    let Some(AppExec::Screen { name }) = ctx.apps.get(app_id).map(|app| &app.exec) else {
+        ctx.launch(app_id, file);
+        return Transition::Stay;
+    };
+    let screen: Box<dyn Screen> = match name.as_str() {
+        "files" => Box::new(FileBrowser::new(file)),
+        _ => {
+            log::warn!("{}: no screen called `{}`", app_id, name);
+            return Transition::Stay;
+        }
+    };
+    ctx.profiles.record_launch(app_id);
+    Transition::Push(screen)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpenWithChoice {
+    pub id: String,
+    pub name: String,
+}
+
+/// Asks which of several apps should open a file. The first choice is the
+/// best match; Select or a tap opens the file and closes the chooser.
+#[derive(Debug, Clone)]
+pub struct OpenWithScreen {
+    pub file: PathBuf,
+    pub file_name: String,
+    pub choices: Vec<OpenWithChoice>,
+    pub list: ListState,
+}
+
+impl OpenWithScreen {
+    pub fn new(file: &Path, choices: Vec<OpenWithChoice>) -> Self {
+        Self {
+            file: file.to_path_buf(),
+            file_name: file_name(file),
+            choices,
+            list: ListState::new(VISIBLE_ROWS),
+        }
+    }
+
+    fn move_selection(&mut self, delta: i32) {
+        self.list.move_by(delta, self.choices.len());
+    }
+
+    fn open(&self, ctx: &mut ScreenContext) -> Transition {
+        match self.choices.get(self.list.selected) {
+            // The chooser makes way for a screen rather than lingering
+            // under it
+            Some(choice) => match launch(&choice.id, Some(&self.file), ctx) {
+                Transition::Push(screen) => Transition::Replace(screen),
+                _ => Transition::Pop,
+            },
+            None => Transition::Stay,
+        }
+    }
+}
+
+impl Screen for OpenWithScreen {
+    fn handle_input(&mut self, event: &InputEvent, ctx: &mut ScreenContext) -> Transition {
+        match *event {
+            InputEvent::NavigateUp => self.move_selection(-1),
+            InputEvent::NavigateDown => self.move_selection(1),
+            InputEvent::EncoderTurned { delta, .. } => self.move_selection(delta as i32),
+            InputEvent::Select | InputEvent::Activate | InputEvent::EncoderPressed(_) => {
+                return self.open(ctx);
+            }
+            InputEvent::Touch(x, y) if y < ROW_TOP - 10 && x < BACK_WIDTH => {
+                return Transition::Pop;
+            }
+            InputEvent::Touch(x, y) => {
+                if !(ROW_LEFT..ROW_LEFT + ROW_WIDTH as i32).contains(&x) {
+                    return Transition::Stay;
+                }
+                let len = self.choices.len();
+                if let Some(index) = self.list.row_at(y, ROW_TOP, ROW_HEIGHT, len) {
+                    self.list.selected = index;
+                    return self.open(ctx);
+                }
+            }
+            _ => return Transition::Unhandled,
+        }
+        Transition::Stay
+    }
+
+    fn render(&self, renderer: &mut GlobalRenderer) {
+        renderer.render_open_with(self);
+    }
+}
@@ -0,0 +1,374 @@
+// src/ui/profiles.rs
+use crate::input::InputEvent;
+use crate::profiles::{self, ProfileStore, MAX_NAME_LEN};
+use crate::ui::global_renderer::GlobalRenderer;
+use crate::ui::list::{ListState, NameEditor};
+use crate::ui::screen::{Screen, ScreenContext, Transition};
+use crate::ui::settings::{BACK_WIDTH, ROW_HEIGHT, ROW_TOP, VISIBLE_ROWS};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProfilePage {
+    List,
+    Profile(u32),
+    Avatar(u32),
+    /// Entering a name; `id` is `None` for a new profile.
+    Name {
+        id: Option<u32>,
+        name: NameEditor,
+    },
+    ConfirmDelete(u32),
+}
+
+/// What a row does when selected.
+#[derive(Debug, Clone, PartialEq)]
+enum RowAction {
+    Open(u32),
+    New,
+    Use(u32),
+    Rename(u32),
+    ChooseAvatar(u32),
+    Delete(u32),
+    ConfirmDelete(u32),
+    SetAvatar(u32, Option<PathBuf>),
+    SaveName,
+    Cancel,
+    None,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProfileRow {
+    pub label: String,
+    /// Shown right-aligned and dimmed.
+    pub detail: String,
+    pub avatar: Option<PathBuf>,
+    action: RowAction,
+}
+
+impl ProfileRow {
+    fn new(action: RowAction, label: impl Into<String>) -> Self {
+        Self {
+            label: label.into(),
+            detail: String::new(),
+            avatar: None,
+            action,
+        }
+    }
+
+    fn detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = detail.into();
+        self
+    }
+
+    fn avatar(mut self, avatar: Option<PathBuf>) -> Self {
+        self.avatar = avatar;
+        self
+    }
+}
+
+/// The profile screen: a list of profiles, each with a page to use, rename,
+/// re-avatar or delete it.
+#[derive(Debug, Clone)]
+pub struct ProfileMenu {
+    pub page: ProfilePage,
+    pub title: String,
+    pub rows: Vec<ProfileRow>,
+    pub list: ListState,
+    /// The last error, shown under the title until the next action.
+    pub message: Option<String>,
+    assets: PathBuf,
+    /// Looked up when the avatar page opens
+    avatars: Vec<PathBuf>,
+}
+
+impl ProfileMenu {
+    pub fn new(profiles: &ProfileStore, assets: &Path) -> Self {
+        let mut menu = Self {
+            page: ProfilePage::List,
+            title: String::new(),
+            rows: Vec::new(),
+            list: ListState::new(VISIBLE_ROWS),
+            message: None,
+            assets: assets.to_path_buf(),
+            avatars: Vec::new(),
+        };
+        menu.refresh(profiles);
+        menu
+    }
+
+    pub fn handle(&mut self, event: &InputEvent, profiles: &mut ProfileStore) -> Transition {
+        let transition = if matches!(self.page, ProfilePage::Name { .. }) {
+            self.handle_name(event, profiles)
+        } else {
+            match *event {
+                InputEvent::NavigateUp => self.move_selection(-1),
+                InputEvent::NavigateDown => self.move_selection(1),
+                InputEvent::EncoderTurned { delta, .. } => self.move_selection(delta as i32),
+                InputEvent::Select | InputEvent::Activate | InputEvent::EncoderPressed(_) => {
+                    self.activate(profiles)
+                }
+                InputEvent::Back => self.back(),
+                InputEvent::Touch(x, y) => self.touch(x, y, profiles),
+                _ => Transition::Unhandled,
+            }
+        };
+        self.refresh(profiles);
+        transition
+    }
+
+    fn handle_name(&mut self, event: &InputEvent, profiles: &mut ProfileStore) -> Transition {
+        let ProfilePage::Name { name, .. } = &mut self.page else {
+            return Transition::Stay;
+        };
+        if name.handle(event) {
+            return Transition::Stay;
+        }
+        match *event {
+            InputEvent::Select | InputEvent::EncoderPressed(_) => self.save_name(profiles),
+            InputEvent::Back => return self.back(),
+            InputEvent::Touch(x, y) => return self.touch(x, y, profiles),
+            _ => {}
+        }
+        Transition::Stay
+    }
+
+    fn move_selection(&mut self, delta: i32) -> Transition {
+        self.list.move_by(delta, self.rows.len());
+        Transition::Stay
+    }
+
+    fn activate(&mut self, profiles: &mut ProfileStore) -> Transition {
+        let Some(row) = self.rows.get(self.list.selected) else {
+            return Transition::Stay;
+        };
+        self.message = None;
+        let result = match row.action.clone() {
+            RowAction::Open(id) => {
+                self.open(ProfilePage::Profile(id));
+                Ok(())
+            }
+            RowAction::New => {
+                self.open(ProfilePage::Name {
+                    id: None,
+                    name: NameEditor::new("", MAX_NAME_LEN),
+                });
+                Ok(())
+            }
+            RowAction::Use(id) => profiles.switch(id).map(|_| self.open(ProfilePage::List)),
+            RowAction::Rename(id) => {
+                let name = profiles.get(id).map_or("", |p| p.name.as_str());
+                let name = NameEditor::new(name, MAX_NAME_LEN);
+                self.open(ProfilePage::Name { id: Some(id), name });
+                Ok(())
+            }
+            RowAction::ChooseAvatar(id) => {
+                self.avatars = profiles::avatar_candidates(&self.assets);
+                self.open(ProfilePage::Avatar(id));
+                Ok(())
+            }
+            RowAction::Delete(id) => {
+                self.open(ProfilePage::ConfirmDelete(id));
+                Ok(())
+            }
+            RowAction::ConfirmDelete(id) => {
+                profiles.delete(id).map(|_| self.open(ProfilePage::List))
+            }
+            RowAction::SetAvatar(id, avatar) => profiles
+                .set_avatar(id, avatar.as_deref())
+                .map(|_| self.open(ProfilePage::Profile(id))),
+            RowAction::SaveName => {
+                self.save_name(profiles);
+                Ok(())
+            }
+            RowAction::Cancel => return self.back(),
+            RowAction::None => Ok(()),
+        };
+        if let Err(e) = result {
+            self.fail(e);
+        }
+        Transition::Stay
+    }
+
+    fn save_name(&mut self, profiles: &mut ProfileStore) {
+        let ProfilePage::Name { id, name } = &self.page else {
+            return;
+        };
+        let result = match *id {
+            Some(id) => profiles.rename(id, &name.text).map(|_| id),
+            None => profiles.create(&name.text),
+        };
+        match result {
+            Ok(id) => self.open(ProfilePage::Profile(id)),
+            Err(e) => self.fail(e),
+        }
+    }
+
+    fn fail(&mut self, e: anyhow::Error) {
+        log::warn!("Profile change failed: {:#}", e);
+        self.message = Some(format!("{:#}", e));
+    }
+
+    fn back(&mut self) -> Transition {
+        self.message = None;
+        let parent = match &self.page {
+            ProfilePage::List => return Transition::Pop,
+            ProfilePage::Profile(_) | ProfilePage::Name { id: None, .. } => ProfilePage::List,
+            ProfilePage::Avatar(id)
+            | ProfilePage::Name { id: Some(id), .. }
+            | ProfilePage::ConfirmDelete(id) => ProfilePage::Profile(*id),
+        };
+        self.open(parent);
+        Transition::Stay
+    }
+
+    fn open(&mut self, page: ProfilePage) {
+        self.page = page;
+        self.list.reset();
+    }
+
+    fn touch(&mut self, x: i32, y: i32, profiles: &mut ProfileStore) -> Transition {
+        if y < ROW_TOP {
+            return if x < BACK_WIDTH {
+                self.back()
+            } else {
+                Transition::Stay
+            };
+        }
+        let Some(index) = self.list.row_at(y, ROW_TOP, ROW_HEIGHT, self.rows.len()) else {
+            return Transition::Stay;
+        };
+        self.list.selected = index;
+        self.activate(profiles)
+    }
+
+    /// Rebuilds the rows from the store and keeps the selection visible.
+    /// A page whose profile has gone falls back to the list.
+    pub fn refresh(&mut self, profiles: &ProfileStore) {
+        let id = match &self.page {
+            ProfilePage::Profile(id) | ProfilePage::Avatar(id) | ProfilePage::ConfirmDelete(id) => {
+                Some(*id)
+            }
+            ProfilePage::Name { id, .. } => *id,
+            ProfilePage::List => None,
+        };
+        if id.is_some_and(|id| profiles.get(id).is_none()) {
+            self.open(ProfilePage::List);
+        }
+
+        self.title = match &self.page {
+            ProfilePage::List => "PROFILES".to_string(),
+            ProfilePage::Profile(id) => profiles
+                .get(*id)
+                .map_or_else(String::new, |p| p.name.to_uppercase()),
+            ProfilePage::Avatar(_) => "AVATAR".to_string(),
+            ProfilePage::Name { id: None, .. } => "NEW PROFILE".to_string(),
+            ProfilePage::Name { id: Some(_), .. } => "RENAME".to_string(),
+            ProfilePage::ConfirmDelete(_) => "DELETE PROFILE".to_string(),
+        };
+        self.rows = match &self.page {
+            ProfilePage::List => list_rows(profiles),
+            ProfilePage::Profile(id) => profile_rows(profiles, *id),
+            ProfilePage::Avatar(id) => avatar_rows(*id, &self.avatars),
+            ProfilePage::Name { name, .. } => vec![
+                ProfileRow::new(RowAction::SaveName, format!("{}_", name.text)),
+                ProfileRow::new(RowAction::SaveName, "Save"),
+                ProfileRow::new(RowAction::Cancel, "Cancel"),
+            ],
+            ProfilePage::ConfirmDelete(id) => {
+                let name = profiles.get(*id).map_or("", |p| p.name.as_str());
+                vec![
+                    ProfileRow::new(RowAction::Cancel, "Keep it"),
+                    ProfileRow::new(RowAction::ConfirmDelete(*id), format!("Delete {}", name))
+                        .detail("Settings and favourites go too"),
+                ]
+            }
+        };
+
+        self.list.clamp(self.rows.len());
+    }
+}
+
+impl Screen for ProfileMenu {
+    fn handle_input(&mut self, event: &InputEvent, ctx: &mut ScreenContext) -> Transition {
+        let before = ctx.profiles.active().id;
+        let transition = self.handle(event, &mut ctx.profiles);
+        // A different profile brings its own settings
+        if ctx.profiles.active().id != before {
+            ctx.reload_settings();
+        }
+        transition
+    }
+
+    fn render(&self, renderer: &mut GlobalRenderer) {
+        renderer.render_profiles(self);
+    }
+}
+
+fn list_rows(profiles: &ProfileStore) -> Vec<ProfileRow> {
+    let active = profiles.active().id;
+    let mut rows: Vec<ProfileRow> = profiles
+        .profiles()
+        .iter()
+        .map(|profile| {
+            ProfileRow::new(RowAction::Open(profile.id), profile.name.clone())
+                .detail(if profile.id == active { "In use" } else { "" })
+                .avatar(profile.avatar.clone())
+        })
+        .collect();
+    rows.push(ProfileRow::new(RowAction::New, "New profile"));
+    rows
+}
+
+fn profile_rows(profiles: &ProfileStore, id: u32) -> Vec<ProfileRow> {
+    let Some(profile) = profiles.get(id) else {
+        return Vec::new();
+    };
+    let mut rows = Vec::new();
+    if profiles.active().id != id {
+        rows.push(ProfileRow::new(RowAction::Use(id), "Use this profile"));
+    }
+    rows.push(ProfileRow::new(RowAction::Rename(id), "Rename"));
+    rows.push(
+        ProfileRow::new(RowAction::ChooseAvatar(id), "Avatar").avatar(profile.avatar.clone()),
+    );
+    rows.push(
+        ProfileRow::new(RowAction::None, "Favourites")
+            .detail(summary(&profile.favourites, "None yet")),
+    );
+    rows.push(
+        ProfileRow::new(RowAction::None, "Recent").detail(summary(&profile.recents, "Nothing yet")),
+    );
+    if profiles.profiles().len() > 1 {
+        rows.push(ProfileRow::new(RowAction::Delete(id), "Delete"));
+    }
+    rows
+}
+
+fn avatar_rows(id: u32, avatars: &[PathBuf]) -> Vec<ProfileRow> {
+    let mut rows = vec![ProfileRow::new(RowAction::SetAvatar(id, None), "No avatar")];
+    rows.extend(avatars.iter().map(|path| {
+        let name = path
+            .file_name()
+            .map_or_else(String::new, |n| n.to_string_lossy().into_owned());
+        let from = path
+            .parent()
+            .map_or_else(String::new, |dir| dir.display().to_string());
+        ProfileRow::new(RowAction::SetAvatar(id, Some(path.clone())), name)
+            .detail(from)
+            .avatar(Some(path.clone()))
+    }));
+    rows
+}
+
+/// The first few of `apps`, or `empty` when there are none.
+fn summary(apps: &[String], empty: &str) -> String {
+    const SHOWN: usize = 3;
+    if apps.is_empty() {
+        return empty.to_string();
+    }
+    let mut text = apps[..apps.len().min(SHOWN)].join(", ");
+    if apps.len() > SHOWN {
+        text.push_str(&format!(" +{}", apps.len() - SHOWN));
+    }
+    text
+}
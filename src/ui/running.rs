@@ -0,0 +1,195 @@
+// src/ui/running.rs
+use crate::input::InputEvent;
+use crate::ui::global_renderer::GlobalRenderer;
+use crate::ui::list::ListState;
+use crate::ui::screen::{Screen, ScreenContext, Transition};
+use crate::ui::settings::{BACK_WIDTH, ROW_HEIGHT, ROW_LEFT, ROW_TOP, ROW_WIDTH};
+use std::time::Duration;
+
+/// Rows shown at once; the stderr of the selected app goes below them.
+pub const VISIBLE_APPS: usize = 4;
+/// Where the STOP button starts on a running app's row.
+pub const STOP_LEFT: i32 = ROW_LEFT + ROW_WIDTH as i32 - 100;
+/// Lines of stderr shown for the selected app.
+pub const STDERR_SHOWN: usize = 3;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunningRow {
+    /// `None` once the app has exited.
+    pub pid: Option<u32>,
+    pub label: String,
+    pub detail: String,
+    pub stderr: Vec<String>,
+}
+
+/// The apps launched from the shell: those still running, then those that
+/// exited recently with their exit status. Select brings a running app's
+/// window forward; Menu, or tapping STOP, stops it after a second press to
+/// confirm.
+#[derive(Debug, Clone)]
+pub struct RunningAppsScreen {
+    pub rows: Vec<RunningRow>,
+    pub list: ListState,
+    /// The app a second stop press will end.
+    pub confirm_stop: Option<u32>,
+    pub message: Option<String>,
+}
+
+impl Default for RunningAppsScreen {
+    fn default() -> Self {
+        Self {
+            rows: Vec::new(),
+            list: ListState::new(VISIBLE_APPS),
+            confirm_stop: None,
+            message: None,
+        }
+    }
+}
+
+impl RunningAppsScreen {
+    fn refresh(&mut self, ctx: &ScreenContext) {
+        let running = ctx.supervisor.running().iter().map(|app| RunningRow {
+            pid: Some(app.pid),
+            label: app.name.clone(),
+            detail: format_duration(app.started.elapsed()),
+            stderr: app.stderr_tail(),
+        });
+        let exited = ctx.supervisor.exited().map(|app| RunningRow {
+            pid: None,
+            label: app.name.clone(),
+            detail: app.describe(),
+            stderr: app.stderr.clone(),
+        });
+        self.rows = running.chain(exited).collect();
+        self.list.clamp(self.rows.len());
+    }
+
+    fn move_selection(&mut self, delta: i32) {
+        self.list.move_by(delta, self.rows.len());
+    }
+
+    fn focus(&mut self, ctx: &mut ScreenContext) {
+        let Some(row) = self.rows.get(self.list.selected) else {
+            return;
+        };
+        let Some(pid) = row.pid else {
+            self.message = Some(format!("{} is not running", row.label));
+            return;
+        };
+        if let Err(e) = ctx.supervisor.focus(pid) {
+            log::warn!("Cannot focus {}: {:#}", row.label, e);
+            self.message = Some(format!("Cannot bring {} forward", row.label));
+        }
+    }
+
+    /// Stops the selected app if `confirmed` is its pid; otherwise asks
+    /// first.
+    fn stop(&mut self, confirmed: Option<u32>, ctx: &mut ScreenContext) {
+        let Some(row) = self.rows.get(self.list.selected) else {
+            return;
+        };
+        let Some(pid) = row.pid else {
+            return;
+        };
+        if confirmed != Some(pid) {
+            self.confirm_stop = Some(pid);
+            self.message = Some(format!("Stop {}? Press again to confirm", row.label));
+            return;
+        }
+        match ctx.supervisor.kill(pid) {
+            Ok(()) => self.message = Some(format!("Stopped {}", row.label)),
+            Err(e) => {
+                log::error!("Failed to stop {}: {:#}", row.label, e);
+                self.message = Some(format!("Could not stop {}", row.label));
+            }
+        }
+    }
+
+    fn touch(
+        &mut self,
+        x: i32,
+        y: i32,
+        confirmed: Option<u32>,
+        ctx: &mut ScreenContext,
+    ) -> Transition {
+        if y < ROW_TOP - 10 {
+            return if x < BACK_WIDTH {
+                Transition::Pop
+            } else {
+                Transition::Stay
+            };
+        }
+        if !(ROW_LEFT..ROW_LEFT + ROW_WIDTH as i32).contains(&x) {
+            return Transition::Stay;
+        }
+        let Some(index) = self.list.row_at(y, ROW_TOP, ROW_HEIGHT, self.rows.len()) else {
+            return Transition::Stay;
+        };
+        let was_selected = index == self.list.selected;
+        self.list.selected = index;
+        if x >= STOP_LEFT && self.rows[index].pid.is_some() {
+            self.stop(confirmed, ctx);
+        } else if was_selected {
+            self.focus(ctx);
+        }
+        Transition::Stay
+    }
+}
+
+impl Screen for RunningAppsScreen {
+    fn handle_input(&mut self, event: &InputEvent, ctx: &mut ScreenContext) -> Transition {
+        // A stop is only confirmed by the very next press
+        let confirmed = self.confirm_stop.take();
+        self.message = None;
+        let transition = match *event {
+            InputEvent::NavigateUp => {
+                self.move_selection(-1);
+                Transition::Stay
+            }
+            InputEvent::NavigateDown => {
+                self.move_selection(1);
+                Transition::Stay
+            }
+            InputEvent::EncoderTurned { delta, .. } => {
+                self.move_selection(delta as i32);
+                Transition::Stay
+            }
+            InputEvent::Select | InputEvent::Activate | InputEvent::EncoderPressed(_) => {
+                self.focus(ctx);
+                Transition::Stay
+            }
+            InputEvent::Menu => {
+                self.stop(confirmed, ctx);
+                Transition::Stay
+            }
+            // Back cancels a pending stop before it leaves the screen
+            InputEvent::Back if confirmed.is_some() => Transition::Stay,
+            InputEvent::Touch(x, y) => self.touch(x, y, confirmed, ctx),
+            _ => Transition::Unhandled,
+        };
+        self.refresh(ctx);
+        transition
+    }
+
+    fn update(&mut self, ctx: &mut ScreenContext, _elapsed: Duration) {
+        self.refresh(ctx);
+    }
+
+    fn render(&self, renderer: &mut GlobalRenderer) {
+        renderer.render_running_apps(self);
+    }
+
+    fn on_enter(&mut self, ctx: &mut ScreenContext) {
+        self.refresh(ctx);
+    }
+}
+
+/// "45s", "12m", "3h".
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m", secs / 60),
+        _ => format!("{}h", secs / 3600),
+    }
+}
@@ -0,0 +1,113 @@
+// src/ui/screen.rs
+use crate::associations::Associations;
+use crate::cli::Cli;
+use crate::input::{InputEvent, InputManager};
+use crate::profiles::ProfileStore;
+use crate::registry::AppRegistry;
+use crate::settings::SettingsStore;
+use crate::supervisor::{Launch, Supervisor};
+use crate::ui::global_renderer::GlobalRenderer;
+use std::path::Path;
+use std::time::Duration;
+
+/// What the navigator should do after a screen handles an event.
+pub enum Transition {
+    /// The event was used; stay on this screen.
+    Stay,
+    /// The event means nothing here. An unhandled Back pops the screen, so
+    /// only screens with pages of their own need to handle it.
+    Unhandled,
+    Push(Box<dyn Screen>),
+    Pop,
+    /// Swap this screen for another without growing the stack.
+    Replace(Box<dyn Screen>),
+    /// Pop everything above the first screen.
+    Home,
+}
+
+/// Everything screens may read or change, owned by the main loop and lent
+/// to the screen on top of the stack.
+pub struct ScreenContext {
+    pub cli: Cli,
+    pub settings: SettingsStore,
+    /// Bumped each time `settings` is replaced, so subscribers know to
+    /// subscribe to the new store.
+    pub settings_generation: u32,
+    pub profiles: ProfileStore,
+    pub apps: AppRegistry,
+    pub associations: Associations,
+    pub input: InputManager,
+    pub supervisor: Supervisor,
+    /// Messages for the notification popup, taken by the main loop.
+    pub notices: Vec<String>,
+}
+
+impl ScreenContext {
+    pub fn new(cli: Cli, profiles: ProfileStore, input: InputManager) -> Self {
+        let settings = cli.settings_store(&profiles);
+        let apps = cli.app_registry();
+        log::debug!("Loaded {} apps", apps.apps().len());
+        let associations = cli.associations();
+        Self {
+            cli,
+            settings,
+            settings_generation: 0,
+            profiles,
+            apps,
+            associations,
+            input,
+            supervisor: Supervisor::default(),
+            notices: Vec::new(),
+        }
+    }
+
+    pub fn notify(&mut self, message: impl Into<String>) {
+        self.notices.push(message.into());
+    }
+
+    /// Starts the app with `app_id`, opening `file` if given, and counts it
+    /// among the profile's recents. Failures are shown in the popup.
+    pub fn launch(&mut self, app_id: &str, file: Option<&Path>) {
+        let Some(app) = self.apps.get(app_id) else {
+            log::warn!("No app called `{}`", app_id);
+            return;
+        };
+        self.profiles.record_launch(&app.id);
+        let output = self.settings.get().sound.output.as_deref();
+        let result = self.supervisor.launch(app, file, output);
+        let name = app.name.clone();
+        match result {
+            Ok(Launch::Started(_)) => {}
+            Ok(Launch::AlreadyRunning(_)) => self.notify(format!("{} is already running", name)),
+            Err(e) => {
+                log::error!("Failed to launch {}: {:#}", name, e);
+                self.notify(format!("Could not start {}", name));
+            }
+        }
+    }
+
+    /// Opens the active profile's settings after a profile switch.
+    pub fn reload_settings(&mut self) {
+        self.settings = self.cli.settings_store(&self.profiles);
+        self.settings_generation += 1;
+        log::info!("Switched to profile {}", self.profiles.active().name);
+    }
+}
+
+/// One screen of the shell. The navigator only ever talks to the screen on
+/// top of its stack.
+pub trait Screen {
+    fn handle_input(&mut self, event: &InputEvent, ctx: &mut ScreenContext) -> Transition;
+
+    /// Called once a frame with the time since the last one.
+    fn update(&mut self, _ctx: &mut ScreenContext, _elapsed: Duration) {}
+
+    fn render(&self, renderer: &mut GlobalRenderer);
+
+    /// Called when the screen comes to the top: pushed, or uncovered by a pop.
+    fn on_enter(&mut self, _ctx: &mut ScreenContext) {}
+
+    /// Called when the screen stops being the top: popped, replaced or
+    /// covered by a push.
+    fn on_exit(&mut self, _ctx: &mut ScreenContext) {}
+}
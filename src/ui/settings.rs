@@ -0,0 +1,550 @@
+// src/ui/settings.rs
+use crate::input::calibration::Rotation;
+use crate::input::{InputEvent, InputManager};
+use crate::settings::{Settings, SettingsStore, Theme};
+use crate::ui::calibration::CalibrationScreen;
+use crate::ui::global_renderer::GlobalRenderer;
+use crate::ui::list::ListState;
+use crate::ui::screen::{Screen, ScreenContext, Transition};
+use cpal::traits::{DeviceTrait, HostTrait};
+use sysinfo::{DiskExt, System, SystemExt};
+
+// Layout shared with `GlobalRenderer::render_settings` for touch hit-testing
+pub const ROW_TOP: i32 = 80;
+pub const ROW_HEIGHT: i32 = 60;
+pub const VISIBLE_ROWS: usize = 6;
+pub const ROW_LEFT: i32 = 40;
+pub const ROW_WIDTH: u32 = 720;
+/// Slider track, in screen x
+pub const SLIDER_LEFT: i32 = 440;
+pub const SLIDER_WIDTH: u32 = 260;
+/// The "< BACK" label in the title bar
+pub const BACK_WIDTH: i32 = 140;
+
+const SCREEN_TIMEOUTS: [(u32, &str); 5] = [
+    (0, "Never"),
+    (30, "30 s"),
+    (60, "1 min"),
+    (300, "5 min"),
+    (900, "15 min"),
+];
+const THEMES: [(Theme, &str); 4] = [
+    (Theme::Green, "Green"),
+    (Theme::Amber, "Amber"),
+    (Theme::Blue, "Blue"),
+    (Theme::White, "White"),
+];
+const ROTATIONS: [Rotation; 4] = [
+    Rotation::Deg0,
+    Rotation::Deg90,
+    Rotation::Deg180,
+    Rotation::Deg270,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingsPage {
+    Root,
+    Display,
+    Sound,
+    Input,
+    Storage,
+}
+
+impl SettingsPage {
+    pub fn title(self) -> &'static str {
+        match self {
+            SettingsPage::Root => "SETTINGS",
+            SettingsPage::Display => "DISPLAY",
+            SettingsPage::Sound => "SOUND",
+            SettingsPage::Input => "INPUT",
+            SettingsPage::Storage => "STORAGE",
+        }
+    }
+}
+
+/// What a row does when selected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RowId {
+    Page(SettingsPage),
+    Brightness,
+    Theme,
+    ScreenTimeout,
+    Volume,
+    Mute,
+    Output,
+    TouchRotation,
+    Calibrate,
+    UsbPopup,
+    Info,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RowKind {
+    Page,
+    Slider {
+        value: u8,
+        max: u8,
+        step: u8,
+    },
+    Toggle(bool),
+    Picker {
+        options: Vec<String>,
+        selected: usize,
+    },
+    /// Runs a command, like touch calibration.
+    Button,
+    Info(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SettingsRow {
+    pub label: String,
+    pub kind: RowKind,
+    id: RowId,
+}
+
+impl SettingsRow {
+    fn new(id: RowId, label: impl Into<String>, kind: RowKind) -> Self {
+        Self {
+            label: label.into(),
+            kind,
+            id,
+        }
+    }
+}
+
+/// The settings screen: nested pages of sliders, toggles and pickers that
+/// write straight to the `SettingsStore`. Up/down (or turning an encoder)
+/// moves between rows; Select opens a page, flips a toggle or starts editing
+/// a slider or picker, which left/right or the encoder then changes. Rows
+/// can also be tapped, and sliders dragged.
+#[derive(Debug, Clone)]
+pub struct SettingsMenu {
+    pub page: SettingsPage,
+    pub rows: Vec<SettingsRow>,
+    pub list: ListState,
+    pub editing: bool,
+    /// Looked up when the Sound page opens
+    outputs: Vec<String>,
+    /// Looked up when the Storage page opens
+    disks: Vec<(String, String)>,
+}
+
+impl SettingsMenu {
+    pub fn new(settings: &SettingsStore, input: &InputManager) -> Self {
+        let mut menu = Self {
+            page: SettingsPage::Root,
+            rows: Vec::new(),
+            list: ListState::new(VISIBLE_ROWS),
+            editing: false,
+            outputs: Vec::new(),
+            disks: Vec::new(),
+        };
+        menu.refresh(settings.get(), input);
+        menu
+    }
+
+    pub fn handle(
+        &mut self,
+        event: &InputEvent,
+        settings: &mut SettingsStore,
+        input: &mut InputManager,
+    ) -> Transition {
+        let transition = match *event {
+            InputEvent::NavigateUp if !self.editing => self.move_selection(-1),
+            InputEvent::NavigateDown if !self.editing => self.move_selection(1),
+            InputEvent::NavigateUp | InputEvent::NavigateRight if self.editing => {
+                self.adjust(1, settings, input)
+            }
+            InputEvent::NavigateDown | InputEvent::NavigateLeft if self.editing => {
+                self.adjust(-1, settings, input)
+            }
+            InputEvent::EncoderTurned { delta, .. } if self.editing => {
+                self.adjust(delta as i32, settings, input)
+            }
+            InputEvent::EncoderTurned { delta, .. } => self.move_selection(delta as i32),
+            InputEvent::Select | InputEvent::Activate | InputEvent::EncoderPressed(_) => {
+                self.activate(settings, input)
+            }
+            InputEvent::Back => self.back(),
+            InputEvent::Touch(x, y) => self.touch(x, y, settings, input),
+            InputEvent::Drag { x, y, .. } => {
+                if let Some(index) = self.row_at(y) {
+                    if matches!(self.rows[index].kind, RowKind::Slider { .. }) {
+                        self.list.selected = index;
+                        self.set_slider_from_x(x, settings, input);
+                    }
+                }
+                Transition::Stay
+            }
+            _ => Transition::Unhandled,
+        };
+        self.refresh(settings.get(), input);
+        transition
+    }
+
+    fn move_selection(&mut self, delta: i32) -> Transition {
+        self.list.move_by(delta, self.rows.len());
+        Transition::Stay
+    }
+
+    fn activate(&mut self, settings: &mut SettingsStore, input: &mut InputManager) -> Transition {
+        let Some(row) = self.rows.get(self.list.selected) else {
+            return Transition::Stay;
+        };
+        match (&row.kind, row.id) {
+            (_, RowId::Page(page)) => self.open(page),
+            (_, RowId::Calibrate) => return Transition::Push(Box::new(CalibrationScreen::new())),
+            (&RowKind::Toggle(on), _) => {
+                return self.adjust(if on { -1 } else { 1 }, settings, input)
+            }
+            (RowKind::Slider { .. } | RowKind::Picker { .. }, _) => self.editing = !self.editing,
+            _ => {}
+        }
+        Transition::Stay
+    }
+
+    fn back(&mut self) -> Transition {
+        if self.editing {
+            self.editing = false;
+            return Transition::Stay;
+        }
+        if self.page == SettingsPage::Root {
+            return Transition::Pop;
+        }
+        let previous = self.page;
+        self.page = SettingsPage::Root;
+        // Land back on the row for the page we came from
+        self.list.reset();
+        self.list.selected = ROOT_PAGES
+            .iter()
+            .position(|&(page, _)| page == previous)
+            .unwrap_or(0);
+        Transition::Stay
+    }
+
+    fn open(&mut self, page: SettingsPage) {
+        match page {
+            SettingsPage::Sound => self.outputs = output_devices(),
+            SettingsPage::Storage => self.disks = disk_usage(),
+            _ => {}
+        }
+        self.page = page;
+        self.list.reset();
+        self.editing = false;
+    }
+
+    fn touch(
+        &mut self,
+        x: i32,
+        y: i32,
+        settings: &mut SettingsStore,
+        input: &mut InputManager,
+    ) -> Transition {
+        if y < ROW_TOP {
+            return if x < BACK_WIDTH {
+                self.editing = false;
+                self.back()
+            } else {
+                Transition::Stay
+            };
+        }
+        let Some(index) = self.row_at(y) else {
+            return Transition::Stay;
+        };
+        self.list.selected = index;
+        self.editing = false;
+        match self.rows[index].kind {
+            RowKind::Slider { .. } if x >= SLIDER_LEFT => {
+                self.set_slider_from_x(x, settings, input);
+                Transition::Stay
+            }
+            // Tapping a picker steps through its options
+            RowKind::Picker { .. } => self.adjust(1, settings, input),
+            RowKind::Slider { .. } => Transition::Stay,
+            _ => self.activate(settings, input),
+        }
+    }
+
+    fn row_at(&self, y: i32) -> Option<usize> {
+        self.list.row_at(y, ROW_TOP, ROW_HEIGHT, self.rows.len())
+    }
+
+    fn set_slider_from_x(
+        &mut self,
+        x: i32,
+        settings: &mut SettingsStore,
+        input: &mut InputManager,
+    ) {
+        let Some(RowKind::Slider { value, max, .. }) =
+            self.rows.get(self.list.selected).map(|r| &r.kind)
+        else {
+            return;
+        };
+        let fraction = (x - SLIDER_LEFT) as f32 / SLIDER_WIDTH as f32;
+        let target = (fraction.clamp(0.0, 1.0) * *max as f32).round() as i32;
+        let delta = target - *value as i32;
+        self.change(delta, false, settings, input);
+    }
+
+    /// Steps the selected row `delta` notches: a slider by its step, a picker
+    /// by one option. A toggle turns on for a positive `delta`, off for a
+    /// negative one.
+    fn adjust(
+        &mut self,
+        delta: i32,
+        settings: &mut SettingsStore,
+        input: &mut InputManager,
+    ) -> Transition {
+        self.change(delta, true, settings, input);
+        Transition::Stay
+    }
+
+    fn change(
+        &mut self,
+        delta: i32,
+        in_steps: bool,
+        settings: &mut SettingsStore,
+        input: &mut InputManager,
+    ) {
+        let Some(row) = self.rows.get(self.list.selected) else {
+            return;
+        };
+        if delta == 0 {
+            return;
+        }
+
+        let id = row.id;
+        let result = match &row.kind {
+            RowKind::Slider { value, max, step } => {
+                let delta = if in_steps {
+                    delta * *step as i32
+                } else {
+                    delta
+                };
+                let value = (*value as i32 + delta).clamp(0, *max as i32) as u8;
+                settings.update(|s| match id {
+                    RowId::Brightness => s.display.brightness = value,
+                    RowId::Volume => s.sound.volume = value,
+                    _ => {}
+                })
+            }
+            RowKind::Toggle(_) => {
+                let on = delta > 0;
+                settings.update(|s| match id {
+                    RowId::Mute => s.sound.muted = on,
+                    RowId::UsbPopup => s.storage.usb_popup = on,
+                    _ => {}
+                })
+            }
+            RowKind::Picker { options, selected } => {
+                let count = options.len() as i32;
+                let index = (*selected as i32 + delta).rem_euclid(count) as usize;
+                let outputs = &self.outputs;
+                match id {
+                    RowId::TouchRotation => input.set_touch_rotation(ROTATIONS[index]),
+                    _ => settings.update(|s| match id {
+                        RowId::Theme => s.display.theme = THEMES[index].0,
+                        RowId::ScreenTimeout => {
+                            s.display.screen_timeout_secs = SCREEN_TIMEOUTS[index].0
+                        }
+                        // Option 0 is the system default
+                        RowId::Output => {
+                            s.sound.output = index.checked_sub(1).map(|i| outputs[i].clone())
+                        }
+                        _ => {}
+                    }),
+                }
+            }
+            _ => Ok(()),
+        };
+        if let Err(e) = result {
+            log::warn!("Failed to save setting {}: {:#}", row.label, e);
+        }
+    }
+
+    /// Rebuilds the rows from current values and keeps the selection visible.
+    pub fn refresh(&mut self, settings: &Settings, input: &InputManager) {
+        self.rows = match self.page {
+            SettingsPage::Root => ROOT_PAGES
+                .iter()
+                .map(|&(page, label)| SettingsRow::new(RowId::Page(page), label, RowKind::Page))
+                .collect(),
+            SettingsPage::Display => display_rows(settings),
+            SettingsPage::Sound => sound_rows(settings, &self.outputs),
+            SettingsPage::Input => input_rows(input),
+            SettingsPage::Storage => storage_rows(settings, &self.disks),
+        };
+
+        self.list.clamp(self.rows.len());
+    }
+}
+
+impl Screen for SettingsMenu {
+    fn handle_input(&mut self, event: &InputEvent, ctx: &mut ScreenContext) -> Transition {
+        self.handle(event, &mut ctx.settings, &mut ctx.input)
+    }
+
+    fn render(&self, renderer: &mut GlobalRenderer) {
+        renderer.render_settings(self);
+    }
+
+    /// Picks up changes made elsewhere, like a new touch calibration.
+    fn on_enter(&mut self, ctx: &mut ScreenContext) {
+        self.refresh(ctx.settings.get(), &ctx.input);
+    }
+}
+
+const ROOT_PAGES: [(SettingsPage, &str); 4] = [
+    (SettingsPage::Display, "Display"),
+    (SettingsPage::Sound, "Sound"),
+    (SettingsPage::Input, "Input"),
+    (SettingsPage::Storage, "Storage"),
+];
+
+fn picker<T>(options: &[(T, &str)], selected: usize) -> RowKind {
+    RowKind::Picker {
+        options: options.iter().map(|(_, label)| label.to_string()).collect(),
+        selected,
+    }
+}
+
+fn display_rows(settings: &Settings) -> Vec<SettingsRow> {
+    let display = &settings.display;
+    let theme = THEMES
+        .iter()
+        .position(|&(theme, _)| theme == display.theme)
+        .unwrap_or(0);
+    // Unlisted timeouts (edited by hand) show as the nearest longer one
+    let timeout = SCREEN_TIMEOUTS
+        .iter()
+        .position(|&(secs, _)| secs >= display.screen_timeout_secs)
+        .unwrap_or(SCREEN_TIMEOUTS.len() - 1);
+
+    vec![
+        SettingsRow::new(
+            RowId::Brightness,
+            "Brightness",
+            RowKind::Slider {
+                value: display.brightness,
+                max: 100,
+                step: 5,
+            },
+        ),
+        SettingsRow::new(RowId::Theme, "Theme", picker(&THEMES, theme)),
+        SettingsRow::new(
+            RowId::ScreenTimeout,
+            "Screen off after",
+            picker(&SCREEN_TIMEOUTS, timeout),
+        ),
+    ]
+}
+
+fn sound_rows(settings: &Settings, outputs: &[String]) -> Vec<SettingsRow> {
+    let sound = &settings.sound;
+    let mut options = vec!["System default".to_string()];
+    options.extend(outputs.iter().cloned());
+    let selected = sound
+        .output
+        .as_ref()
+        .and_then(|name| outputs.iter().position(|o| o == name))
+        .map_or(0, |i| i + 1);
+
+    vec![
+        SettingsRow::new(
+            RowId::Volume,
+            "Volume",
+            RowKind::Slider {
+                value: sound.volume,
+                max: 100,
+                step: 5,
+            },
+        ),
+        SettingsRow::new(RowId::Mute, "Mute", RowKind::Toggle(sound.muted)),
+        SettingsRow::new(
+            RowId::Output,
+            "Output",
+            RowKind::Picker { options, selected },
+        ),
+    ]
+}
+
+fn input_rows(input: &InputManager) -> Vec<SettingsRow> {
+    let rotation = input.touchscreen.calibration().rotation;
+    let mut rows = vec![
+        SettingsRow::new(
+            RowId::TouchRotation,
+            "Touch rotation",
+            RowKind::Picker {
+                options: ROTATIONS
+                    .iter()
+                    .map(|&r| format!("{}°", u16::from(r)))
+                    .collect(),
+                selected: ROTATIONS.iter().position(|&r| r == rotation).unwrap_or(0),
+            },
+        ),
+        SettingsRow::new(RowId::Calibrate, "Calibrate touchscreen", RowKind::Button),
+    ];
+    rows.extend(input.device_status().into_iter().map(|device| {
+        SettingsRow::new(
+            RowId::Info,
+            device.name,
+            RowKind::Info(device.health.to_string()),
+        )
+    }));
+    rows
+}
+
+fn storage_rows(settings: &Settings, disks: &[(String, String)]) -> Vec<SettingsRow> {
+    let mut rows = vec![SettingsRow::new(
+        RowId::UsbPopup,
+        "Ask to open USB drives",
+        RowKind::Toggle(settings.storage.usb_popup),
+    )];
+    rows.extend(disks.iter().map(|(mount, usage)| {
+        SettingsRow::new(RowId::Info, mount.clone(), RowKind::Info(usage.clone()))
+    }));
+    rows
+}
+
+fn output_devices() -> Vec<String> {
+    let host = cpal::default_host();
+    match host.output_devices() {
+        Ok(devices) => devices.filter_map(|device| device.name().ok()).collect(),
+        Err(e) => {
+            log::warn!("Cannot list audio outputs: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Mount point and "free of total" for each disk.
+fn disk_usage() -> Vec<(String, String)> {
+    let mut system = System::new();
+    system.refresh_disks_list();
+    system
+        .disks()
+        .iter()
+        .map(|disk| {
+            (
+                disk.mount_point().display().to_string(),
+                format!(
+                    "{} free of {}",
+                    format_bytes(disk.available_space()),
+                    format_bytes(disk.total_space())
+                ),
+            )
+        })
+        .collect()
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
@@ -0,0 +1,50 @@
+// src/ui/welcome.rs
+use crate::input::InputEvent;
+use crate::ui::calibration::CalibrationScreen;
+use crate::ui::global_renderer::GlobalRenderer;
+use crate::ui::main_menu::MainMenuScreen;
+use crate::ui::profiles::ProfileMenu;
+use crate::ui::screen::{Screen, ScreenContext, Transition};
+use crate::ui::settings::SettingsMenu;
+
+/// The first screen: a greeting and the APPS, PROFILE and SETTINGS buttons
+/// along the bottom.
+#[derive(Debug, Clone, Default)]
+pub struct WelcomeScreen;
+
+impl WelcomeScreen {
+    fn open(label: &str, ctx: &ScreenContext) -> Transition {
+        let screen: Box<dyn Screen> = match label {
+            "APPS" => Box::new(MainMenuScreen::new(0)),
+            "PROFILE" => Box::new(ProfileMenu::new(&ctx.profiles, &ctx.cli.assets)),
+            "SETTINGS" => Box::new(SettingsMenu::new(&ctx.settings, &ctx.input)),
+            _ => return Transition::Unhandled,
+        };
+        Transition::Push(screen)
+    }
+}
+
+impl Screen for WelcomeScreen {
+    fn handle_input(&mut self, event: &InputEvent, ctx: &mut ScreenContext) -> Transition {
+        match event {
+            // The buttons split the bottom edge in thirds
+            InputEvent::Touch(x, _) => {
+                let label = match x {
+                    x if *x < 266 => "APPS",
+                    x if *x < 533 => "PROFILE",
+                    _ => "SETTINGS",
+                };
+                Self::open(label, ctx)
+            }
+            InputEvent::ButtonPressed(label) => Self::open(label, ctx),
+            InputEvent::Select => Self::open("APPS", ctx),
+            // Works even when taps land in the wrong place
+            InputEvent::TouchLongPress(..) => Transition::Push(Box::new(CalibrationScreen::new())),
+            _ => Transition::Unhandled,
+        }
+    }
+
+    fn render(&self, renderer: &mut GlobalRenderer) {
+        renderer.render_welcome();
+    }
+}